@@ -0,0 +1,321 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::audit::{self, AuditContext};
+use crate::db::{DbError, Result};
+
+/// Kinds of leave a worker can be on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum AbsenceKind {
+    Vacaciones,
+    LicenciaMedica,
+    PermisoConGoce,
+    PermisoSinGoce,
+    DiaAdministrativo,
+}
+
+impl AbsenceKind {
+    /// Order of the options in the absence picker.
+    pub const ALL: [AbsenceKind; 5] = [
+        AbsenceKind::Vacaciones,
+        AbsenceKind::LicenciaMedica,
+        AbsenceKind::PermisoConGoce,
+        AbsenceKind::PermisoSinGoce,
+        AbsenceKind::DiaAdministrativo,
+    ];
+
+    /// Stable value stored in `absences.kind`.
+    pub fn code(self) -> &'static str {
+        match self {
+            AbsenceKind::Vacaciones => "vacaciones",
+            AbsenceKind::LicenciaMedica => "licencia_medica",
+            AbsenceKind::PermisoConGoce => "permiso_con_goce",
+            AbsenceKind::PermisoSinGoce => "permiso_sin_goce",
+            AbsenceKind::DiaAdministrativo => "dia_administrativo",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AbsenceKind::Vacaciones => "Vacaciones",
+            AbsenceKind::LicenciaMedica => "Licencia médica",
+            AbsenceKind::PermisoConGoce => "Permiso con goce",
+            AbsenceKind::PermisoSinGoce => "Permiso sin goce",
+            AbsenceKind::DiaAdministrativo => "Día administrativo",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<AbsenceKind> {
+        AbsenceKind::ALL.into_iter().find(|k| k.code() == code)
+    }
+
+    pub fn index(self) -> usize {
+        AbsenceKind::ALL
+            .iter()
+            .position(|k| *k == self)
+            .unwrap_or(0)
+    }
+
+    /// Whether the kind is normally paid; the flag can still be changed
+    /// per absence.
+    pub fn paid_by_default(self) -> bool {
+        self != AbsenceKind::PermisoSinGoce
+    }
+}
+
+/// A period of leave from `start_date` through `end_date` (inclusive).
+#[derive(Clone, Debug, Serialize)]
+pub struct Absence {
+    pub id: i64,
+    pub worker_id: i64,
+    pub kind: AbsenceKind,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    /// Paid absences count their scheduled minutes as worked.
+    pub paid: bool,
+    pub note: String,
+}
+
+impl Absence {
+    pub fn covers(&self, date: NaiveDate) -> bool {
+        self.start_date <= date && date <= self.end_date
+    }
+
+    /// The kind's label for report rows, noting the pay when it differs
+    /// from what the kind implies, e.g. "Vacaciones (sin goce)".
+    pub fn describe(&self) -> String {
+        if self.paid == self.kind.paid_by_default() {
+            return self.kind.label().to_string();
+        }
+        format!(
+            "{} ({})",
+            self.kind.label(),
+            if self.paid { "con goce" } else { "sin goce" }
+        )
+    }
+}
+
+const ABSENCE_COLUMNS: &str = "id, worker_id, kind, start_date, end_date, paid, note";
+
+fn absence_from_row(row: &rusqlite::Row) -> rusqlite::Result<Option<Absence>> {
+    let kind: String = row.get(2)?;
+    let start: String = row.get(3)?;
+    let end: String = row.get(4)?;
+    let (Some(kind), Ok(start_date), Ok(end_date)) = (
+        AbsenceKind::from_code(&kind),
+        NaiveDate::parse_from_str(&start, "%Y-%m-%d"),
+        NaiveDate::parse_from_str(&end, "%Y-%m-%d"),
+    ) else {
+        return Ok(None);
+    };
+    Ok(Some(Absence {
+        id: row.get(0)?,
+        worker_id: row.get(1)?,
+        kind,
+        start_date,
+        end_date,
+        paid: row.get(5)?,
+        note: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+    }))
+}
+
+fn query_absences(
+    conn: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<Absence>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map(params, absence_from_row)?;
+    let mut absences = Vec::new();
+    for row in rows {
+        if let Some(absence) = row? {
+            absences.push(absence);
+        }
+    }
+    Ok(absences)
+}
+
+pub fn get_absence(conn: &Connection, id: i64) -> Result<Option<Absence>> {
+    Ok(conn
+        .query_row(
+            &format!("SELECT {} FROM absences WHERE id = ?", ABSENCE_COLUMNS),
+            rusqlite::params![id],
+            absence_from_row,
+        )
+        .optional()?
+        .flatten())
+}
+
+/// The worker's absences, most recent first.
+pub fn worker_absences(conn: &Connection, worker_id: i64) -> Result<Vec<Absence>> {
+    query_absences(
+        conn,
+        &format!(
+            "SELECT {} FROM absences WHERE worker_id = ? ORDER BY start_date DESC",
+            ABSENCE_COLUMNS
+        ),
+        rusqlite::params![worker_id],
+    )
+}
+
+/// The worker's absence on each day from `from` through `to`, keyed by date.
+pub fn absences_by_day(
+    conn: &Connection,
+    worker_id: i64,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<BTreeMap<NaiveDate, Absence>> {
+    let absences = query_absences(
+        conn,
+        &format!(
+            "SELECT {} FROM absences WHERE worker_id = ? AND start_date <= ? AND end_date >= ?",
+            ABSENCE_COLUMNS
+        ),
+        rusqlite::params![worker_id, to.to_string(), from.to_string()],
+    )?;
+    let mut by_day = BTreeMap::new();
+    for absence in absences {
+        let mut day = absence.start_date.max(from);
+        while day <= absence.end_date.min(to) {
+            by_day.insert(day, absence.clone());
+            let Some(next) = day.succ_opt() else {
+                break;
+            };
+            day = next;
+        }
+    }
+    Ok(by_day)
+}
+
+/// Rejects ranges that end before they start or overlap another absence of
+/// the same worker. `exclude_id` is the absence being edited, if any.
+fn validate_range(
+    conn: &Connection,
+    worker_id: i64,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    exclude_id: Option<i64>,
+) -> Result<()> {
+    if end_date < start_date {
+        return Err(DbError::Validation(
+            "la fecha de término es anterior a la de inicio".to_string(),
+        ));
+    }
+    if let Some(other) = worker_absences(conn, worker_id)?.into_iter().find(|other| {
+        Some(other.id) != exclude_id && other.start_date <= end_date && start_date <= other.end_date
+    }) {
+        return Err(DbError::Validation(format!(
+            "se superpone con {} del {} al {}",
+            other.kind.label(),
+            other.start_date,
+            other.end_date
+        )));
+    }
+    Ok(())
+}
+
+/// Records a new absence (`absence.id` is ignored) and returns its id.
+pub fn add_absence(conn: &mut Connection, absence: &Absence, ctx: &AuditContext) -> Result<i64> {
+    let tx = conn.transaction()?;
+    crate::hour_bank::check_open(&tx, absence.start_date)?;
+    validate_range(
+        &tx,
+        absence.worker_id,
+        absence.start_date,
+        absence.end_date,
+        None,
+    )?;
+    tx.execute(
+        "INSERT INTO absences (worker_id, kind, start_date, end_date, paid, note)
+         VALUES (?, ?, ?, ?, ?, ?)",
+        rusqlite::params![
+            absence.worker_id,
+            absence.kind.code(),
+            absence.start_date.to_string(),
+            absence.end_date.to_string(),
+            absence.paid,
+            absence.note.trim()
+        ],
+    )?;
+    let id = tx.last_insert_rowid();
+    let after = get_absence(&tx, id)?;
+    audit::record(
+        &tx,
+        ctx,
+        "absences",
+        Some(id),
+        "create",
+        None,
+        after.as_ref().and_then(audit::snapshot),
+    )?;
+    tx.commit()?;
+    Ok(id)
+}
+
+/// Replaces the type, dates, paid flag and note of an existing absence.
+pub fn update_absence(conn: &mut Connection, absence: &Absence, ctx: &AuditContext) -> Result<()> {
+    let tx = conn.transaction()?;
+    let before = get_absence(&tx, absence.id)?
+        .ok_or_else(|| DbError::Validation(format!("no existe la ausencia {}", absence.id)))?;
+    // The note can still be edited once the month is closed.
+    let same_days = before.kind == absence.kind
+        && before.start_date == absence.start_date
+        && before.end_date == absence.end_date
+        && before.paid == absence.paid;
+    if !same_days {
+        crate::hour_bank::check_open(&tx, before.start_date.min(absence.start_date))?;
+    }
+    validate_range(
+        &tx,
+        before.worker_id,
+        absence.start_date,
+        absence.end_date,
+        Some(absence.id),
+    )?;
+    tx.execute(
+        "UPDATE absences SET kind = ?, start_date = ?, end_date = ?, paid = ?, note = ?
+         WHERE id = ?",
+        rusqlite::params![
+            absence.kind.code(),
+            absence.start_date.to_string(),
+            absence.end_date.to_string(),
+            absence.paid,
+            absence.note.trim(),
+            absence.id
+        ],
+    )?;
+    let after = get_absence(&tx, absence.id)?;
+    audit::record(
+        &tx,
+        ctx,
+        "absences",
+        Some(absence.id),
+        "update",
+        audit::snapshot(&before),
+        after.as_ref().and_then(audit::snapshot),
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn delete_absence(conn: &mut Connection, id: i64, ctx: &AuditContext) -> Result<()> {
+    let tx = conn.transaction()?;
+    let before = get_absence(&tx, id)?
+        .ok_or_else(|| DbError::Validation(format!("no existe la ausencia {}", id)))?;
+    crate::hour_bank::check_open(&tx, before.start_date)?;
+    tx.execute("DELETE FROM absences WHERE id = ?", rusqlite::params![id])?;
+    audit::record(
+        &tx,
+        ctx,
+        "absences",
+        Some(id),
+        "delete",
+        audit::snapshot(&before),
+        None,
+    )?;
+    tx.commit()?;
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use chrono::Utc;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use crate::db;
+use crate::reports::ReportError;
+
+/// Where a mutation originated.
+#[derive(Clone, Copy, Debug)]
+pub enum AuditSource {
+    /// A badge scan at the kiosk.
+    Kiosk,
+    /// An action taken from the admin tabs of the UI.
+    Admin,
+    /// Automatic maintenance such as quarantining unreadable rows.
+    System,
+}
+
+impl AuditSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditSource::Kiosk => "kiosk",
+            AuditSource::Admin => "admin",
+            AuditSource::System => "system",
+        }
+    }
+}
+
+/// Who is making a change and why; every mutating `db` function takes one.
+#[derive(Clone, Debug)]
+pub struct AuditContext {
+    pub actor: String,
+    pub source: AuditSource,
+    pub reason: Option<String>,
+}
+
+impl AuditContext {
+    pub fn kiosk(actor: &str) -> Self {
+        AuditContext {
+            actor: actor.to_string(),
+            source: AuditSource::Kiosk,
+            reason: None,
+        }
+    }
+
+    pub fn admin(actor: &str) -> Self {
+        let actor = actor.trim();
+        AuditContext {
+            actor: if actor.is_empty() { "admin" } else { actor }.to_string(),
+            source: AuditSource::Admin,
+            reason: None,
+        }
+    }
+
+    pub fn system() -> Self {
+        AuditContext {
+            actor: "system".to_string(),
+            source: AuditSource::System,
+            reason: None,
+        }
+    }
+
+    pub fn with_reason(mut self, reason: &str) -> Self {
+        let reason = reason.trim();
+        self.reason = (!reason.is_empty()).then(|| reason.to_string());
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub at: String,
+    pub actor: String,
+    pub source: String,
+    pub entity: String,
+    pub entity_id: Option<i64>,
+    pub action: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub reason: Option<String>,
+}
+
+const AUDIT_COLUMNS: &str =
+    "id, at, actor, source, entity, entity_id, action, before_json, after_json, reason";
+
+/// Serializes a row for the `before`/`after` columns.
+pub fn snapshot<T: Serialize>(value: &T) -> Option<serde_json::Value> {
+    serde_json::to_value(value).ok()
+}
+
+/// Appends one entry to `audit_log`. Call it with the same connection or
+/// transaction that performs the mutation so both commit together.
+pub fn record(
+    conn: &Connection,
+    ctx: &AuditContext,
+    entity: &str,
+    entity_id: Option<i64>,
+    action: &str,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+) -> db::Result<()> {
+    conn.execute(
+        "INSERT INTO audit_log (at, actor, source, entity, entity_id, action, before_json, after_json, reason)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        rusqlite::params![
+            Utc::now().to_rfc3339(),
+            ctx.actor,
+            ctx.source.as_str(),
+            entity,
+            entity_id,
+            action,
+            before.map(|v| v.to_string()),
+            after.map(|v| v.to_string()),
+            ctx.reason,
+        ],
+    )?;
+    Ok(())
+}
+
+/// The most recent `limit` entries, newest first.
+pub fn recent_entries(conn: &Connection, limit: usize) -> db::Result<Vec<AuditEntry>> {
+    query_entries(conn, Some(limit))
+}
+
+fn query_entries(conn: &Connection, limit: Option<usize>) -> db::Result<Vec<AuditEntry>> {
+    let mut sql = format!("SELECT {} FROM audit_log ORDER BY id DESC", AUDIT_COLUMNS);
+    if let Some(limit) = limit {
+        write!(sql, " LIMIT {}", limit).expect("write to string");
+    }
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map([], |row| {
+        Ok(AuditEntry {
+            id: row.get(0)?,
+            at: row.get(1)?,
+            actor: row.get(2)?,
+            source: row.get(3)?,
+            entity: row.get(4)?,
+            entity_id: row.get(5)?,
+            action: row.get(6)?,
+            before: row.get(7)?,
+            after: row.get(8)?,
+            reason: row.get(9)?,
+        })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+/// Writes the whole log, oldest first, as CSV and returns the row count.
+pub fn export_csv(conn: &Connection, path: &Path) -> Result<usize, ReportError> {
+    let mut entries = query_entries(conn, None)?;
+    entries.reverse();
+    let mut contents = String::new();
+    contents.push_str("id,fecha,actor,origen,entidad,entidad_id,accion,antes,despues,motivo\n");
+    for entry in &entries {
+        let fields = [
+            entry.id.to_string(),
+            entry.at.clone(),
+            entry.actor.clone(),
+            entry.source.clone(),
+            entry.entity.clone(),
+            entry.entity_id.map(|id| id.to_string()).unwrap_or_default(),
+            entry.action.clone(),
+            entry.before.clone().unwrap_or_default(),
+            entry.after.clone().unwrap_or_default(),
+            entry.reason.clone().unwrap_or_default(),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        writeln!(contents, "{}", line.join(",")).expect("write to string");
+    }
+    let mut file = File::create(path)?;
+    file.write_all(contents.as_bytes())?;
+    Ok(entries.len())
+}
+
+/// `value` as one CSV field: quoted, with quotes doubled, when it holds a
+/// comma, a quote or a line break.
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,482 @@
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use rusqlite::Connection;
+
+use crate::audit::{self, AuditContext};
+use crate::db::{self, DbError, ENTRY_COLUMNS, Result, TimesheetEntry};
+use crate::schedules::{WorkerCalendar, load_worker_calendar};
+use crate::timezone;
+use crate::utils::local_time_on;
+
+const POLICY_KEY: &str = "auto_close.policy";
+const CUTOFF_KEY: &str = "auto_close.cutoff";
+const MAX_SHIFT_KEY: &str = "auto_close.max_shift_hours";
+const GRACE_KEY: &str = "auto_close.grace_minutes";
+const DEFAULT_CUTOFF: &str = "23:00";
+
+/// What to do with sessions nobody clocked out of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AutoClosePolicy {
+    Disabled,
+    /// Close at the end of the worker's scheduled day.
+    ScheduledEnd,
+    /// Close at a fixed local time on the day the session started.
+    FixedCutoff(NaiveTime),
+    /// Close with zero minutes and leave the row for an admin to fix.
+    NeedsReview,
+}
+
+impl AutoClosePolicy {
+    /// Order of the options in the Settings picker.
+    pub const LABELS: [&'static str; 4] = [
+        "Desactivado",
+        "Hora de término programada",
+        "Hora de corte fija",
+        "Marcar para revisión",
+    ];
+
+    pub fn index(self) -> usize {
+        match self {
+            AutoClosePolicy::Disabled => 0,
+            AutoClosePolicy::ScheduledEnd => 1,
+            AutoClosePolicy::FixedCutoff(_) => 2,
+            AutoClosePolicy::NeedsReview => 3,
+        }
+    }
+
+    pub fn from_index(index: usize, cutoff: NaiveTime) -> Option<AutoClosePolicy> {
+        match index {
+            0 => Some(AutoClosePolicy::Disabled),
+            1 => Some(AutoClosePolicy::ScheduledEnd),
+            2 => Some(AutoClosePolicy::FixedCutoff(cutoff)),
+            3 => Some(AutoClosePolicy::NeedsReview),
+            _ => None,
+        }
+    }
+
+    fn code(self) -> &'static str {
+        match self {
+            AutoClosePolicy::Disabled => "disabled",
+            AutoClosePolicy::ScheduledEnd => "scheduled_end",
+            AutoClosePolicy::FixedCutoff(_) => "fixed_cutoff",
+            AutoClosePolicy::NeedsReview => "needs_review",
+        }
+    }
+}
+
+/// When an open session counts as forgotten: `grace_minutes` after the end
+/// of the worker's scheduled shift or, without one, after `max_shift_hours`
+/// from the clock-in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StaleLimits {
+    pub max_shift_hours: i64,
+    pub grace_minutes: i64,
+}
+
+impl Default for StaleLimits {
+    /// A 10-hour day plus the two hours of overtime the law allows; the
+    /// grace leaves the same two hours after a scheduled shift.
+    fn default() -> Self {
+        StaleLimits {
+            max_shift_hours: 12,
+            grace_minutes: 120,
+        }
+    }
+}
+
+impl StaleLimits {
+    /// Parses the Settings fields: whole hours (1–24) and minutes (0–720).
+    pub fn parse(max_shift_hours: &str, grace_minutes: &str) -> Option<StaleLimits> {
+        let max_shift_hours = max_shift_hours.trim().parse().ok()?;
+        let grace_minutes = grace_minutes.trim().parse().ok()?;
+        ((1..=24).contains(&max_shift_hours) && (0..=720).contains(&grace_minutes)).then_some(
+            StaleLimits {
+                max_shift_hours,
+                grace_minutes,
+            },
+        )
+    }
+
+    /// The instant after which a session is forgotten, given the end of its
+    /// scheduled shift if it has one.
+    fn stale_at(self, clock_in: DateTime<Utc>, shift_end: Option<DateTime<Utc>>) -> DateTime<Utc> {
+        shift_end.unwrap_or(clock_in + Duration::hours(self.max_shift_hours))
+            + Duration::minutes(self.grace_minutes)
+    }
+}
+
+/// Marker stored in `timesheets.auto_close` for rows closed by the policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AutoCloseMark {
+    AutoClosed,
+    NeedsReview,
+}
+
+impl AutoCloseMark {
+    pub fn code(self) -> &'static str {
+        match self {
+            AutoCloseMark::AutoClosed => "auto_closed",
+            AutoCloseMark::NeedsReview => "needs_review",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AutoCloseMark::AutoClosed => "Cierre automático",
+            AutoCloseMark::NeedsReview => "Requiere revisión",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<AutoCloseMark> {
+        [AutoCloseMark::AutoClosed, AutoCloseMark::NeedsReview]
+            .into_iter()
+            .find(|m| m.code() == code)
+    }
+}
+
+/// The stored policy. Nothing is closed automatically until an admin saves
+/// one, so upgrading never changes what happens to open sessions.
+pub fn load_policy(conn: &Connection) -> Result<AutoClosePolicy> {
+    let cutoff = load_cutoff(conn)?;
+    Ok(match crate::settings::get(conn, POLICY_KEY)?.as_deref() {
+        Some("scheduled_end") => AutoClosePolicy::ScheduledEnd,
+        Some("fixed_cutoff") => AutoClosePolicy::FixedCutoff(cutoff),
+        Some("needs_review") => AutoClosePolicy::NeedsReview,
+        _ => AutoClosePolicy::Disabled,
+    })
+}
+
+/// The configured cutoff time, even when another policy is active.
+pub fn load_cutoff(conn: &Connection) -> Result<NaiveTime> {
+    let stored = crate::settings::get(conn, CUTOFF_KEY)?;
+    Ok(parse_cutoff(stored.as_deref().unwrap_or(DEFAULT_CUTOFF))
+        .unwrap_or_else(|| parse_cutoff(DEFAULT_CUTOFF).expect("valid default cutoff")))
+}
+
+pub fn load_limits(conn: &Connection) -> Result<StaleLimits> {
+    let default = StaleLimits::default();
+    let stored = |key| -> Result<Option<i64>> {
+        Ok(crate::settings::get(conn, key)?.and_then(|value| value.trim().parse().ok()))
+    };
+    Ok(StaleLimits {
+        max_shift_hours: stored(MAX_SHIFT_KEY)?.unwrap_or(default.max_shift_hours),
+        grace_minutes: stored(GRACE_KEY)?.unwrap_or(default.grace_minutes),
+    })
+}
+
+pub fn parse_cutoff(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+pub fn save_policy(
+    conn: &mut Connection,
+    policy: AutoClosePolicy,
+    limits: StaleLimits,
+    ctx: &AuditContext,
+) -> Result<()> {
+    let max_shift = limits.max_shift_hours.to_string();
+    let grace = limits.grace_minutes.to_string();
+    let mut values = vec![
+        (MAX_SHIFT_KEY, max_shift.as_str()),
+        (GRACE_KEY, grace.as_str()),
+        (POLICY_KEY, policy.code()),
+    ];
+    let cutoff = match policy {
+        AutoClosePolicy::FixedCutoff(cutoff) => Some(cutoff.format("%H:%M").to_string()),
+        _ => None,
+    };
+    if let Some(cutoff) = &cutoff {
+        values.push((CUTOFF_KEY, cutoff));
+    }
+    crate::settings::set_many(conn, &values, ctx)
+}
+
+/// Closes every open session that the policy considers forgotten as of
+/// `now` and returns the closed rows.
+///
+/// A session is forgotten once its scheduled shift (the one starting on the
+/// clock-in day, or the previous day's night shift) has ended, or, without
+/// a shift, once `max_shift_hours` have passed since the clock-in, plus the
+/// grace period in both cases. A shift that runs past midnight therefore
+/// stays open until the worker's closing scan. Under `FixedCutoff` the
+/// session is closed at the cutoff on the day it started unless the worker
+/// is scheduled past it. When the policy cannot produce a closing time after
+/// the clock-in (a cutoff before the clock-in, or no scheduled shift) the
+/// forgotten row is closed with zero minutes and marked for review instead.
+pub fn close_stale_sessions(
+    conn: &mut Connection,
+    policy: AutoClosePolicy,
+    limits: StaleLimits,
+    now: DateTime<Utc>,
+    ctx: &AuditContext,
+) -> Result<Vec<TimesheetEntry>> {
+    if policy == AutoClosePolicy::Disabled {
+        return Ok(Vec::new());
+    }
+    let tx = conn.transaction()?;
+    let open = db::query_entries(
+        &tx,
+        &format!(
+            "SELECT {} FROM timesheets WHERE clock_out IS NULL ORDER BY id",
+            ENTRY_COLUMNS
+        ),
+        [],
+    )?;
+    let ctx = ctx
+        .clone()
+        .with_reason(&format!("cierre automático ({})", policy.code()));
+    let mut closed = Vec::new();
+    for entry in open {
+        let calendar = load_worker_calendar(&tx, entry.worker_id)?;
+        let Some((clock_out, mark)) = closing_for(&entry, policy, &calendar, limits, now) else {
+            continue;
+        };
+        tx.execute(
+            "UPDATE timesheets SET clock_out = ?, auto_close = ? WHERE id = ? AND clock_out IS NULL",
+            rusqlite::params![clock_out.to_rfc3339(), mark.code(), entry.id],
+        )?;
+        crate::breaks::close_open_break(&tx, entry.id, clock_out, &ctx)?;
+        let after = db::get_entry(&tx, entry.id)?
+            .ok_or_else(|| DbError::Validation(format!("no existe el registro {}", entry.id)))?;
+        audit::record(
+            &tx,
+            &ctx,
+            "timesheets",
+            Some(entry.id),
+            "auto_close",
+            audit::snapshot(&entry),
+            audit::snapshot(&after),
+        )?;
+        closed.push(after);
+    }
+    tx.commit()?;
+    Ok(closed)
+}
+
+/// Runs the stored policy with the system as actor and logs what it closed.
+/// Saves the policy and runs it right away, so sessions it already
+/// considers stale are closed without waiting for the next pass. Returns
+/// how many were closed.
+pub fn apply_policy(
+    conn: &mut Connection,
+    policy: AutoClosePolicy,
+    limits: StaleLimits,
+    ctx: &AuditContext,
+) -> Result<usize> {
+    save_policy(conn, policy, limits, ctx)?;
+    run(conn)
+}
+
+pub fn run(conn: &mut Connection) -> Result<usize> {
+    let policy = load_policy(conn)?;
+    let limits = load_limits(conn)?;
+    let closed = close_stale_sessions(conn, policy, limits, Utc::now(), &AuditContext::system())?;
+    for entry in &closed {
+        crate::config::log_event(&format!(
+            "Auto-closed session {} of worker {} ({})",
+            entry.id,
+            entry.worker_id,
+            entry.auto_close.as_deref().unwrap_or("")
+        ));
+    }
+    Ok(closed.len())
+}
+
+fn closing_for(
+    entry: &TimesheetEntry,
+    policy: AutoClosePolicy,
+    calendar: &WorkerCalendar,
+    limits: StaleLimits,
+    now: DateTime<Utc>,
+) -> Option<(DateTime<Utc>, AutoCloseMark)> {
+    let shift_end = calendar.shift_for(entry.clock_in).map(|(_, _, end)| end);
+    let stale_at = limits.stale_at(entry.clock_in, shift_end);
+    let review = (now >= stale_at).then_some((entry.clock_in, AutoCloseMark::NeedsReview));
+
+    let (close_at, due) = match policy {
+        AutoClosePolicy::Disabled => return None,
+        AutoClosePolicy::NeedsReview => return review,
+        AutoClosePolicy::ScheduledEnd => match shift_end {
+            Some(end) => (end, stale_at),
+            None => return review,
+        },
+        AutoClosePolicy::FixedCutoff(cutoff) => {
+            let start_date = timezone::to_local(entry.clock_in).date_naive();
+            match local_time_on(start_date, &cutoff.format("%H:%M").to_string()) {
+                // Scheduled to work past the cutoff, e.g. a night shift.
+                Some(at) if shift_end.is_some_and(|end| end > at) => (shift_end?, stale_at),
+                Some(at) => (at, at),
+                None => return review,
+            }
+        }
+    };
+    if close_at <= entry.clock_in {
+        return review;
+    }
+    (now >= due).then_some((close_at.min(now), AutoCloseMark::AutoClosed))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::{NaiveDate, Weekday};
+
+    use super::*;
+    use crate::schedules::{ScheduleDay, save_schedule, set_default_schedule};
+    use crate::timezone::TEST_ZONE_LOCK;
+
+    fn time(value: &str) -> NaiveTime {
+        parse_cutoff(value).unwrap()
+    }
+
+    /// A database with one worker and, if given, a default schedule with
+    /// the same shift every day.
+    fn test_db(shift: Option<(&str, &str)>) -> (Connection, i64, AuditContext) {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&mut conn, Path::new("backups")).unwrap();
+        let ctx = AuditContext::admin("test");
+        let worker_id = db::add_worker(&mut conn, "Prueba", "1000", &ctx).unwrap();
+        if let Some((start, end)) = shift {
+            let days: Vec<ScheduleDay> = (0..7)
+                .map(|day| ScheduleDay {
+                    weekday: Weekday::try_from(day).unwrap(),
+                    start: time(start),
+                    end: time(end),
+                    break_minutes: 0,
+                })
+                .collect();
+            let id = save_schedule(&mut conn, None, "Turno", &days, NaiveDate::MIN, &ctx).unwrap();
+            set_default_schedule(&mut conn, id, &ctx).unwrap();
+        }
+        (conn, worker_id, ctx)
+    }
+
+    /// `value` on the local clock, on March `day` 2026.
+    fn local(day: u32, value: &str) -> DateTime<Utc> {
+        local_time_on(NaiveDate::from_ymd_opt(2026, 3, day).unwrap(), value).unwrap()
+    }
+
+    fn scan(
+        conn: &mut Connection,
+        worker_id: i64,
+        ctx: &AuditContext,
+        at: DateTime<Utc>,
+    ) -> db::ClockEvent {
+        db::toggle_clock(conn, worker_id, None, at, false, ctx).unwrap()
+    }
+
+    fn close(
+        conn: &mut Connection,
+        policy: AutoClosePolicy,
+        now: DateTime<Utc>,
+        ctx: &AuditContext,
+    ) -> Vec<TimesheetEntry> {
+        close_stale_sessions(conn, policy, StaleLimits::default(), now, ctx).unwrap()
+    }
+
+    #[test]
+    fn night_shift_stays_open_until_the_morning_scan() {
+        let _guard = TEST_ZONE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        timezone::set_current(timezone::DEFAULT_TIME_ZONE);
+        for policy in [
+            AutoClosePolicy::NeedsReview,
+            AutoClosePolicy::ScheduledEnd,
+            AutoClosePolicy::FixedCutoff(time("23:00")),
+        ] {
+            let (mut conn, worker_id, ctx) = test_db(Some(("22:00", "06:00")));
+            scan(&mut conn, worker_id, &ctx, local(10, "22:00"));
+            // The kiosk runs the policy every minute and before each scan.
+            for now in [local(11, "00:00"), local(11, "00:01"), local(11, "06:00")] {
+                assert!(
+                    close(&mut conn, policy, now, &ctx).is_empty(),
+                    "{:?}",
+                    policy
+                );
+            }
+            match scan(&mut conn, worker_id, &ctx, local(11, "06:00")) {
+                db::ClockEvent::ClockedOut { clock_in, .. } => {
+                    assert_eq!(clock_in, local(10, "22:00"))
+                }
+                other => panic!("{:?}: expected a clock-out, got {:?}", policy, other),
+            }
+        }
+    }
+
+    #[test]
+    fn forgotten_night_shift_closes_after_the_grace_period() {
+        let _guard = TEST_ZONE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        timezone::set_current(timezone::DEFAULT_TIME_ZONE);
+        let (mut conn, worker_id, ctx) = test_db(Some(("22:00", "06:00")));
+        scan(&mut conn, worker_id, &ctx, local(10, "22:00"));
+        let policy = AutoClosePolicy::ScheduledEnd;
+        assert!(close(&mut conn, policy, local(11, "07:59"), &ctx).is_empty());
+        let closed = close(&mut conn, policy, local(11, "08:00"), &ctx);
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].clock_out, Some(local(11, "06:00")));
+        assert_eq!(closed[0].auto_close.as_deref(), Some("auto_closed"));
+
+        scan(&mut conn, worker_id, &ctx, local(11, "22:00"));
+        let closed = close(
+            &mut conn,
+            AutoClosePolicy::NeedsReview,
+            local(12, "08:00"),
+            &ctx,
+        );
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].clock_out, Some(local(11, "22:00")));
+        assert_eq!(closed[0].auto_close.as_deref(), Some("needs_review"));
+    }
+
+    #[test]
+    fn nothing_is_closed_until_a_policy_is_saved() {
+        let _guard = TEST_ZONE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        timezone::set_current(timezone::DEFAULT_TIME_ZONE);
+        let (mut conn, worker_id, ctx) = test_db(None);
+        assert_eq!(load_policy(&conn).unwrap(), AutoClosePolicy::Disabled);
+        scan(&mut conn, worker_id, &ctx, local(10, "09:00"));
+        assert_eq!(run(&mut conn).unwrap(), 0);
+    }
+
+    #[test]
+    fn sessions_without_a_closing_time_wait_for_the_maximum_shift() {
+        let _guard = TEST_ZONE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        timezone::set_current(timezone::DEFAULT_TIME_ZONE);
+        // Neither policy can close a 23:30 clock-in without a schedule at a
+        // time after it, so the row waits 12 hours plus the grace period and
+        // is then marked for review rather than cut at midnight.
+        for policy in [
+            AutoClosePolicy::ScheduledEnd,
+            AutoClosePolicy::FixedCutoff(time("23:00")),
+        ] {
+            let (mut conn, worker_id, ctx) = test_db(None);
+            scan(&mut conn, worker_id, &ctx, local(10, "23:30"));
+            assert!(close(&mut conn, policy, local(11, "00:00"), &ctx).is_empty());
+            assert!(close(&mut conn, policy, local(11, "13:29"), &ctx).is_empty());
+            let closed = close(&mut conn, policy, local(11, "13:30"), &ctx);
+            assert_eq!(closed.len(), 1, "{:?}", policy);
+            assert_eq!(closed[0].clock_out, Some(local(10, "23:30")));
+            assert_eq!(closed[0].auto_close.as_deref(), Some("needs_review"));
+        }
+    }
+
+    #[test]
+    fn saving_a_policy_runs_it_on_the_same_connection() {
+        let _guard = TEST_ZONE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        timezone::set_current(timezone::DEFAULT_TIME_ZONE);
+        let (mut conn, worker_id, ctx) = test_db(None);
+        scan(&mut conn, worker_id, &ctx, local(10, "09:00"));
+        // Shared the way the UI handlers share it.
+        let conn = std::cell::RefCell::new(conn);
+        let closed = apply_policy(
+            &mut conn.borrow_mut(),
+            AutoClosePolicy::NeedsReview,
+            StaleLimits::default(),
+            &ctx,
+        )
+        .unwrap();
+        assert_eq!(closed, 1);
+        let conn = conn.borrow();
+        assert_eq!(load_policy(&conn).unwrap(), AutoClosePolicy::NeedsReview);
+        assert!(db::get_current_status(&conn, worker_id).unwrap().is_none());
+    }
+}
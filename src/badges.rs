@@ -0,0 +1,192 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::audit::{self, AuditContext};
+use crate::db::{DbError, Result};
+
+/// A barcode card held by a worker between `valid_from` and `valid_to`.
+/// A badge with no `valid_to` is the worker's current card.
+#[derive(Clone, Debug, Serialize)]
+pub struct WorkerBadge {
+    pub id: i64,
+    pub worker_id: i64,
+    pub barcode: String,
+    pub valid_from: String,
+    pub valid_to: Option<String>,
+    pub revoked_reason: Option<String>,
+}
+
+impl WorkerBadge {
+    pub fn is_active(&self) -> bool {
+        self.valid_to.is_none()
+    }
+}
+
+const BADGE_COLUMNS: &str = "id, worker_id, barcode, valid_from, valid_to, revoked_reason";
+
+fn badge_from_row(row: &rusqlite::Row) -> rusqlite::Result<WorkerBadge> {
+    Ok(WorkerBadge {
+        id: row.get(0)?,
+        worker_id: row.get(1)?,
+        barcode: row.get(2)?,
+        valid_from: row.get(3)?,
+        valid_to: row.get(4)?,
+        revoked_reason: row.get(5)?,
+    })
+}
+
+pub fn get_badge(conn: &Connection, badge_id: i64) -> Result<Option<WorkerBadge>> {
+    Ok(conn
+        .query_row(
+            &format!("SELECT {} FROM worker_badges WHERE id = ?", BADGE_COLUMNS),
+            rusqlite::params![badge_id],
+            badge_from_row,
+        )
+        .optional()?)
+}
+
+/// The badge currently valid for `barcode`, if any.
+pub fn get_active_badge(conn: &Connection, barcode: &str) -> Result<Option<WorkerBadge>> {
+    Ok(conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM worker_badges WHERE barcode = ? AND valid_to IS NULL",
+                BADGE_COLUMNS
+            ),
+            rusqlite::params![barcode],
+            badge_from_row,
+        )
+        .optional()?)
+}
+
+/// Every badge the worker has held, newest first.
+pub fn get_worker_badges(conn: &Connection, worker_id: i64) -> Result<Vec<WorkerBadge>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM worker_badges WHERE worker_id = ? ORDER BY id DESC",
+        BADGE_COLUMNS
+    ))?;
+    let badges = stmt.query_map(rusqlite::params![worker_id], badge_from_row)?;
+    Ok(badges.collect::<rusqlite::Result<_>>()?)
+}
+
+/// Inserts a new current badge for the worker inside the caller's
+/// transaction. The caller records the audit entry.
+pub(crate) fn issue(
+    conn: &Connection,
+    worker_id: i64,
+    barcode: &str,
+    at: DateTime<Utc>,
+) -> Result<WorkerBadge> {
+    conn.execute(
+        "INSERT INTO worker_badges (worker_id, barcode, valid_from) VALUES (?, ?, ?)",
+        rusqlite::params![worker_id, barcode, at.to_rfc3339()],
+    )
+    .map_err(|e| barcode_in_use(e, barcode))?;
+    let id = conn.last_insert_rowid();
+    get_badge(conn, id)?.ok_or_else(|| DbError::Validation(format!("no existe la tarjeta {}", id)))
+}
+
+/// Ends the validity of a badge, for example when the card is lost.
+pub fn revoke_badge(
+    conn: &mut Connection,
+    badge_id: i64,
+    reason: &str,
+    ctx: &AuditContext,
+) -> Result<()> {
+    let tx = conn.transaction()?;
+    let before = get_badge(&tx, badge_id)?
+        .ok_or_else(|| DbError::Validation(format!("no existe la tarjeta {}", badge_id)))?;
+    if !before.is_active() {
+        return Err(DbError::Validation(format!(
+            "la tarjeta {} ya fue revocada",
+            before.barcode
+        )));
+    }
+    revoke(&tx, badge_id, reason, Utc::now())?;
+    let after = get_badge(&tx, badge_id)?;
+    audit::record(
+        &tx,
+        &ctx.clone().with_reason(reason),
+        "worker_badges",
+        Some(badge_id),
+        "revoke",
+        audit::snapshot(&before),
+        after.as_ref().and_then(audit::snapshot),
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Revokes the worker's current badges and issues `barcode` in their place.
+/// Returns the new badge id.
+pub fn replace_badge(
+    conn: &mut Connection,
+    worker_id: i64,
+    barcode: &str,
+    reason: &str,
+    ctx: &AuditContext,
+) -> Result<i64> {
+    let tx = conn.transaction()?;
+    let new_badge = replace_within(&tx, worker_id, barcode, reason, ctx)?;
+    tx.commit()?;
+    Ok(new_badge.id)
+}
+
+/// `replace_badge` inside an existing transaction, shared with
+/// `db::update_worker` when the barcode field changes.
+pub(crate) fn replace_within(
+    conn: &Connection,
+    worker_id: i64,
+    barcode: &str,
+    reason: &str,
+    ctx: &AuditContext,
+) -> Result<WorkerBadge> {
+    let now = Utc::now();
+    let current: Vec<WorkerBadge> = get_worker_badges(conn, worker_id)?
+        .into_iter()
+        .filter(WorkerBadge::is_active)
+        .collect();
+    for badge in &current {
+        revoke(conn, badge.id, reason, now)?;
+    }
+    let new_badge = issue(conn, worker_id, barcode, now)?;
+    audit::record(
+        conn,
+        &ctx.clone().with_reason(reason),
+        "worker_badges",
+        Some(new_badge.id),
+        "replace",
+        serde_json::to_value(&current).ok(),
+        audit::snapshot(&new_badge),
+    )?;
+    Ok(new_badge)
+}
+
+fn revoke(conn: &Connection, badge_id: i64, reason: &str, at: DateTime<Utc>) -> Result<()> {
+    let reason = reason.trim();
+    conn.execute(
+        "UPDATE worker_badges SET valid_to = ?, revoked_reason = ? WHERE id = ? AND valid_to IS NULL",
+        rusqlite::params![
+            at.to_rfc3339(),
+            (!reason.is_empty()).then_some(reason),
+            badge_id
+        ],
+    )?;
+    Ok(())
+}
+
+/// Turns a violation of the one-holder-per-barcode index into a readable error.
+fn barcode_in_use(error: rusqlite::Error, barcode: &str) -> DbError {
+    match error {
+        rusqlite::Error::SqliteFailure(ref e, _)
+            if e.code == rusqlite::ErrorCode::ConstraintViolation =>
+        {
+            DbError::Conflict(format!(
+                "el código {} ya está asignado a otra tarjeta vigente",
+                barcode
+            ))
+        }
+        other => other.into(),
+    }
+}
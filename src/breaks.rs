@@ -0,0 +1,802 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::audit::{self, AuditContext};
+use crate::db::{DbError, InvalidRow, Result, TimesheetEntry, text_column};
+use crate::schedules::ScheduleDay;
+use crate::timezone;
+
+const SCAN_WINDOW_KEY: &str = "breaks.scan_window";
+const PAID_KEY: &str = "breaks.paid";
+
+/// What a punch recorded. A session runs from work start to work end and
+/// may contain breaks, each from break start to break end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum PunchType {
+    WorkStart,
+    BreakStart,
+    BreakEnd,
+    WorkEnd,
+}
+
+impl PunchType {
+    pub fn label(self) -> &'static str {
+        match self {
+            PunchType::WorkStart => "Entrada registrada",
+            PunchType::BreakStart => "Inicio de colación",
+            PunchType::BreakEnd => "Fin de colación",
+            PunchType::WorkEnd => "Salida registrada",
+        }
+    }
+
+    /// Punches that start something (work or the return from a break).
+    pub fn is_start(self) -> bool {
+        matches!(self, PunchType::WorkStart | PunchType::BreakEnd)
+    }
+}
+
+/// A break inside a session. `end` is `None` while the worker is out.
+#[derive(Clone, Debug, Serialize)]
+pub struct Break {
+    pub id: i64,
+    pub timesheet_id: i64,
+    pub start: DateTime<Utc>,
+    pub end: Option<DateTime<Utc>>,
+    /// Paid breaks count as worked time; unpaid ones are subtracted.
+    pub paid: bool,
+    pub start_badge_id: Option<i64>,
+    pub end_badge_id: Option<i64>,
+}
+
+impl Break {
+    /// Minutes of the break that fall inside the session, with open breaks
+    /// and sessions running until `now`.
+    pub fn minutes_within(&self, entry: &TimesheetEntry, now: DateTime<Utc>) -> i64 {
+        self.minutes_between(entry.clock_in, entry.clock_out.unwrap_or(now), now)
+    }
+
+    /// Minutes of the break between `from` and `to`.
+    pub fn minutes_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> i64 {
+        let start = self.start.max(from);
+        let end = self.end.unwrap_or(now).min(to);
+        (end - start).num_minutes().max(0)
+    }
+}
+
+/// How scans turn into breaks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BreakSettings {
+    /// Local time range in which a scan during an open session starts a
+    /// break instead of ending the day, once per session, for sessions
+    /// without a scheduled break. `None` leaves those breaks to the break
+    /// key.
+    pub scan_window: Option<(NaiveTime, NaiveTime)>,
+    /// Whether breaks recorded from now on are paid.
+    pub paid: bool,
+}
+
+impl BreakSettings {
+    /// Whether a scan at `at` starts the session's break. A scheduled shift
+    /// with a break decides through its own break window; the global window
+    /// only applies when the session has no such shift.
+    fn starts_break(
+        &self,
+        entry: &TimesheetEntry,
+        shift: Option<(&ScheduleDay, DateTime<Utc>, DateTime<Utc>)>,
+        at: DateTime<Utc>,
+    ) -> bool {
+        if let Some((from, to)) =
+            shift.and_then(|(day, start, end)| scheduled_break_window(day, start, end))
+        {
+            return from <= at && at < to;
+        }
+        let Some((from, to)) = self.scan_window else {
+            return false;
+        };
+        let local = at.with_timezone(&timezone::current());
+        let same_day = local.date_naive()
+            == entry
+                .clock_in
+                .with_timezone(&timezone::current())
+                .date_naive();
+        same_day && from <= local.time() && local.time() < to
+    }
+}
+
+/// Where a scan starts the scheduled break: the schedule only says how long
+/// the break is, so it is expected around the middle of the shift, within
+/// its length either side. `None` when the shift has no break.
+fn scheduled_break_window(
+    day: &ScheduleDay,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    if day.break_minutes <= 0 {
+        return None;
+    }
+    let middle = start + (end - start) / 2;
+    let margin = Duration::minutes(day.break_minutes);
+    Some(((middle - margin).max(start), (middle + margin).min(end)))
+}
+
+/// Parses "HH:MM-HH:MM"; an empty value disables the window.
+pub fn parse_scan_window(value: &str) -> Option<Option<(NaiveTime, NaiveTime)>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Some(None);
+    }
+    let (from, to) = value.split_once('-')?;
+    let from = NaiveTime::parse_from_str(from.trim(), "%H:%M").ok()?;
+    let to = NaiveTime::parse_from_str(to.trim(), "%H:%M").ok()?;
+    (from < to).then_some(Some((from, to)))
+}
+
+pub fn format_scan_window(window: Option<(NaiveTime, NaiveTime)>) -> String {
+    window
+        .map(|(from, to)| format!("{}-{}", from.format("%H:%M"), to.format("%H:%M")))
+        .unwrap_or_default()
+}
+
+pub fn load_settings(conn: &Connection) -> Result<BreakSettings> {
+    Ok(BreakSettings {
+        scan_window: crate::settings::get(conn, SCAN_WINDOW_KEY)?
+            .and_then(|value| parse_scan_window(&value))
+            .flatten(),
+        paid: crate::settings::get(conn, PAID_KEY)?.as_deref() == Some("true"),
+    })
+}
+
+pub fn save_settings(
+    conn: &mut Connection,
+    settings: BreakSettings,
+    ctx: &AuditContext,
+) -> Result<()> {
+    crate::settings::set_many(
+        conn,
+        &[
+            (SCAN_WINDOW_KEY, &format_scan_window(settings.scan_window)),
+            (PAID_KEY, if settings.paid { "true" } else { "false" }),
+        ],
+        ctx,
+    )
+}
+
+const BREAK_COLUMNS: &str =
+    "id, timesheet_id, break_start, break_end, paid, start_badge_id, end_badge_id";
+
+/// Reads a break row; unreadable times come back as the invalid row rather
+/// than a query error, like timesheet rows.
+fn break_from_row(row: &rusqlite::Row) -> rusqlite::Result<std::result::Result<Break, InvalidRow>> {
+    let id: i64 = row.get(0)?;
+    let start = text_column(row, 2)?;
+    let end = text_column(row, 3)?;
+    let invalid = |column, value: Option<&str>, problem| InvalidRow {
+        table: "breaks",
+        row_id: id,
+        column,
+        value: value.unwrap_or("NULL").to_string(),
+        problem,
+    };
+    let parse = |column, value: Option<&str>| {
+        let text = value.ok_or_else(|| invalid(column, value, "valor vacío"))?;
+        DateTime::parse_from_rfc3339(text)
+            .map(|dt| dt.with_timezone(&Utc))
+            .map_err(|_| invalid(column, value, "fecha inválida"))
+    };
+    let start = match parse("break_start", start.as_deref()) {
+        Ok(start) => start,
+        Err(invalid) => return Ok(Err(invalid)),
+    };
+    let end = match end.as_deref() {
+        Some(value) => match parse("break_end", Some(value)) {
+            Ok(end) if end < start => {
+                return Ok(Err(invalid(
+                    "break_end",
+                    Some(value),
+                    "fin anterior al inicio",
+                )));
+            }
+            Ok(end) => Some(end),
+            Err(invalid) => return Ok(Err(invalid)),
+        },
+        None => None,
+    };
+    Ok(Ok(Break {
+        id,
+        timesheet_id: row.get(1)?,
+        start,
+        end,
+        paid: row.get(4)?,
+        start_badge_id: row.get(5)?,
+        end_badge_id: row.get(6)?,
+    }))
+}
+
+/// Keeps a readable break, noting an unreadable one so it shows up with the
+/// other rows readers skipped.
+fn readable(row: std::result::Result<Break, InvalidRow>) -> Option<Break> {
+    row.map_err(crate::db::note_invalid_row).ok()
+}
+
+/// The session's breaks in order.
+pub fn entry_breaks(conn: &Connection, timesheet_id: i64) -> Result<Vec<Break>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM breaks WHERE timesheet_id = ? ORDER BY break_start",
+        BREAK_COLUMNS
+    ))?;
+    let rows = stmt.query_map(rusqlite::params![timesheet_id], break_from_row)?;
+    let mut breaks = Vec::new();
+    for row in rows {
+        if let Some(b) = readable(row?) {
+            breaks.push(b);
+        }
+    }
+    Ok(breaks)
+}
+
+pub fn open_break(conn: &Connection, timesheet_id: i64) -> Result<Option<Break>> {
+    Ok(conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM breaks WHERE timesheet_id = ? AND break_end IS NULL",
+                BREAK_COLUMNS
+            ),
+            rusqlite::params![timesheet_id],
+            break_from_row,
+        )
+        .optional()?
+        .and_then(readable))
+}
+
+fn get_break(conn: &Connection, id: i64) -> Result<Option<Break>> {
+    Ok(conn
+        .query_row(
+            &format!("SELECT {} FROM breaks WHERE id = ?", BREAK_COLUMNS),
+            rusqlite::params![id],
+            break_from_row,
+        )
+        .optional()?
+        .and_then(readable))
+}
+
+/// Checks every break row and returns those that cannot be interpreted.
+pub(crate) fn invalid_breaks(conn: &Connection) -> Result<Vec<InvalidRow>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM breaks ORDER BY id", BREAK_COLUMNS))?;
+    let rows = stmt
+        .query_map([], break_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows.into_iter().filter_map(|row| row.err()).collect())
+}
+
+/// Break and unpaid break minutes of a session.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BreakMinutes {
+    pub total: i64,
+    pub unpaid: i64,
+}
+
+/// Breaks of a set of sessions, keyed by session id.
+#[derive(Clone, Debug, Default)]
+pub struct SessionBreaks(HashMap<i64, Vec<Break>>);
+
+impl SessionBreaks {
+    pub fn load(conn: &Connection, entries: &[TimesheetEntry]) -> Result<SessionBreaks> {
+        let mut by_entry = HashMap::new();
+        for entry in entries {
+            let breaks = entry_breaks(conn, entry.id)?;
+            if !breaks.is_empty() {
+                by_entry.insert(entry.id, breaks);
+            }
+        }
+        Ok(SessionBreaks(by_entry))
+    }
+
+    pub fn minutes(&self, entry: &TimesheetEntry, now: DateTime<Utc>) -> BreakMinutes {
+        self.minutes_between(entry, entry.clock_in, entry.clock_out.unwrap_or(now), now)
+    }
+
+    /// Break minutes of the session that fall between `from` and `to`.
+    pub fn minutes_between(
+        &self,
+        entry: &TimesheetEntry,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> BreakMinutes {
+        let mut minutes = BreakMinutes::default();
+        for b in self.0.get(&entry.id).into_iter().flatten() {
+            let within = b.minutes_between(from.max(entry.clock_in), to, now);
+            minutes.total += within;
+            if !b.paid {
+                minutes.unpaid += within;
+            }
+        }
+        minutes
+    }
+}
+
+/// Opens a break in `entry` at `at`. Runs inside the caller's transaction.
+pub(crate) fn start_break(
+    tx: &rusqlite::Transaction,
+    entry: &TimesheetEntry,
+    badge_id: Option<i64>,
+    at: DateTime<Utc>,
+    paid: bool,
+    ctx: &AuditContext,
+) -> Result<Break> {
+    let at = at.max(entry.clock_in);
+    // An unreadable open break still holds the session's one open break.
+    let unreadable: Option<i64> = tx
+        .query_row(
+            "SELECT id FROM breaks WHERE timesheet_id = ? AND break_end IS NULL",
+            rusqlite::params![entry.id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(id) = unreadable {
+        return Err(DbError::Validation(format!(
+            "la colación abierta {} no se puede leer; muévala a cuarentena en Ajustes",
+            id
+        )));
+    }
+    tx.execute(
+        "INSERT INTO breaks (timesheet_id, break_start, paid, start_badge_id) VALUES (?, ?, ?, ?)",
+        rusqlite::params![entry.id, at.to_rfc3339(), paid, badge_id],
+    )?;
+    let id = tx.last_insert_rowid();
+    let after = get_break(tx, id)?
+        .ok_or_else(|| DbError::Validation(format!("no existe la colación {}", id)))?;
+    audit::record(
+        tx,
+        ctx,
+        "breaks",
+        Some(id),
+        "break_start",
+        None,
+        audit::snapshot(&after),
+    )?;
+    Ok(after)
+}
+
+/// Closes an open break at `at` (never before it started).
+pub(crate) fn end_break(
+    tx: &rusqlite::Transaction,
+    open: &Break,
+    badge_id: Option<i64>,
+    at: DateTime<Utc>,
+    ctx: &AuditContext,
+) -> Result<Break> {
+    let at = at.max(open.start);
+    tx.execute(
+        "UPDATE breaks SET break_end = ?, end_badge_id = ? WHERE id = ? AND break_end IS NULL",
+        rusqlite::params![at.to_rfc3339(), badge_id, open.id],
+    )?;
+    let after = Break {
+        end: Some(at),
+        end_badge_id: badge_id,
+        ..open.clone()
+    };
+    audit::record(
+        tx,
+        ctx,
+        "breaks",
+        Some(open.id),
+        "break_end",
+        audit::snapshot(open),
+        audit::snapshot(&after),
+    )?;
+    Ok(after)
+}
+
+/// Ends the session's open break, if any, when the session itself is
+/// closed at `at`.
+pub(crate) fn close_open_break(
+    tx: &rusqlite::Transaction,
+    timesheet_id: i64,
+    at: DateTime<Utc>,
+    ctx: &AuditContext,
+) -> Result<()> {
+    if let Some(open) = open_break(tx, timesheet_id)? {
+        end_break(tx, &open, None, at, ctx)?;
+    }
+    Ok(())
+}
+
+/// Trims the session's breaks to `clock_in`..`clock_out` after a correction
+/// moved the session's bounds, deleting breaks left with nothing inside it.
+/// Runs inside the caller's transaction.
+pub(crate) fn fit_breaks(
+    tx: &rusqlite::Transaction,
+    timesheet_id: i64,
+    clock_in: DateTime<Utc>,
+    clock_out: Option<DateTime<Utc>>,
+    ctx: &AuditContext,
+) -> Result<()> {
+    for b in entry_breaks(tx, timesheet_id)? {
+        let start = b.start.max(clock_in);
+        let end = match (b.end, clock_out) {
+            (Some(end), Some(out)) => Some(end.min(out)),
+            (end, None) => end,
+            (None, out) => out,
+        };
+        if end.is_some_and(|end| end <= start) {
+            tx.execute("DELETE FROM breaks WHERE id = ?", rusqlite::params![b.id])?;
+            audit::record(
+                tx,
+                ctx,
+                "breaks",
+                Some(b.id),
+                "delete",
+                audit::snapshot(&b),
+                None,
+            )?;
+        } else if start != b.start || end != b.end {
+            tx.execute(
+                "UPDATE breaks SET break_start = ?, break_end = ? WHERE id = ?",
+                rusqlite::params![start.to_rfc3339(), end.map(|t| t.to_rfc3339()), b.id],
+            )?;
+            let after = Break {
+                start,
+                end,
+                ..b.clone()
+            };
+            audit::record(
+                tx,
+                ctx,
+                "breaks",
+                Some(b.id),
+                "adjust",
+                audit::snapshot(&b),
+                audit::snapshot(&after),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Moves the breaks of session `from_id` that fall after `at` to session
+/// `to_id`, the second half of a split. A break running across `at` is
+/// split with it. Runs inside the caller's transaction.
+pub(crate) fn split_breaks(
+    tx: &rusqlite::Transaction,
+    from_id: i64,
+    to_id: i64,
+    at: DateTime<Utc>,
+    ctx: &AuditContext,
+) -> Result<()> {
+    for b in entry_breaks(tx, from_id)? {
+        if b.end.is_some_and(|end| end <= at) {
+            continue;
+        }
+        if b.start >= at {
+            tx.execute(
+                "UPDATE breaks SET timesheet_id = ? WHERE id = ?",
+                rusqlite::params![to_id, b.id],
+            )?;
+            let after = Break {
+                timesheet_id: to_id,
+                ..b.clone()
+            };
+            audit::record(
+                tx,
+                ctx,
+                "breaks",
+                Some(b.id),
+                "move",
+                audit::snapshot(&b),
+                audit::snapshot(&after),
+            )?;
+            continue;
+        }
+        tx.execute(
+            "UPDATE breaks SET break_end = ?, end_badge_id = NULL WHERE id = ?",
+            rusqlite::params![at.to_rfc3339(), b.id],
+        )?;
+        tx.execute(
+            "INSERT INTO breaks (timesheet_id, break_start, break_end, paid, end_badge_id)
+             VALUES (?, ?, ?, ?, ?)",
+            rusqlite::params![
+                to_id,
+                at.to_rfc3339(),
+                b.end.map(|t| t.to_rfc3339()),
+                b.paid,
+                b.end_badge_id
+            ],
+        )?;
+        let second = get_break(tx, tx.last_insert_rowid())?;
+        let first = Break {
+            end: Some(at),
+            end_badge_id: None,
+            ..b.clone()
+        };
+        audit::record(
+            tx,
+            ctx,
+            "breaks",
+            Some(b.id),
+            "split",
+            audit::snapshot(&b),
+            Some(serde_json::json!([first, second])),
+        )?;
+    }
+    Ok(())
+}
+
+/// Decides what a scan during the open session `entry` records: the end of
+/// an open break, a new break (when the break key was pressed or the scan
+/// falls in the break window of the worker's shift, or in the global window
+/// when the shift has no break, and the session has no break yet), or `None` for the end of
+/// the work day.
+pub(crate) fn scan_in_session(
+    tx: &rusqlite::Transaction,
+    entry: &TimesheetEntry,
+    badge_id: Option<i64>,
+    at: DateTime<Utc>,
+    break_key: bool,
+    ctx: &AuditContext,
+) -> Result<Option<(PunchType, Break)>> {
+    if let Some(open) = open_break(tx, entry.id)? {
+        return Ok(Some((
+            PunchType::BreakEnd,
+            end_break(tx, &open, badge_id, at, ctx)?,
+        )));
+    }
+    let settings = load_settings(tx)?;
+    let first_break = entry_breaks(tx, entry.id)?.is_empty();
+    let starts_break = first_break && {
+        let calendar = crate::schedules::load_worker_calendar(tx, entry.worker_id)?;
+        settings.starts_break(entry, calendar.shift_for(entry.clock_in), at)
+    };
+    if break_key || starts_break {
+        return Ok(Some((
+            PunchType::BreakStart,
+            start_break(tx, entry, badge_id, at, settings.paid, ctx)?,
+        )));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::{NaiveDate, TimeZone, Weekday};
+
+    use super::*;
+    use crate::db::ClockEvent;
+    use crate::schedules::{save_schedule, set_default_schedule};
+    use crate::timezone::TEST_ZONE_LOCK;
+    use crate::utils::local_time_on;
+
+    fn test_db() -> (Connection, i64, AuditContext) {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&mut conn, Path::new("backups")).unwrap();
+        let ctx = AuditContext::admin("test");
+        let worker_id = crate::db::add_worker(&mut conn, "Prueba", "1000", &ctx).unwrap();
+        (conn, worker_id, ctx)
+    }
+
+    #[test]
+    fn unreadable_breaks_are_reported_and_quarantined() {
+        let _guard = crate::db::TEST_INVALID_ROWS_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let (mut conn, worker_id, ctx) = test_db();
+        let at = Utc.with_ymd_and_hms(2024, 3, 4, 12, 0, 0).unwrap();
+        let ClockEvent::ClockedIn { entry_id, .. } =
+            crate::db::toggle_clock(&mut conn, worker_id, None, at, false, &ctx).unwrap()
+        else {
+            panic!("expected a clock-in");
+        };
+        // An open break with an unreadable start, and a closed one stored
+        // as a number instead of text.
+        conn.execute(
+            "INSERT INTO breaks (timesheet_id, break_start, paid) VALUES (?, 'ayer', 0)",
+            rusqlite::params![entry_id],
+        )
+        .unwrap();
+        let open_id = conn.last_insert_rowid();
+        conn.execute(
+            "INSERT INTO breaks (timesheet_id, break_start, break_end, paid)
+             VALUES (?, 42, '2024-03-04T12:30:00+00:00', 0)",
+            rusqlite::params![entry_id],
+        )
+        .unwrap();
+        let numeric_id = conn.last_insert_rowid();
+
+        assert!(entry_breaks(&conn, entry_id).unwrap().is_empty());
+        let seen = |table: &str, id| {
+            crate::db::invalid_rows_seen()
+                .iter()
+                .any(|row| row.table == table && row.row_id == id)
+        };
+        assert!(seen("breaks", open_id) && seen("breaks", numeric_id));
+        let scanned = crate::db::scan_invalid_rows(&conn).unwrap();
+        assert_eq!(
+            scanned
+                .iter()
+                .filter(|row| row.table == "breaks")
+                .map(|row| row.row_id)
+                .collect::<Vec<_>>(),
+            vec![open_id, numeric_id]
+        );
+
+        // The unreadable open break still holds the session's open slot.
+        let break_at = at + chrono::Duration::hours(1);
+        match crate::db::toggle_clock(&mut conn, worker_id, None, break_at, true, &ctx) {
+            Err(DbError::Validation(message)) => assert!(message.contains("cuarentena")),
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+
+        assert_eq!(
+            crate::db::quarantine_invalid_rows(&mut conn, &ctx).unwrap(),
+            2
+        );
+        let kept: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM breaks_quarantine WHERE timesheet_id = ?",
+                rusqlite::params![entry_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(kept, 2);
+        assert!(crate::db::scan_invalid_rows(&conn).unwrap().is_empty());
+        let event =
+            crate::db::toggle_clock(&mut conn, worker_id, None, break_at, true, &ctx).unwrap();
+        assert!(matches!(event, ClockEvent::BreakStarted { .. }));
+    }
+
+    #[test]
+    fn a_session_never_holds_two_open_breaks() {
+        let (mut conn, worker_id, ctx) = test_db();
+        let at = Utc.with_ymd_and_hms(2024, 3, 4, 12, 0, 0).unwrap();
+        let ClockEvent::ClockedIn { entry_id, .. } =
+            crate::db::toggle_clock(&mut conn, worker_id, None, at, false, &ctx).unwrap()
+        else {
+            panic!("expected a clock-in");
+        };
+        let later = |minutes| at + chrono::Duration::minutes(minutes);
+        let event =
+            crate::db::toggle_clock(&mut conn, worker_id, None, later(60), true, &ctx).unwrap();
+        assert!(matches!(event, ClockEvent::BreakStarted { .. }));
+
+        let duplicate = conn.execute(
+            "INSERT INTO breaks (timesheet_id, break_start, paid) VALUES (?, ?, 0)",
+            rusqlite::params![entry_id, later(70).to_rfc3339()],
+        );
+        assert!(duplicate.is_err());
+
+        // A second break key press ends the open break instead.
+        let event =
+            crate::db::toggle_clock(&mut conn, worker_id, None, later(90), true, &ctx).unwrap();
+        assert!(matches!(event, ClockEvent::BreakEnded { .. }));
+        let breaks = entry_breaks(&conn, entry_id).unwrap();
+        assert_eq!(breaks.len(), 1);
+        assert_eq!(
+            (breaks[0].start, breaks[0].end),
+            (later(60), Some(later(90)))
+        );
+    }
+
+    #[test]
+    fn quarantined_sessions_take_their_breaks_along() {
+        let _guard = crate::db::TEST_INVALID_ROWS_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let (mut conn, worker_id, ctx) = test_db();
+        let at = Utc.with_ymd_and_hms(2024, 3, 4, 12, 0, 0).unwrap();
+        let ClockEvent::ClockedIn { entry_id, .. } =
+            crate::db::toggle_clock(&mut conn, worker_id, None, at, false, &ctx).unwrap()
+        else {
+            panic!("expected a clock-in");
+        };
+        crate::db::toggle_clock(
+            &mut conn,
+            worker_id,
+            None,
+            at + chrono::Duration::hours(1),
+            true,
+            &ctx,
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE timesheets SET clock_in = 'mañana' WHERE id = ?",
+            rusqlite::params![entry_id],
+        )
+        .unwrap();
+
+        assert_eq!(
+            crate::db::quarantine_invalid_rows(&mut conn, &ctx).unwrap(),
+            1
+        );
+        assert!(entry_breaks(&conn, entry_id).unwrap().is_empty());
+        let before: String = conn
+            .query_row(
+                "SELECT before_json FROM audit_log WHERE entity = 'timesheets' AND entity_id = ?
+                 AND action = 'quarantine'",
+                rusqlite::params![entry_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let before: serde_json::Value = serde_json::from_str(&before).unwrap();
+        assert_eq!(before["breaks"].as_array().map(Vec::len), Some(1));
+        assert_eq!(before["clock_in"], "mañana");
+    }
+
+    fn time(value: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(value, "%H:%M").unwrap()
+    }
+
+    /// `value` on the local clock, on March `day` 2026.
+    fn local(day: u32, value: &str) -> DateTime<Utc> {
+        local_time_on(NaiveDate::from_ymd_opt(2026, 3, day).unwrap(), value).unwrap()
+    }
+
+    fn scan(
+        conn: &mut Connection,
+        worker_id: i64,
+        ctx: &AuditContext,
+        at: DateTime<Utc>,
+    ) -> ClockEvent {
+        crate::db::toggle_clock(conn, worker_id, None, at, false, ctx).unwrap()
+    }
+
+    fn set_window(conn: &mut Connection, window: &str, ctx: &AuditContext) {
+        let settings = BreakSettings {
+            scan_window: parse_scan_window(window).unwrap(),
+            paid: false,
+        };
+        save_settings(conn, settings, ctx).unwrap();
+    }
+
+    #[test]
+    fn scheduled_break_decides_over_the_global_window() {
+        let _guard = TEST_ZONE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        timezone::set_current(timezone::DEFAULT_TIME_ZONE);
+        let (mut conn, worker_id, ctx) = test_db();
+        set_window(&mut conn, "10:00-11:00", &ctx);
+        let days: Vec<ScheduleDay> = (0..7)
+            .map(|day| ScheduleDay {
+                weekday: Weekday::try_from(day).unwrap(),
+                start: time("09:00"),
+                end: time("18:00"),
+                break_minutes: 60,
+            })
+            .collect();
+        let id = save_schedule(&mut conn, None, "Oficina", &days, NaiveDate::MIN, &ctx).unwrap();
+        set_default_schedule(&mut conn, id, &ctx).unwrap();
+
+        // Mid-shift (13:30 ± 60 min) starts the break.
+        scan(&mut conn, worker_id, &ctx, local(10, "09:00"));
+        let event = scan(&mut conn, worker_id, &ctx, local(10, "12:45"));
+        assert!(matches!(event, ClockEvent::BreakStarted { .. }));
+        scan(&mut conn, worker_id, &ctx, local(10, "13:30"));
+        scan(&mut conn, worker_id, &ctx, local(10, "18:00"));
+
+        // Inside the global window but outside the scheduled one.
+        scan(&mut conn, worker_id, &ctx, local(11, "09:00"));
+        let event = scan(&mut conn, worker_id, &ctx, local(11, "10:30"));
+        assert!(matches!(event, ClockEvent::ClockedOut { .. }));
+    }
+
+    #[test]
+    fn global_window_applies_when_the_shift_has_no_break() {
+        let _guard = TEST_ZONE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        timezone::set_current(timezone::DEFAULT_TIME_ZONE);
+        // The seeded default schedule has no breaks.
+        let (mut conn, worker_id, ctx) = test_db();
+        set_window(&mut conn, "10:00-11:00", &ctx);
+
+        scan(&mut conn, worker_id, &ctx, local(10, "09:00"));
+        let event = scan(&mut conn, worker_id, &ctx, local(10, "10:30"));
+        assert!(matches!(event, ClockEvent::BreakStarted { .. }));
+    }
+}
@@ -0,0 +1,264 @@
+use chrono::Local;
+use serde::Deserialize;
+use std::env;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const APP_DIR_NAME: &str = "timesheet";
+const DATA_DIR_FLAG: &str = "--data-dir";
+const DATA_DIR_ENV: &str = "TIMESHEET_DATA_DIR";
+const DATABASE_FILE: &str = "timesheet.db";
+/// Where versions before the data directory kept the database, relative to
+/// the working directory.
+const LEGACY_DATABASE: &str = "timesheet.db";
+
+static DATA_DIR: OnceLock<DataDir> = OnceLock::new();
+
+/// Where the data directory setting came from, reported at startup so it is
+/// obvious which database the kiosk is using.
+#[derive(Clone, Debug)]
+pub enum DataDirSource {
+    CliFlag,
+    Environment,
+    ConfigFile(PathBuf),
+    XdgDefault,
+}
+
+impl fmt::Display for DataDirSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataDirSource::CliFlag => write!(f, "argumento {}", DATA_DIR_FLAG),
+            DataDirSource::Environment => write!(f, "variable {}", DATA_DIR_ENV),
+            DataDirSource::ConfigFile(path) => write!(f, "archivo {}", path.display()),
+            DataDirSource::XdgDefault => write!(f, "ubicación por defecto"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    MissingFlagValue(&'static str),
+    Io(PathBuf, io::Error),
+    Json(PathBuf, serde_json::Error),
+    NoHomeDirectory,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::MissingFlagValue(flag) => write!(f, "missing value for {}", flag),
+            ConfigError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            ConfigError::Json(path, e) => {
+                write!(f, "invalid config file {}: {}", path.display(), e)
+            }
+            ConfigError::NoHomeDirectory => write!(
+                f,
+                "cannot determine a data directory: HOME is not set (use {} or {})",
+                DATA_DIR_FLAG, DATA_DIR_ENV
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// What `adopt_legacy_database` found.
+#[derive(Debug)]
+pub enum LegacyDatabase {
+    /// The data directory already had a database, or there was nothing to adopt.
+    None,
+    /// `./timesheet.db` was copied into the data directory.
+    Adopted(PathBuf),
+    /// `./timesheet.db` exists but could not be copied; the kiosk is about to
+    /// open an empty database instead.
+    Failed(PathBuf, ConfigError),
+}
+
+#[derive(Deserialize, Default)]
+struct ConfigFile {
+    #[serde(default)]
+    data_dir: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DataDir {
+    root: PathBuf,
+    source: DataDirSource,
+}
+
+impl DataDir {
+    /// Resolves the data directory from, in order: the `--data-dir` flag, the
+    /// `TIMESHEET_DATA_DIR` variable, `data_dir` in
+    /// `$XDG_CONFIG_HOME/timesheet/config.json`, and finally
+    /// `$XDG_DATA_HOME/timesheet`.
+    pub fn resolve<I>(args: I) -> Result<DataDir, ConfigError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        if let Some(root) = data_dir_from_args(args)? {
+            return Ok(DataDir {
+                root,
+                source: DataDirSource::CliFlag,
+            });
+        }
+        if let Some(root) = env::var_os(DATA_DIR_ENV).filter(|v| !v.is_empty()) {
+            return Ok(DataDir {
+                root: PathBuf::from(root),
+                source: DataDirSource::Environment,
+            });
+        }
+        if let Some(config_path) = config_file_path()
+            && config_path.is_file()
+        {
+            let contents = fs::read_to_string(&config_path)
+                .map_err(|e| ConfigError::Io(config_path.clone(), e))?;
+            let config: ConfigFile = serde_json::from_str(&contents)
+                .map_err(|e| ConfigError::Json(config_path.clone(), e))?;
+            if let Some(root) = config.data_dir {
+                return Ok(DataDir {
+                    root,
+                    source: DataDirSource::ConfigFile(config_path),
+                });
+            }
+        }
+        let base =
+            xdg_dir("XDG_DATA_HOME", &[".local", "share"]).ok_or(ConfigError::NoHomeDirectory)?;
+        Ok(DataDir {
+            root: base.join(APP_DIR_NAME),
+            source: DataDirSource::XdgDefault,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn source(&self) -> &DataDirSource {
+        &self.source
+    }
+
+    pub fn database_path(&self) -> PathBuf {
+        self.root.join(DATABASE_FILE)
+    }
+
+    pub fn reports_dir(&self) -> PathBuf {
+        self.root.join("reports")
+    }
+
+    pub fn backups_dir(&self) -> PathBuf {
+        self.root.join("backups")
+    }
+
+    pub fn logs_dir(&self) -> PathBuf {
+        self.root.join("logs")
+    }
+
+    /// On the first run with a data directory, copies the database an older
+    /// version left in the working directory so workers and history carry
+    /// over. The original file is kept so the previous version still runs.
+    pub fn adopt_legacy_database(&self) -> LegacyDatabase {
+        let legacy = PathBuf::from(LEGACY_DATABASE);
+        let target = self.database_path();
+        if target.exists() || !legacy.is_file() {
+            return LegacyDatabase::None;
+        }
+        // Copy under a temporary name so an interrupted copy never looks
+        // like a database on the next start.
+        let partial = target.with_extension("db.partial");
+        let copied = fs::copy(&legacy, &partial)
+            .and_then(|_| fs::rename(&partial, &target))
+            .map_err(|e| ConfigError::Io(legacy.clone(), e));
+        match copied {
+            Ok(()) => LegacyDatabase::Adopted(legacy),
+            Err(e) => {
+                let _ = fs::remove_file(&partial);
+                LegacyDatabase::Failed(legacy, e)
+            }
+        }
+    }
+
+    pub fn ensure_dirs(&self) -> Result<(), ConfigError> {
+        for dir in [
+            self.root.clone(),
+            self.reports_dir(),
+            self.backups_dir(),
+            self.logs_dir(),
+        ] {
+            fs::create_dir_all(&dir).map_err(|e| ConfigError::Io(dir.clone(), e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Installs the resolved data directory for the rest of the process.
+/// Only the first call has any effect.
+pub fn init(data_dir: DataDir) -> &'static DataDir {
+    DATA_DIR.get_or_init(|| data_dir)
+}
+
+/// The data directory installed by `init`. Falls back to the default
+/// resolution when called before `init` (for example from tools that only
+/// link the library).
+pub fn data_dir() -> &'static DataDir {
+    DATA_DIR.get_or_init(|| {
+        DataDir::resolve(Vec::new()).unwrap_or_else(|_| DataDir {
+            root: PathBuf::from("."),
+            source: DataDirSource::XdgDefault,
+        })
+    })
+}
+
+/// Prints `message` and appends it, timestamped, to `logs/timesheet.log`
+/// in the data directory. Logging failures never interrupt the kiosk.
+pub fn log_event(message: &str) {
+    println!("{}", message);
+    let path = data_dir().logs_dir().join("timesheet.log");
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&path) {
+        let _ = writeln!(
+            file,
+            "{} {}",
+            Local::now().format("%Y-%m-%d %H:%M:%S"),
+            message
+        );
+    }
+}
+
+fn data_dir_from_args<I>(args: I) -> Result<Option<PathBuf>, ConfigError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == DATA_DIR_FLAG {
+            let value = args
+                .next()
+                .ok_or(ConfigError::MissingFlagValue(DATA_DIR_FLAG))?;
+            return Ok(Some(PathBuf::from(value)));
+        }
+        if let Some(value) = arg.strip_prefix("--data-dir=") {
+            return Ok(Some(PathBuf::from(value)));
+        }
+    }
+    Ok(None)
+}
+
+fn config_file_path() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", &[".config"]).map(|dir| dir.join(APP_DIR_NAME).join("config.json"))
+}
+
+fn xdg_dir(variable: &str, home_fallback: &[&str]) -> Option<PathBuf> {
+    if let Some(dir) = env::var_os(variable).map(PathBuf::from)
+        && dir.is_absolute()
+    {
+        return Some(dir);
+    }
+    let home = env::var_os("HOME").filter(|v| !v.is_empty())?;
+    let mut dir = PathBuf::from(home);
+    for part in home_fallback {
+        dir.push(part);
+    }
+    Some(dir)
+}
@@ -0,0 +1,548 @@
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+
+use crate::audit::{self, AuditContext};
+use crate::db::{self, DbError, ENTRY_COLUMNS, Result, TimesheetEntry};
+use crate::timezone;
+
+/// Why a timesheet row was changed by hand. Every manual correction needs one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReasonCode {
+    Olvido,
+    ErrorLector,
+    Permiso,
+    TrabajoExterno,
+    AjusteAdministrativo,
+    Otro,
+}
+
+impl ReasonCode {
+    pub const ALL: [ReasonCode; 6] = [
+        ReasonCode::Olvido,
+        ReasonCode::ErrorLector,
+        ReasonCode::Permiso,
+        ReasonCode::TrabajoExterno,
+        ReasonCode::AjusteAdministrativo,
+        ReasonCode::Otro,
+    ];
+
+    /// Stable value stored in `timesheets.correction_reason`.
+    pub fn code(self) -> &'static str {
+        match self {
+            ReasonCode::Olvido => "olvido",
+            ReasonCode::ErrorLector => "error_lector",
+            ReasonCode::Permiso => "permiso",
+            ReasonCode::TrabajoExterno => "trabajo_externo",
+            ReasonCode::AjusteAdministrativo => "ajuste_administrativo",
+            ReasonCode::Otro => "otro",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ReasonCode::Olvido => "Olvido de marca",
+            ReasonCode::ErrorLector => "Error de lector",
+            ReasonCode::Permiso => "Permiso",
+            ReasonCode::TrabajoExterno => "Trabajo en terreno",
+            ReasonCode::AjusteAdministrativo => "Ajuste administrativo",
+            ReasonCode::Otro => "Otro",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<ReasonCode> {
+        ReasonCode::ALL.into_iter().find(|r| r.code() == code)
+    }
+}
+
+fn audit_context(ctx: &AuditContext, reason: ReasonCode, note: &str) -> AuditContext {
+    let note = note.trim();
+    let text = if note.is_empty() {
+        reason.code().to_string()
+    } else {
+        format!("{}: {}", reason.code(), note)
+    };
+    ctx.clone().with_reason(&text)
+}
+
+/// Sessions that start or end in a closed hour-bank month stay as they were.
+fn check_months_open(conn: &Connection, times: &[Option<DateTime<Utc>>]) -> Result<()> {
+    for at in times.iter().flatten() {
+        crate::hour_bank::check_open(conn, timezone::to_local(*at).date_naive())?;
+    }
+    Ok(())
+}
+
+fn require_entry(conn: &Connection, entry_id: i64) -> Result<TimesheetEntry> {
+    db::get_entry(conn, entry_id)?
+        .ok_or_else(|| DbError::Validation(format!("no existe el registro {}", entry_id)))
+}
+
+/// Rejects sessions that end before they start or overlap another session
+/// of the same worker. `exclude_id` is the row being edited, if any.
+fn validate_session(
+    conn: &Connection,
+    worker_id: i64,
+    clock_in: DateTime<Utc>,
+    clock_out: Option<DateTime<Utc>>,
+    exclude_id: Option<i64>,
+) -> Result<()> {
+    if let Some(out) = clock_out
+        && out <= clock_in
+    {
+        return Err(DbError::Validation(
+            "la salida debe ser posterior a la entrada".to_string(),
+        ));
+    }
+    let others = db::query_entries(
+        conn,
+        &format!(
+            "SELECT {} FROM timesheets WHERE worker_id = ? AND id != ?",
+            ENTRY_COLUMNS
+        ),
+        rusqlite::params![worker_id, exclude_id.unwrap_or(-1)],
+    )?;
+    let end = clock_out.unwrap_or(DateTime::<Utc>::MAX_UTC);
+    if let Some(other) = others.iter().find(|other| {
+        let other_end = other.clock_out.unwrap_or(DateTime::<Utc>::MAX_UTC);
+        other.clock_in < end && clock_in < other_end
+    }) {
+        return Err(DbError::Validation(format!(
+            "se superpone con el registro {} ({})",
+            other.id,
+            other.clock_in.to_rfc3339()
+        )));
+    }
+    Ok(())
+}
+
+/// Adds a session the worker forgot to scan. `clock_out` may be `None` to
+/// open a session, which fails if the worker already has one open.
+pub fn insert_missed_punch(
+    conn: &mut Connection,
+    worker_id: i64,
+    clock_in: DateTime<Utc>,
+    clock_out: Option<DateTime<Utc>>,
+    reason: ReasonCode,
+    note: &str,
+    ctx: &AuditContext,
+) -> Result<i64> {
+    let tx = conn.transaction()?;
+    check_months_open(&tx, &[Some(clock_in), clock_out])?;
+    validate_session(&tx, worker_id, clock_in, clock_out, None)?;
+    tx.execute(
+        "INSERT INTO timesheets (worker_id, clock_in, clock_out, correction_reason) VALUES (?, ?, ?, ?)",
+        rusqlite::params![
+            worker_id,
+            clock_in.to_rfc3339(),
+            clock_out.map(|t| t.to_rfc3339()),
+            reason.code()
+        ],
+    )?;
+    let id = tx.last_insert_rowid();
+    let after = require_entry(&tx, id)?;
+    audit::record(
+        &tx,
+        &audit_context(ctx, reason, note),
+        "timesheets",
+        Some(id),
+        "insert_missed_punch",
+        None,
+        audit::snapshot(&after),
+    )?;
+    tx.commit()?;
+    Ok(id)
+}
+
+/// Replaces the clock-in and clock-out of an existing session. Any
+/// auto-close marker is cleared since the row has now been reviewed, and
+/// breaks are trimmed to the new bounds.
+pub fn adjust_entry(
+    conn: &mut Connection,
+    entry_id: i64,
+    clock_in: DateTime<Utc>,
+    clock_out: Option<DateTime<Utc>>,
+    reason: ReasonCode,
+    note: &str,
+    ctx: &AuditContext,
+) -> Result<()> {
+    let tx = conn.transaction()?;
+    let before = require_entry(&tx, entry_id)?;
+    check_months_open(
+        &tx,
+        &[
+            Some(before.clock_in),
+            before.clock_out,
+            Some(clock_in),
+            clock_out,
+        ],
+    )?;
+    validate_session(&tx, before.worker_id, clock_in, clock_out, Some(entry_id))?;
+    // A punch the admin changed was typed in, not scanned.
+    let clock_in_badge_id = before
+        .clock_in_badge_id
+        .filter(|_| clock_in == before.clock_in);
+    let clock_out_badge_id = before
+        .clock_out_badge_id
+        .filter(|_| clock_out == before.clock_out);
+    tx.execute(
+        "UPDATE timesheets SET clock_in = ?, clock_out = ?, correction_reason = ?, auto_close = NULL,
+         clock_in_badge_id = ?, clock_out_badge_id = ? WHERE id = ?",
+        rusqlite::params![
+            clock_in.to_rfc3339(),
+            clock_out.map(|t| t.to_rfc3339()),
+            reason.code(),
+            clock_in_badge_id,
+            clock_out_badge_id,
+            entry_id
+        ],
+    )?;
+    crate::breaks::fit_breaks(
+        &tx,
+        entry_id,
+        clock_in,
+        clock_out,
+        &audit_context(ctx, reason, note),
+    )?;
+    let after = require_entry(&tx, entry_id)?;
+    audit::record(
+        &tx,
+        &audit_context(ctx, reason, note),
+        "timesheets",
+        Some(entry_id),
+        "adjust",
+        audit::snapshot(&before),
+        audit::snapshot(&after),
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Splits a session in two at `at`, for example to carve out an unrecorded
+/// exit. Returns the id of the new second half.
+pub fn split_entry(
+    conn: &mut Connection,
+    entry_id: i64,
+    at: DateTime<Utc>,
+    reason: ReasonCode,
+    note: &str,
+    ctx: &AuditContext,
+) -> Result<i64> {
+    let tx = conn.transaction()?;
+    let before = require_entry(&tx, entry_id)?;
+    check_months_open(&tx, &[Some(before.clock_in), before.clock_out])?;
+    let inside_end = before.clock_out.is_none_or(|out| at < out);
+    if at <= before.clock_in || !inside_end {
+        return Err(DbError::Validation(
+            "la hora de división debe estar dentro del registro".to_string(),
+        ));
+    }
+    // Close the first half before inserting the second so the
+    // one-open-session index never sees two open rows. The scanned
+    // clock-out now ends the second half; the split time is typed in.
+    tx.execute(
+        "UPDATE timesheets SET clock_out = ?, correction_reason = ?, auto_close = NULL,
+         clock_out_badge_id = NULL WHERE id = ?",
+        rusqlite::params![at.to_rfc3339(), reason.code(), entry_id],
+    )?;
+    tx.execute(
+        "INSERT INTO timesheets (worker_id, clock_in, clock_out, correction_reason, clock_out_badge_id)
+         VALUES (?, ?, ?, ?, ?)",
+        rusqlite::params![
+            before.worker_id,
+            at.to_rfc3339(),
+            before.clock_out.map(|t| t.to_rfc3339()),
+            reason.code(),
+            before.clock_out_badge_id
+        ],
+    )?;
+    let new_id = tx.last_insert_rowid();
+    // Breaks taken after the split belong to the second half.
+    crate::breaks::split_breaks(&tx, entry_id, new_id, at, &audit_context(ctx, reason, note))?;
+    let first = require_entry(&tx, entry_id)?;
+    let second = require_entry(&tx, new_id)?;
+    audit::record(
+        &tx,
+        &audit_context(ctx, reason, note),
+        "timesheets",
+        Some(entry_id),
+        "split",
+        audit::snapshot(&before),
+        Some(serde_json::json!([first, second])),
+    )?;
+    tx.commit()?;
+    Ok(new_id)
+}
+
+/// Removes a session entirely; the audit log keeps its last values.
+pub fn delete_entry(
+    conn: &mut Connection,
+    entry_id: i64,
+    reason: ReasonCode,
+    note: &str,
+    ctx: &AuditContext,
+) -> Result<()> {
+    let tx = conn.transaction()?;
+    let before = require_entry(&tx, entry_id)?;
+    check_months_open(&tx, &[Some(before.clock_in), before.clock_out])?;
+    tx.execute(
+        "DELETE FROM breaks WHERE timesheet_id = ?",
+        rusqlite::params![entry_id],
+    )?;
+    tx.execute(
+        "DELETE FROM timesheets WHERE id = ?",
+        rusqlite::params![entry_id],
+    )?;
+    audit::record(
+        &tx,
+        &audit_context(ctx, reason, note),
+        "timesheets",
+        Some(entry_id),
+        "delete",
+        audit::snapshot(&before),
+        None,
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::breaks::entry_breaks;
+
+    fn test_db() -> (Connection, i64, AuditContext) {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&mut conn, Path::new("backups")).unwrap();
+        let ctx = AuditContext::admin("test");
+        let worker_id = db::add_worker(&mut conn, "Prueba", "1000", &ctx).unwrap();
+        (conn, worker_id, ctx)
+    }
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 10, hour, minute, 0).unwrap()
+    }
+
+    /// A 09:00–18:00 session with breaks 12:00–13:00 and 15:00–15:30.
+    fn session_with_breaks(conn: &mut Connection, worker_id: i64, ctx: &AuditContext) -> i64 {
+        let entry_id = clock_in(conn, worker_id, ctx, at(9, 0));
+        for time in [at(12, 0), at(13, 0), at(15, 0), at(15, 30)] {
+            db::toggle_clock(conn, worker_id, None, time, true, ctx).unwrap();
+        }
+        db::toggle_clock(conn, worker_id, None, at(18, 0), false, ctx).unwrap();
+        entry_id
+    }
+
+    fn clock_in(
+        conn: &mut Connection,
+        worker_id: i64,
+        ctx: &AuditContext,
+        time: DateTime<Utc>,
+    ) -> i64 {
+        match db::toggle_clock(conn, worker_id, None, time, false, ctx).unwrap() {
+            db::ClockEvent::ClockedIn { entry_id, .. } => entry_id,
+            other => panic!("expected a clock-in, got {:?}", other),
+        }
+    }
+
+    fn spans(conn: &Connection, entry_id: i64) -> Vec<(DateTime<Utc>, Option<DateTime<Utc>>)> {
+        entry_breaks(conn, entry_id)
+            .unwrap()
+            .into_iter()
+            .map(|b| (b.start, b.end))
+            .collect()
+    }
+
+    #[test]
+    fn split_divides_a_break_that_spans_the_split() {
+        let (mut conn, worker_id, ctx) = test_db();
+        let entry_id = session_with_breaks(&mut conn, worker_id, &ctx);
+
+        let second_id = split_entry(
+            &mut conn,
+            entry_id,
+            at(12, 30),
+            ReasonCode::Olvido,
+            "",
+            &ctx,
+        )
+        .unwrap();
+
+        assert_eq!(spans(&conn, entry_id), vec![(at(12, 0), Some(at(12, 30)))]);
+        assert_eq!(
+            spans(&conn, second_id),
+            vec![(at(12, 30), Some(at(13, 0))), (at(15, 0), Some(at(15, 30)))]
+        );
+    }
+
+    #[test]
+    fn adjust_trims_breaks_to_the_new_bounds() {
+        let (mut conn, worker_id, ctx) = test_db();
+        let entry_id = session_with_breaks(&mut conn, worker_id, &ctx);
+
+        adjust_entry(
+            &mut conn,
+            entry_id,
+            at(12, 30),
+            Some(at(15, 0)),
+            ReasonCode::AjusteAdministrativo,
+            "",
+            &ctx,
+        )
+        .unwrap();
+
+        // The lunch break loses the half hour before the new clock-in and
+        // the afternoon break, now after the clock-out, is gone.
+        assert_eq!(spans(&conn, entry_id), vec![(at(12, 30), Some(at(13, 0)))]);
+    }
+
+    #[test]
+    fn closing_an_open_session_closes_its_open_break() {
+        let (mut conn, worker_id, ctx) = test_db();
+        let entry_id = clock_in(&mut conn, worker_id, &ctx, at(9, 0));
+        db::toggle_clock(&mut conn, worker_id, None, at(12, 0), true, &ctx).unwrap();
+
+        adjust_entry(
+            &mut conn,
+            entry_id,
+            at(9, 0),
+            Some(at(12, 45)),
+            ReasonCode::Olvido,
+            "",
+            &ctx,
+        )
+        .unwrap();
+
+        assert_eq!(spans(&conn, entry_id), vec![(at(12, 0), Some(at(12, 45)))]);
+    }
+
+    #[test]
+    fn corrected_punches_lose_their_badge() {
+        let (mut conn, worker_id, ctx) = test_db();
+        let badge_id: i64 = conn
+            .query_row(
+                "SELECT id FROM worker_badges WHERE worker_id = ?",
+                rusqlite::params![worker_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let scan = |conn: &mut Connection, time| {
+            db::toggle_clock(conn, worker_id, Some(badge_id), time, false, &ctx).unwrap()
+        };
+        let badges = |conn: &Connection, id| {
+            let entry = db::get_entry(conn, id).unwrap().unwrap();
+            (entry.clock_in_badge_id, entry.clock_out_badge_id)
+        };
+
+        let db::ClockEvent::ClockedIn { entry_id, .. } = scan(&mut conn, at(9, 0)) else {
+            panic!("expected a clock-in");
+        };
+        scan(&mut conn, at(18, 0));
+        assert_eq!(badges(&conn, entry_id), (Some(badge_id), Some(badge_id)));
+
+        // Only the clock-out is changed.
+        adjust_entry(
+            &mut conn,
+            entry_id,
+            at(9, 0),
+            Some(at(17, 0)),
+            ReasonCode::Olvido,
+            "",
+            &ctx,
+        )
+        .unwrap();
+        assert_eq!(badges(&conn, entry_id), (Some(badge_id), None));
+
+        let db::ClockEvent::ClockedIn { entry_id, .. } = scan(&mut conn, at(19, 0)) else {
+            panic!("expected a clock-in");
+        };
+        scan(&mut conn, at(23, 0));
+        let second_id =
+            split_entry(&mut conn, entry_id, at(21, 0), ReasonCode::Olvido, "", &ctx).unwrap();
+        assert_eq!(badges(&conn, entry_id), (Some(badge_id), None));
+        assert_eq!(badges(&conn, second_id), (None, Some(badge_id)));
+    }
+
+    #[test]
+    fn sessions_in_a_closed_month_cannot_be_corrected() {
+        let (mut conn, worker_id, ctx) = test_db();
+        let february = |day, hour| Utc.with_ymd_and_hms(2026, 2, day, hour, 0, 0).unwrap();
+        let entry_id = insert_missed_punch(
+            &mut conn,
+            worker_id,
+            february(27, 12),
+            Some(february(27, 21)),
+            ReasonCode::Olvido,
+            "",
+            &ctx,
+        )
+        .unwrap();
+        let tx = conn.transaction().unwrap();
+        crate::hour_bank::record_close(&tx, worker_id, "2026-02", 0, &ctx).unwrap();
+        tx.commit().unwrap();
+
+        let closed = |result: Result<()>| {
+            assert!(
+                matches!(&result, Err(DbError::Validation(message)) if message.contains("2026-02")),
+                "{:?}",
+                result
+            );
+        };
+        closed(
+            insert_missed_punch(
+                &mut conn,
+                worker_id,
+                february(20, 12),
+                Some(february(20, 21)),
+                ReasonCode::Olvido,
+                "",
+                &ctx,
+            )
+            .map(|_| ()),
+        );
+        // Moving the session into March still changes February.
+        closed(adjust_entry(
+            &mut conn,
+            entry_id,
+            at(12, 0),
+            Some(at(21, 0)),
+            ReasonCode::Olvido,
+            "",
+            &ctx,
+        ));
+        closed(
+            split_entry(
+                &mut conn,
+                entry_id,
+                february(27, 16),
+                ReasonCode::Permiso,
+                "",
+                &ctx,
+            )
+            .map(|_| ()),
+        );
+        closed(delete_entry(
+            &mut conn,
+            entry_id,
+            ReasonCode::Otro,
+            "",
+            &ctx,
+        ));
+        let entry = require_entry(&conn, entry_id).unwrap();
+        assert_eq!(entry.clock_out, Some(february(27, 21)));
+
+        insert_missed_punch(
+            &mut conn,
+            worker_id,
+            at(12, 0),
+            Some(at(21, 0)),
+            ReasonCode::Olvido,
+            "",
+            &ctx,
+        )
+        .unwrap();
+    }
+}
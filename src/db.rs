@@ -1,24 +1,128 @@
 use chrono::{DateTime, Datelike, NaiveDate, Utc};
-use rusqlite::{Connection, Result};
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OptionalExtension, TransactionBehavior};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
 
-use crate::utils::santiago_day_bounds_utc;
+use crate::audit::{self, AuditContext};
+use crate::breaks::PunchType;
+use crate::migrations::{self, MigrationError};
+
+use crate::utils::local_day_bounds_utc;
 
 #[allow(dead_code)]
-#[derive(Clone)]
+#[derive(Clone, Debug, Serialize)]
 pub struct Worker {
     pub id: i64,
     pub name: String,
-    pub barcode: String,
+    /// Barcode of the worker's current badge; `None` after it was revoked
+    /// without a replacement.
+    pub barcode: Option<String>,
     pub active: bool,
+    /// Start of the current employment; vacation accrues from this date.
+    pub hire_date: Option<NaiveDate>,
+    /// Years worked for previous employers, which count towards the
+    /// feriado progresivo.
+    pub prior_service_years: i64,
+    /// Chilean tax id as "12345678-5", used by payroll exports.
+    pub rut: Option<String>,
 }
 
 #[allow(dead_code)]
-#[derive(Clone)]
+#[derive(Clone, Debug, Serialize)]
 pub struct TimesheetEntry {
     pub id: i64,
     pub worker_id: i64,
     pub clock_in: DateTime<Utc>,
     pub clock_out: Option<DateTime<Utc>>,
+    /// Reason code of the last manual correction, if the row was corrected.
+    pub correction_reason: Option<String>,
+    /// Set when the end-of-day policy closed a forgotten session.
+    pub auto_close: Option<String>,
+    /// Badges scanned for each punch; `None` for punches entered by hand.
+    pub clock_in_badge_id: Option<i64>,
+    pub clock_out_badge_id: Option<i64>,
+}
+
+pub type Result<T, E = DbError> = std::result::Result<T, E>;
+
+/// A stored row whose contents cannot be interpreted. Readers skip these rows
+/// instead of failing, and remember them so the UI can point at them.
+#[derive(Clone, Debug)]
+pub struct InvalidRow {
+    pub table: &'static str,
+    pub row_id: i64,
+    pub column: &'static str,
+    pub value: String,
+    pub problem: &'static str,
+}
+
+impl fmt::Display for InvalidRow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} id {}: {} = '{}' ({})",
+            self.table, self.row_id, self.column, self.value, self.problem
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum DbError {
+    Sqlite(rusqlite::Error),
+    InvalidRow(InvalidRow),
+    Conflict(String),
+    Validation(String),
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::Sqlite(e) => write!(f, "{}", e),
+            DbError::InvalidRow(row) => write!(f, "fila inválida: {}", row),
+            DbError::Conflict(m) => write!(f, "{}", m),
+            DbError::Validation(m) => write!(f, "{}", m),
+        }
+    }
+}
+
+impl std::error::Error for DbError {}
+
+impl From<rusqlite::Error> for DbError {
+    fn from(value: rusqlite::Error) -> Self {
+        DbError::Sqlite(value)
+    }
+}
+
+impl From<InvalidRow> for DbError {
+    fn from(value: InvalidRow) -> Self {
+        DbError::InvalidRow(value)
+    }
+}
+
+/// Invalid rows seen by readers since startup (or since the last quarantine),
+/// keyed by table and row id so repeated refreshes do not pile up duplicates.
+static INVALID_ROWS: Mutex<BTreeMap<(&'static str, i64), InvalidRow>> = Mutex::new(BTreeMap::new());
+
+/// Tests that quarantine rows or look for noted ones run one at a time,
+/// since quarantining clears the process-wide list.
+#[cfg(test)]
+pub(crate) static TEST_INVALID_ROWS_LOCK: Mutex<()> = Mutex::new(());
+
+pub(crate) fn note_invalid_row(row: InvalidRow) {
+    let mut seen = INVALID_ROWS.lock().unwrap();
+    if let std::collections::btree_map::Entry::Vacant(slot) = seen.entry((row.table, row.row_id)) {
+        crate::config::log_event(&format!("Skipping invalid row: {}", row));
+        slot.insert(row);
+    }
+}
+
+/// Invalid rows that readers have skipped so far.
+pub fn invalid_rows_seen() -> Vec<InvalidRow> {
+    INVALID_ROWS.lock().unwrap().values().cloned().collect()
 }
 
 fn parse_date(date: &str) -> Option<NaiveDate> {
@@ -40,314 +144,981 @@ fn next_month_start(date: NaiveDate) -> Option<NaiveDate> {
     }
 }
 
-pub fn init_db() -> Result<Connection> {
-    let conn = Connection::open("timesheet.db")?;
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS workers (
-            id INTEGER PRIMARY KEY,
-            name TEXT NOT NULL UNIQUE,
-            barcode TEXT NOT NULL UNIQUE,
-            active BOOLEAN DEFAULT 1
-        )",
-        [],
-    )?;
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS timesheets (
-            id INTEGER PRIMARY KEY,
-            worker_id INTEGER NOT NULL,
-            clock_in TEXT NOT NULL,
-            clock_out TEXT,
-            FOREIGN KEY (worker_id) REFERENCES workers(id)
-        )",
-        [],
-    )?;
+pub fn init_db(
+    db_path: &Path,
+    backup_dir: &Path,
+) -> std::result::Result<Connection, MigrationError> {
+    let mut conn = Connection::open(db_path)?;
+    // Another kiosk process may hold the write lock briefly; wait instead of failing.
+    conn.busy_timeout(std::time::Duration::from_secs(5))?;
+    migrations::migrate(&mut conn, backup_dir)?;
     Ok(conn)
 }
 
 // Worker management
-pub fn add_worker(conn: &Connection, name: &str, barcode: &str) -> Result<i64> {
-    conn.execute(
-        "INSERT INTO workers (name, barcode) VALUES (?, ?)",
-        rusqlite::params![name, barcode],
+const WORKER_COLUMNS: &str = "id, name,
+    (SELECT barcode FROM worker_badges
+     WHERE worker_badges.worker_id = workers.id AND valid_to IS NULL
+     ORDER BY worker_badges.id DESC LIMIT 1),
+    active, hire_date, prior_service_years, rut";
+
+fn worker_from_row(row: &rusqlite::Row) -> rusqlite::Result<Worker> {
+    Ok(Worker {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        barcode: row.get(2)?,
+        active: row.get(3)?,
+        hire_date: row
+            .get::<_, Option<String>>(4)?
+            .and_then(|date| NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()),
+        prior_service_years: row.get(5)?,
+        rut: row.get(6)?,
+    })
+}
+
+pub fn add_worker(
+    conn: &mut Connection,
+    name: &str,
+    barcode: &str,
+    ctx: &AuditContext,
+) -> Result<i64> {
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT INTO workers (name) VALUES (?)",
+        rusqlite::params![name],
+    )?;
+    let id = tx.last_insert_rowid();
+    crate::badges::issue(&tx, id, barcode, Utc::now())?;
+    let after = get_worker(&tx, id)?;
+    audit::record(
+        &tx,
+        ctx,
+        "workers",
+        Some(id),
+        "create",
+        None,
+        after.as_ref().and_then(audit::snapshot),
     )?;
-    Ok(conn.last_insert_rowid())
+    tx.commit()?;
+    Ok(id)
+}
+
+pub fn get_worker(conn: &Connection, id: i64) -> Result<Option<Worker>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM workers WHERE id = ?",
+        WORKER_COLUMNS
+    ))?;
+    let mut rows = stmt.query(rusqlite::params![id])?;
+    match rows.next()? {
+        Some(row) => Ok(Some(worker_from_row(row)?)),
+        None => Ok(None),
+    }
 }
 
 pub fn get_workers(conn: &Connection) -> Result<Vec<Worker>> {
-    let mut stmt =
-        conn.prepare("SELECT id, name, barcode, active FROM workers WHERE active = 1")?;
-    let worker_iter = stmt.query_map([], |row| {
-        Ok(Worker {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            barcode: row.get(2)?,
-            active: row.get(3)?,
-        })
-    })?;
-    worker_iter.collect()
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM workers WHERE active = 1",
+        WORKER_COLUMNS
+    ))?;
+    let worker_iter = stmt.query_map([], worker_from_row)?;
+    Ok(worker_iter.collect::<rusqlite::Result<_>>()?)
 }
 
-pub fn update_worker(conn: &Connection, id: i64, name: &str, barcode: &str) -> Result<()> {
-    conn.execute(
-        "UPDATE workers SET name = ?, barcode = ? WHERE id = ?",
-        rusqlite::params![name, barcode, id],
+/// Renames a worker. When `barcode` differs from the current badge, the
+/// current badge is revoked and a new one issued, keeping the history.
+pub fn update_worker(
+    conn: &mut Connection,
+    id: i64,
+    name: &str,
+    barcode: Option<&str>,
+    ctx: &AuditContext,
+) -> Result<()> {
+    let tx = conn.transaction()?;
+    let before = get_worker(&tx, id)?
+        .ok_or_else(|| DbError::Validation(format!("no existe el trabajador {}", id)))?;
+    tx.execute(
+        "UPDATE workers SET name = ? WHERE id = ?",
+        rusqlite::params![name, id],
     )?;
+    if let Some(barcode) = barcode
+        && before.barcode.as_deref() != Some(barcode)
+    {
+        crate::badges::replace_within(&tx, id, barcode, "cambio de código", ctx)?;
+    }
+    let after = get_worker(&tx, id)?;
+    audit::record(
+        &tx,
+        ctx,
+        "workers",
+        Some(id),
+        "update",
+        audit::snapshot(&before),
+        after.as_ref().and_then(audit::snapshot),
+    )?;
+    tx.commit()?;
     Ok(())
 }
 
-#[allow(dead_code)]
-pub fn soft_delete_worker(conn: &Connection, id: i64) -> Result<()> {
-    conn.execute(
-        "UPDATE workers SET active = 0 WHERE id = ?",
-        rusqlite::params![id],
+/// Stores the employment data used for the vacation balance.
+pub fn set_worker_employment(
+    conn: &mut Connection,
+    id: i64,
+    hire_date: Option<NaiveDate>,
+    prior_service_years: i64,
+    ctx: &AuditContext,
+) -> Result<()> {
+    if prior_service_years < 0 {
+        return Err(DbError::Validation(
+            "los años con otros empleadores no pueden ser negativos".to_string(),
+        ));
+    }
+    let tx = conn.transaction()?;
+    let before = get_worker(&tx, id)?
+        .ok_or_else(|| DbError::Validation(format!("no existe el trabajador {}", id)))?;
+    tx.execute(
+        "UPDATE workers SET hire_date = ?, prior_service_years = ? WHERE id = ?",
+        rusqlite::params![hire_date.map(|d| d.to_string()), prior_service_years, id],
+    )?;
+    let after = get_worker(&tx, id)?;
+    audit::record(
+        &tx,
+        ctx,
+        "workers",
+        Some(id),
+        "update_employment",
+        audit::snapshot(&before),
+        after.as_ref().and_then(audit::snapshot),
     )?;
+    tx.commit()?;
     Ok(())
 }
 
-pub fn get_worker_by_barcode(conn: &Connection, barcode: &str) -> Result<Option<Worker>> {
-    let mut stmt = conn.prepare(
-        "SELECT id, name, barcode, active FROM workers WHERE barcode = ? AND active = 1",
+/// Stores the worker's RUT, or clears it when `rut` is blank. Accepts it
+/// with or without dots and checks the verification digit.
+pub fn set_worker_rut(conn: &mut Connection, id: i64, rut: &str, ctx: &AuditContext) -> Result<()> {
+    let rut = match rut.trim() {
+        "" => None,
+        value => Some(
+            normalize_rut(value)
+                .ok_or_else(|| DbError::Validation(format!("RUT inválido: {}", value)))?,
+        ),
+    };
+    let tx = conn.transaction()?;
+    let before = get_worker(&tx, id)?
+        .ok_or_else(|| DbError::Validation(format!("no existe el trabajador {}", id)))?;
+    if let Some(rut) = &rut {
+        let taken: Option<String> = tx
+            .query_row(
+                "SELECT name FROM workers WHERE rut = ? AND id != ?",
+                rusqlite::params![rut, id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(name) = taken {
+            return Err(DbError::Validation(format!(
+                "el RUT {} ya está asignado a {}",
+                rut, name
+            )));
+        }
+    }
+    tx.execute(
+        "UPDATE workers SET rut = ? WHERE id = ?",
+        rusqlite::params![rut, id],
     )?;
-    let mut rows = stmt.query(rusqlite::params![barcode])?;
-    if let Some(row) = rows.next()? {
-        Ok(Some(Worker {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            barcode: row.get(2)?,
-            active: row.get(3)?,
-        }))
-    } else {
-        Ok(None)
+    let after = get_worker(&tx, id)?;
+    audit::record(
+        &tx,
+        ctx,
+        "workers",
+        Some(id),
+        "update_rut",
+        audit::snapshot(&before),
+        after.as_ref().and_then(audit::snapshot),
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// "12.345.678-5" or "123456785" as "12345678-5", or `None` when the
+/// verification digit does not match.
+pub fn normalize_rut(value: &str) -> Option<String> {
+    let mut body: String = value
+        .chars()
+        .filter(|c| !matches!(c, '.' | '-' | ' '))
+        .collect::<String>()
+        .to_uppercase();
+    let check = body.pop()?;
+    let body = body.trim_start_matches('0');
+    if body.is_empty() || body.len() > 8 || !body.chars().all(|c| c.is_ascii_digit()) {
+        return None;
     }
+    let sum: u32 = body
+        .chars()
+        .rev()
+        .zip([2, 3, 4, 5, 6, 7].iter().cycle())
+        .map(|(digit, factor)| digit.to_digit(10).unwrap_or(0) * factor)
+        .sum();
+    let expected = match 11 - sum % 11 {
+        11 => '0',
+        10 => 'K',
+        digit => char::from_digit(digit, 10)?,
+    };
+    (check == expected).then(|| format!("{}-{}", body, check))
 }
 
-// Timesheet functions
-pub fn clock_in(conn: &Connection, worker_id: i64) -> Result<i64> {
-    let now = Utc::now().to_rfc3339();
-    conn.execute(
-        "INSERT INTO timesheets (worker_id, clock_in) VALUES (?, ?)",
-        rusqlite::params![worker_id, now],
+/// Every worker, including deactivated ones, ordered by name.
+pub fn get_all_workers(conn: &Connection) -> Result<Vec<Worker>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM workers ORDER BY name",
+        WORKER_COLUMNS
+    ))?;
+    let worker_iter = stmt.query_map([], worker_from_row)?;
+    Ok(worker_iter.collect::<rusqlite::Result<_>>()?)
+}
+
+/// Active workers plus anyone, active or not, with a session starting in
+/// `month` ("YYYY-MM"), so that people who left still appear in the reports
+/// for the months they worked.
+pub fn get_workers_for_month(conn: &Connection, month: &str) -> Result<Vec<Worker>> {
+    let (Some(month_start), Some(last_day)) = (
+        parse_month_start(month),
+        parse_month_start(month)
+            .and_then(next_month_start)
+            .and_then(|next| next.pred_opt()),
+    ) else {
+        return get_workers(conn);
+    };
+    get_workers_for_range(conn, month_start, last_day)
+}
+
+/// Active workers plus anyone who clocked in on local days `from` through
+/// `to` (inclusive), so former workers still appear in the periods they
+/// worked.
+pub fn get_workers_for_range(
+    conn: &Connection,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<Worker>> {
+    let (start_utc, _) = local_day_bounds_utc(from);
+    let (_, end_utc) = local_day_bounds_utc(to);
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM workers
+         WHERE active = 1
+            OR id IN (SELECT worker_id FROM timesheets WHERE clock_in >= ? AND clock_in < ?)
+         ORDER BY id",
+        WORKER_COLUMNS
+    ))?;
+    let worker_iter = stmt.query_map(
+        rusqlite::params![start_utc.to_rfc3339(), end_utc.to_rfc3339()],
+        worker_from_row,
     )?;
-    Ok(conn.last_insert_rowid())
+    Ok(worker_iter.collect::<rusqlite::Result<_>>()?)
 }
 
-pub fn clock_out(conn: &Connection, worker_id: i64) -> Result<()> {
-    let now = Utc::now().to_rfc3339();
-    conn.execute(
-        "UPDATE timesheets SET clock_out = ? WHERE worker_id = ? AND clock_out IS NULL",
-        rusqlite::params![now, worker_id],
+/// Hides a worker from the kiosk and the live lists. Their history stays
+/// and is still reported for the months they have sessions in. Refused
+/// while the worker has an open session.
+pub fn deactivate_worker(conn: &mut Connection, id: i64, ctx: &AuditContext) -> Result<()> {
+    set_worker_active(conn, id, false, ctx)
+}
+
+pub fn reactivate_worker(conn: &mut Connection, id: i64, ctx: &AuditContext) -> Result<()> {
+    set_worker_active(conn, id, true, ctx)
+}
+
+fn set_worker_active(
+    conn: &mut Connection,
+    id: i64,
+    active: bool,
+    ctx: &AuditContext,
+) -> Result<()> {
+    let tx = conn.transaction()?;
+    let before = get_worker(&tx, id)?
+        .ok_or_else(|| DbError::Validation(format!("no existe el trabajador {}", id)))?;
+    if !active && get_current_status(&tx, id)?.is_some() {
+        return Err(DbError::Validation(format!(
+            "{} tiene una sesión abierta; registre su salida antes de desactivarlo",
+            before.name
+        )));
+    }
+    tx.execute(
+        "UPDATE workers SET active = ? WHERE id = ?",
+        rusqlite::params![active, id],
+    )?;
+    let after = get_worker(&tx, id)?;
+    audit::record(
+        &tx,
+        ctx,
+        "workers",
+        Some(id),
+        if active { "reactivate" } else { "deactivate" },
+        audit::snapshot(&before),
+        after.as_ref().and_then(audit::snapshot),
     )?;
+    tx.commit()?;
     Ok(())
 }
 
-pub fn get_current_status(conn: &Connection, worker_id: i64) -> Result<Option<TimesheetEntry>> {
-    let mut stmt = conn.prepare(
-        "SELECT id, worker_id, clock_in, clock_out FROM timesheets WHERE worker_id = ? AND clock_out IS NULL ORDER BY id DESC LIMIT 1"
+/// The active worker whose current badge is `barcode`, with that badge's id.
+pub fn get_worker_by_barcode(conn: &Connection, barcode: &str) -> Result<Option<(Worker, i64)>> {
+    let Some(badge) = crate::badges::get_active_badge(conn, barcode)? else {
+        return Ok(None);
+    };
+    Ok(get_worker(conn, badge.worker_id)?
+        .filter(|worker| worker.active)
+        .map(|worker| (worker, badge.id)))
+}
+
+// Timesheet functions
+/// Outcome of a badge scan: the worker started or finished a session, or
+/// left for or came back from a break inside it.
+#[derive(Clone, Debug)]
+pub enum ClockEvent {
+    ClockedIn {
+        entry_id: i64,
+        at: DateTime<Utc>,
+    },
+    ClockedOut {
+        entry_id: i64,
+        clock_in: DateTime<Utc>,
+        at: DateTime<Utc>,
+    },
+    BreakStarted {
+        entry_id: i64,
+        break_id: i64,
+        at: DateTime<Utc>,
+    },
+    BreakEnded {
+        entry_id: i64,
+        break_id: i64,
+        at: DateTime<Utc>,
+    },
+}
+
+impl ClockEvent {
+    pub fn punch_type(&self) -> PunchType {
+        match self {
+            ClockEvent::ClockedIn { .. } => PunchType::WorkStart,
+            ClockEvent::ClockedOut { .. } => PunchType::WorkEnd,
+            ClockEvent::BreakStarted { .. } => PunchType::BreakStart,
+            ClockEvent::BreakEnded { .. } => PunchType::BreakEnd,
+        }
+    }
+}
+
+/// Records the next punch of the worker: clocks in when there is no open
+/// session; otherwise ends an open break, starts a break (see
+/// `breaks::scan_in_session`; `break_key` forces one) or clocks out.
+///
+/// The lookup and the write share one IMMEDIATE transaction, so a second
+/// process scanning the same badge waits for the write lock (see the busy
+/// timeout in `init_db`) and then sees this scan's result. The partial
+/// unique index on open sessions guarantees at most one open row per worker.
+pub fn toggle_clock(
+    conn: &mut Connection,
+    worker_id: i64,
+    badge_id: Option<i64>,
+    at: DateTime<Utc>,
+    break_key: bool,
+    ctx: &AuditContext,
+) -> Result<ClockEvent> {
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let open = query_entries(
+        &tx,
+        &format!(
+            "SELECT {} FROM timesheets WHERE worker_id = ? AND clock_out IS NULL ORDER BY id DESC",
+            ENTRY_COLUMNS
+        ),
+        rusqlite::params![worker_id],
     )?;
-    let mut rows = stmt.query(rusqlite::params![worker_id])?;
-    if let Some(row) = rows.next()? {
-        Ok(Some(TimesheetEntry {
+    if open.is_empty() && break_key {
+        return Err(DbError::Validation(
+            "no hay una jornada abierta para registrar la colación".to_string(),
+        ));
+    }
+    let event = match open.into_iter().next() {
+        Some(entry) => {
+            if let Some((punch, b)) =
+                crate::breaks::scan_in_session(&tx, &entry, badge_id, at, break_key, ctx)?
+            {
+                let event = if punch == PunchType::BreakStart {
+                    ClockEvent::BreakStarted {
+                        entry_id: entry.id,
+                        break_id: b.id,
+                        at: b.start,
+                    }
+                } else {
+                    ClockEvent::BreakEnded {
+                        entry_id: entry.id,
+                        break_id: b.id,
+                        at: b.end.unwrap_or(at),
+                    }
+                };
+                tx.commit()?;
+                return Ok(event);
+            }
+            // Never close a session before it started, even if the clock drifted.
+            let at = at.max(entry.clock_in);
+            tx.execute(
+                "UPDATE timesheets SET clock_out = ?, clock_out_badge_id = ? WHERE id = ? AND clock_out IS NULL",
+                rusqlite::params![at.to_rfc3339(), badge_id, entry.id],
+            )?;
+            let after = TimesheetEntry {
+                clock_out: Some(at),
+                clock_out_badge_id: badge_id,
+                ..entry.clone()
+            };
+            audit::record(
+                &tx,
+                ctx,
+                "timesheets",
+                Some(entry.id),
+                "clock_out",
+                audit::snapshot(&entry),
+                audit::snapshot(&after),
+            )?;
+            ClockEvent::ClockedOut {
+                entry_id: entry.id,
+                clock_in: entry.clock_in,
+                at,
+            }
+        }
+        None => {
+            tx.execute(
+                "INSERT INTO timesheets (worker_id, clock_in, clock_in_badge_id) VALUES (?, ?, ?)",
+                rusqlite::params![worker_id, at.to_rfc3339(), badge_id],
+            )
+            .map_err(|e| open_session_conflict(e, worker_id))?;
+            let entry = TimesheetEntry {
+                id: tx.last_insert_rowid(),
+                worker_id,
+                clock_in: at,
+                clock_out: None,
+                correction_reason: None,
+                auto_close: None,
+                clock_in_badge_id: badge_id,
+                clock_out_badge_id: None,
+            };
+            audit::record(
+                &tx,
+                ctx,
+                "timesheets",
+                Some(entry.id),
+                "clock_in",
+                None,
+                audit::snapshot(&entry),
+            )?;
+            ClockEvent::ClockedIn {
+                entry_id: entry.id,
+                at,
+            }
+        }
+    };
+    tx.commit()?;
+    Ok(event)
+}
+
+/// Turns a violation of the one-open-session index into a readable error.
+/// This only happens when the open row exists but could not be parsed.
+fn open_session_conflict(error: rusqlite::Error, worker_id: i64) -> DbError {
+    match error {
+        rusqlite::Error::SqliteFailure(ref e, _)
+            if e.code == rusqlite::ErrorCode::ConstraintViolation =>
+        {
+            DbError::Conflict(format!(
+                "el trabajador {} ya tiene una sesión abierta ilegible; revise los registros inválidos",
+                worker_id
+            ))
+        }
+        other => other.into(),
+    }
+}
+
+/// A `timesheets` row as stored, before its timestamps are interpreted.
+struct RawEntry {
+    id: i64,
+    worker_id: i64,
+    clock_in: Option<String>,
+    clock_out: Option<String>,
+    correction_reason: Option<String>,
+    auto_close: Option<String>,
+    clock_in_badge_id: Option<i64>,
+    clock_out_badge_id: Option<i64>,
+}
+
+pub(crate) const ENTRY_COLUMNS: &str = "id, worker_id, clock_in, clock_out, correction_reason, auto_close, clock_in_badge_id, clock_out_badge_id";
+
+impl RawEntry {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<RawEntry> {
+        Ok(RawEntry {
             id: row.get(0)?,
             worker_id: row.get(1)?,
-            clock_in: DateTime::parse_from_rfc3339(&row.get::<_, String>(2)?)
-                .expect("Invalid time")
-                .with_timezone(&Utc),
-            clock_out: None,
-        }))
-    } else {
-        Ok(None)
+            clock_in: text_column(row, 2)?,
+            clock_out: text_column(row, 3)?,
+            correction_reason: text_column(row, 4)?,
+            auto_close: text_column(row, 5)?,
+            clock_in_badge_id: row.get(6)?,
+            clock_out_badge_id: row.get(7)?,
+        })
+    }
+
+    fn parse(&self) -> std::result::Result<TimesheetEntry, InvalidRow> {
+        let clock_in = parse_timestamp(self.id, "clock_in", self.clock_in.as_deref())?;
+        let clock_out = match self.clock_out.as_deref() {
+            Some(value) => Some(parse_timestamp(self.id, "clock_out", Some(value))?),
+            None => None,
+        };
+        if let Some(out) = clock_out
+            && out < clock_in
+        {
+            return Err(InvalidRow {
+                table: "timesheets",
+                row_id: self.id,
+                column: "clock_out",
+                value: self.clock_out.clone().unwrap_or_default(),
+                problem: "salida anterior a la entrada",
+            });
+        }
+        Ok(TimesheetEntry {
+            id: self.id,
+            worker_id: self.worker_id,
+            clock_in,
+            clock_out,
+            correction_reason: self.correction_reason.clone(),
+            auto_close: self.auto_close.clone(),
+            clock_in_badge_id: self.clock_in_badge_id,
+            clock_out_badge_id: self.clock_out_badge_id,
+        })
     }
 }
 
-// Reporting functions
-pub fn get_daily_timesheet_entries(
+/// Reads a column as text whatever its storage class, so that a stray
+/// integer or blob is reported as an invalid value rather than a query error.
+pub(crate) fn text_column(row: &rusqlite::Row, idx: usize) -> rusqlite::Result<Option<String>> {
+    Ok(match row.get_ref(idx)? {
+        ValueRef::Null => None,
+        ValueRef::Integer(i) => Some(i.to_string()),
+        ValueRef::Real(r) => Some(r.to_string()),
+        ValueRef::Text(t) | ValueRef::Blob(t) => Some(String::from_utf8_lossy(t).into_owned()),
+    })
+}
+
+fn parse_timestamp(
+    row_id: i64,
+    column: &'static str,
+    value: Option<&str>,
+) -> std::result::Result<DateTime<Utc>, InvalidRow> {
+    let invalid = |value: &str, problem| InvalidRow {
+        table: "timesheets",
+        row_id,
+        column,
+        value: value.to_string(),
+        problem,
+    };
+    let value = value.ok_or_else(|| invalid("NULL", "valor vacío"))?;
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|_| invalid(value, "fecha inválida"))
+}
+
+/// Runs an entry query and returns the rows that parse, noting the rest.
+pub(crate) fn query_entries<P: rusqlite::Params>(
     conn: &Connection,
-    worker_id: i64,
-    date: &str,
+    sql: &str,
+    params: P,
 ) -> Result<Vec<TimesheetEntry>> {
-    let Some(date) = parse_date(date) else {
-        return Ok(Vec::new());
-    };
-    let (start_utc, end_utc) = santiago_day_bounds_utc(date);
-    let mut stmt = conn.prepare(
-        "SELECT id, worker_id, clock_in, clock_out FROM timesheets WHERE worker_id = ? AND clock_in >= ? AND clock_in < ? ORDER BY clock_in",
-    )?;
-    let entry_iter = stmt.query_map(
-        rusqlite::params![worker_id, start_utc.to_rfc3339(), end_utc.to_rfc3339()],
-        |row| {
-            let clock_out: Option<String> = row.get(3)?;
-            Ok(TimesheetEntry {
-                id: row.get(0)?,
-                worker_id: row.get(1)?,
-                clock_in: DateTime::parse_from_rfc3339(&row.get::<_, String>(2)?)
-                    .expect("Invalid time")
-                    .with_timezone(&Utc),
-                clock_out: if let Some(out) = clock_out {
-                    Some(
-                        DateTime::parse_from_rfc3339(&out)
-                            .expect("Invalid time")
-                            .with_timezone(&Utc),
-                    )
-                } else {
-                    None
-                },
-            })
-    })?;
-    entry_iter.collect()
+    let mut stmt = conn.prepare(sql)?;
+    let raw_rows = stmt
+        .query_map(params, RawEntry::from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(raw_rows
+        .iter()
+        .filter_map(|raw| match raw.parse() {
+            Ok(entry) => Some(entry),
+            Err(invalid) => {
+                note_invalid_row(invalid);
+                None
+            }
+        })
+        .collect())
 }
 
-pub fn get_daily_hours(conn: &Connection, worker_id: i64, date: &str) -> Result<f64> {
-    let entries = get_daily_timesheet_entries(conn, worker_id, date)?;
-    let mut total_hours = 0.0;
+fn sum_hours(entries: &[TimesheetEntry], include_open: bool) -> f64 {
     let now = Utc::now();
+    let mut total_hours = 0.0;
     for entry in entries {
         if let Some(out_time) = entry.clock_out {
             total_hours += (out_time - entry.clock_in).num_seconds() as f64 / 3600.0;
-        } else {
+        } else if include_open {
             // Ongoing session
             total_hours += (now - entry.clock_in).num_seconds() as f64 / 3600.0;
         }
     }
-    Ok(total_hours)
+    total_hours
+}
+
+fn entries_between(
+    conn: &Connection,
+    worker_id: i64,
+    start_utc: DateTime<Utc>,
+    end_utc: DateTime<Utc>,
+) -> Result<Vec<TimesheetEntry>> {
+    query_entries(
+        conn,
+        &format!(
+            "SELECT {} FROM timesheets WHERE worker_id = ? AND clock_in >= ? AND clock_in < ? ORDER BY clock_in",
+            ENTRY_COLUMNS
+        ),
+        rusqlite::params![worker_id, start_utc.to_rfc3339(), end_utc.to_rfc3339()],
+    )
+}
+
+pub fn get_entry(conn: &Connection, entry_id: i64) -> Result<Option<TimesheetEntry>> {
+    let entries = query_entries(
+        conn,
+        &format!("SELECT {} FROM timesheets WHERE id = ?", ENTRY_COLUMNS),
+        rusqlite::params![entry_id],
+    )?;
+    Ok(entries.into_iter().next())
+}
+
+pub fn get_current_status(conn: &Connection, worker_id: i64) -> Result<Option<TimesheetEntry>> {
+    let entries = query_entries(
+        conn,
+        &format!(
+            "SELECT {} FROM timesheets WHERE worker_id = ? AND clock_out IS NULL ORDER BY id DESC",
+            ENTRY_COLUMNS
+        ),
+        rusqlite::params![worker_id],
+    )?;
+    Ok(entries.into_iter().next())
+}
+
+// Reporting functions
+/// Sessions on the local day `date`, including those that started the day
+/// before and ran past midnight.
+pub fn get_daily_timesheet_entries(
+    conn: &Connection,
+    worker_id: i64,
+    date: &str,
+) -> Result<Vec<TimesheetEntry>> {
+    let Some(date) = parse_date(date) else {
+        return Ok(Vec::new());
+    };
+    get_timesheet_entries_overlapping(conn, worker_id, date, date)
+}
+
+pub fn get_daily_hours(conn: &Connection, worker_id: i64, date: &str) -> Result<f64> {
+    let Some(date) = parse_date(date) else {
+        return Ok(0.0);
+    };
+    crate::timekeeping::worked_hours_between(conn, worker_id, date, date)
 }
 
 #[allow(dead_code)]
 pub fn get_total_hours(conn: &Connection, worker_id: i64) -> Result<f64> {
-    let mut stmt =
-        conn.prepare("SELECT clock_in, clock_out FROM timesheets WHERE worker_id = ?")?;
-    let mut rows = stmt.query(rusqlite::params![worker_id])?;
-    let mut total_hours = 0.0;
-    while let Some(row) = rows.next()? {
-        let clock_in: String = row.get(0)?;
-        let clock_out: Option<String> = row.get(1)?;
-        if let Some(out) = clock_out {
-            let in_time = DateTime::parse_from_rfc3339(&clock_in)
-                .expect("Invalid time")
-                .with_timezone(&Utc);
-            let out_time = DateTime::parse_from_rfc3339(&out)
-                .expect("Invalid time")
-                .with_timezone(&Utc);
-            total_hours += (out_time - in_time).num_seconds() as f64 / 3600.0;
-        }
-    }
-    Ok(total_hours)
+    let entries = query_entries(
+        conn,
+        &format!(
+            "SELECT {} FROM timesheets WHERE worker_id = ?",
+            ENTRY_COLUMNS
+        ),
+        rusqlite::params![worker_id],
+    )?;
+    Ok(sum_hours(&entries, false))
 }
 
 pub fn get_weekly_hours(
     conn: &Connection,
     worker_id: i64,
-    start_date: &str,
-    end_date: &str,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
 ) -> Result<f64> {
-    let (Some(start_date), Some(end_date)) = (parse_date(start_date), parse_date(end_date)) else {
-        return Ok(0.0);
-    };
-    let (start_utc, _) = santiago_day_bounds_utc(start_date);
-    let (_, end_utc) = santiago_day_bounds_utc(end_date);
-    let mut stmt = conn.prepare(
-        "SELECT clock_in, clock_out FROM timesheets WHERE worker_id = ? AND clock_in >= ? AND clock_in < ?"
-    )?;
-    let mut rows = stmt.query(rusqlite::params![
-        worker_id,
-        start_utc.to_rfc3339(),
-        end_utc.to_rfc3339()
-    ])?;
-    let mut total_hours = 0.0;
-    let now = Utc::now();
-    while let Some(row) = rows.next()? {
-        let clock_in: String = row.get(0)?;
-        let clock_out: Option<String> = row.get(1)?;
-        let in_time = DateTime::parse_from_rfc3339(&clock_in)
-            .expect("Invalid time")
-            .with_timezone(&Utc);
-        if let Some(out) = clock_out {
-            let out_time = DateTime::parse_from_rfc3339(&out)
-                .expect("Invalid time")
-                .with_timezone(&Utc);
-            total_hours += (out_time - in_time).num_seconds() as f64 / 3600.0;
-        } else {
-            // Ongoing session
-            total_hours += (now - in_time).num_seconds() as f64 / 3600.0;
-        }
-    }
-    Ok(total_hours)
+    crate::timekeeping::worked_hours_between(conn, worker_id, start_date, end_date)
+}
+
+/// Entries that started on local days `from` through `to` (inclusive).
+pub fn get_timesheet_entries_between(
+    conn: &Connection,
+    worker_id: i64,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<TimesheetEntry>> {
+    let (start_utc, _) = local_day_bounds_utc(from);
+    let (_, end_utc) = local_day_bounds_utc(to);
+    entries_between(conn, worker_id, start_utc, end_utc)
+}
+
+/// Sessions that overlap local days `from` through `to` (inclusive): those
+/// that started in the range and those still running into it.
+pub fn get_timesheet_entries_overlapping(
+    conn: &Connection,
+    worker_id: i64,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<TimesheetEntry>> {
+    let (start_utc, _) = local_day_bounds_utc(from);
+    let (_, end_utc) = local_day_bounds_utc(to);
+    query_entries(
+        conn,
+        &format!(
+            "SELECT {} FROM timesheets
+             WHERE worker_id = ? AND clock_in < ? AND (clock_out IS NULL OR clock_out > ?)
+             ORDER BY clock_in",
+            ENTRY_COLUMNS
+        ),
+        rusqlite::params![worker_id, end_utc.to_rfc3339(), start_utc.to_rfc3339()],
+    )
 }
 
 pub fn get_monthly_hours(conn: &Connection, worker_id: i64, month: &str) -> Result<f64> {
-    let Some(month_start) = parse_month_start(month) else {
-        return Ok(0.0);
-    };
-    let Some(next_month) = next_month_start(month_start) else {
+    let (Some(month_start), Some(last_day)) = (
+        parse_month_start(month),
+        parse_month_start(month)
+            .and_then(next_month_start)
+            .and_then(|next| next.pred_opt()),
+    ) else {
         return Ok(0.0);
     };
-    let (start_utc, _) = santiago_day_bounds_utc(month_start);
-    let (end_utc, _) = santiago_day_bounds_utc(next_month);
-    let mut stmt = conn.prepare(
-        "SELECT clock_in, clock_out FROM timesheets WHERE worker_id = ? AND clock_in >= ? AND clock_in < ?"
-    )?;
-    let mut rows = stmt.query(rusqlite::params![
-        worker_id,
-        start_utc.to_rfc3339(),
-        end_utc.to_rfc3339()
-    ])?;
-    let mut total_hours = 0.0;
-    let now = Utc::now();
-    while let Some(row) = rows.next()? {
-        let clock_in: String = row.get(0)?;
-        let clock_out: Option<String> = row.get(1)?;
-        let in_time = DateTime::parse_from_rfc3339(&clock_in)
-            .expect("Invalid time")
-            .with_timezone(&Utc);
-        if let Some(out) = clock_out {
-            let out_time = DateTime::parse_from_rfc3339(&out)
-                .expect("Invalid time")
-                .with_timezone(&Utc);
-            total_hours += (out_time - in_time).num_seconds() as f64 / 3600.0;
-        } else {
-            // Ongoing session
-            total_hours += (now - in_time).num_seconds() as f64 / 3600.0;
-        }
-    }
-    Ok(total_hours)
+    crate::timekeeping::worked_hours_between(conn, worker_id, month_start, last_day)
 }
 
+/// Sessions that overlap the month, see `get_timesheet_entries_overlapping`.
 pub fn get_monthly_timesheet_entries(
     conn: &Connection,
     worker_id: i64,
     month: &str,
 ) -> Result<Vec<TimesheetEntry>> {
-    let Some(month_start) = parse_month_start(month) else {
+    let (Some(month_start), Some(last_day)) = (
+        parse_month_start(month),
+        parse_month_start(month)
+            .and_then(next_month_start)
+            .and_then(|next| next.pred_opt()),
+    ) else {
         return Ok(Vec::new());
     };
-    let Some(next_month) = next_month_start(month_start) else {
-        return Ok(Vec::new());
-    };
-    let (start_utc, _) = santiago_day_bounds_utc(month_start);
-    let (end_utc, _) = santiago_day_bounds_utc(next_month);
-    let mut stmt = conn.prepare(
-        "SELECT id, worker_id, clock_in, clock_out FROM timesheets WHERE worker_id = ? AND clock_in >= ? AND clock_in < ? ORDER BY clock_in",
-    )?;
-    let entry_iter = stmt.query_map(
-        rusqlite::params![worker_id, start_utc.to_rfc3339(), end_utc.to_rfc3339()],
-        |row| {
-            let clock_out: Option<String> = row.get(3)?;
-            Ok(TimesheetEntry {
-                id: row.get(0)?,
-                worker_id: row.get(1)?,
-                clock_in: DateTime::parse_from_rfc3339(&row.get::<_, String>(2)?)
-                    .expect("Invalid time")
-                    .with_timezone(&Utc),
-                clock_out: if let Some(out) = clock_out {
-                    Some(
-                        DateTime::parse_from_rfc3339(&out)
-                            .expect("Invalid time")
-                            .with_timezone(&Utc),
-                    )
-                } else {
-                    None
+    get_timesheet_entries_overlapping(conn, worker_id, month_start, last_day)
+}
+
+// Data integrity
+/// Checks every timesheet and break row and returns those that cannot be
+/// interpreted.
+pub fn scan_invalid_rows(conn: &Connection) -> Result<Vec<InvalidRow>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM timesheets ORDER BY id",
+        ENTRY_COLUMNS
+    ))?;
+    let raw_rows = stmt
+        .query_map([], RawEntry::from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let mut invalid: Vec<InvalidRow> = raw_rows
+        .iter()
+        .filter_map(|raw| raw.parse().err())
+        .collect();
+    invalid.extend(crate::breaks::invalid_breaks(conn)?);
+    for row in &invalid {
+        note_invalid_row(row.clone());
+    }
+    Ok(invalid)
+}
+
+/// Moves every invalid timesheet row into `timesheets_quarantine` and every
+/// invalid break into `breaks_quarantine`, keeping the original values and
+/// the reason, and returns how many rows were moved. Breaks recorded inside
+/// a quarantined timesheet row are deleted with it.
+pub fn quarantine_invalid_rows(conn: &mut Connection, ctx: &AuditContext) -> Result<usize> {
+    let invalid = scan_invalid_rows(conn)?;
+    let tx = conn.transaction()?;
+    let now = Utc::now().to_rfc3339();
+    let mut moved = 0;
+    for row in invalid.iter().filter(|row| row.table == "timesheets") {
+        tx.execute(
+            "INSERT INTO timesheets_quarantine (original_id, worker_id, clock_in, clock_out, reason, quarantined_at)
+             SELECT id, worker_id, clock_in, clock_out, ?, ? FROM timesheets WHERE id = ?",
+            rusqlite::params![row.to_string(), now, row.row_id],
+        )?;
+        let mut before = tx.query_row(
+            "SELECT worker_id, clock_in, clock_out FROM timesheets WHERE id = ?",
+            rusqlite::params![row.row_id],
+            |r| {
+                Ok(serde_json::json!({
+                    "id": row.row_id,
+                    "worker_id": r.get::<_, Option<i64>>(0)?,
+                    "clock_in": text_column(r, 1)?,
+                    "clock_out": text_column(r, 2)?,
+                }))
+            },
+        )?;
+        // The row's breaks go with it; the audit entry keeps their values.
+        let breaks = tx
+            .prepare(
+                "SELECT id, break_start, break_end, paid FROM breaks
+                 WHERE timesheet_id = ? ORDER BY id",
+            )?
+            .query_map(rusqlite::params![row.row_id], |r| {
+                Ok(serde_json::json!({
+                    "id": r.get::<_, i64>(0)?,
+                    "break_start": text_column(r, 1)?,
+                    "break_end": text_column(r, 2)?,
+                    "paid": r.get::<_, Option<bool>>(3)?,
+                }))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        before["breaks"] = serde_json::Value::Array(breaks);
+        tx.execute(
+            "DELETE FROM breaks WHERE timesheet_id = ?",
+            rusqlite::params![row.row_id],
+        )?;
+        tx.execute(
+            "DELETE FROM timesheets WHERE id = ?",
+            rusqlite::params![row.row_id],
+        )?;
+        audit::record(
+            &tx,
+            ctx,
+            "timesheets",
+            Some(row.row_id),
+            "quarantine",
+            Some(before),
+            None,
+        )?;
+        moved += 1;
+    }
+    for row in invalid.iter().filter(|row| row.table == "breaks") {
+        // Already gone if its session was quarantined above.
+        let before = tx
+            .query_row(
+                "SELECT timesheet_id, break_start, break_end, paid FROM breaks WHERE id = ?",
+                rusqlite::params![row.row_id],
+                |r| {
+                    Ok(serde_json::json!({
+                        "id": row.row_id,
+                        "timesheet_id": r.get::<_, Option<i64>>(0)?,
+                        "break_start": text_column(r, 1)?,
+                        "break_end": text_column(r, 2)?,
+                        "paid": text_column(r, 3)?,
+                    }))
                 },
-            })
-        },
-    )?;
-    entry_iter.collect()
+            )
+            .optional()?;
+        let Some(before) = before else {
+            continue;
+        };
+        tx.execute(
+            "INSERT INTO breaks_quarantine (original_id, timesheet_id, break_start, break_end, paid, reason, quarantined_at)
+             SELECT id, timesheet_id, break_start, break_end, paid, ?, ? FROM breaks WHERE id = ?",
+            rusqlite::params![row.to_string(), now, row.row_id],
+        )?;
+        tx.execute(
+            "DELETE FROM breaks WHERE id = ?",
+            rusqlite::params![row.row_id],
+        )?;
+        audit::record(
+            &tx,
+            ctx,
+            "breaks",
+            Some(row.row_id),
+            "quarantine",
+            Some(before),
+            None,
+        )?;
+        moved += 1;
+    }
+    tx.commit()?;
+    INVALID_ROWS
+        .lock()
+        .unwrap()
+        .retain(|(table, _), _| !matches!(*table, "timesheets" | "breaks"));
+    Ok(moved)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn test_db() -> (Connection, i64, AuditContext) {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrations::migrate(&mut conn, Path::new("backups")).unwrap();
+        let ctx = AuditContext::admin("test");
+        let worker_id = add_worker(&mut conn, "Prueba", "1000", &ctx).unwrap();
+        (conn, worker_id, ctx)
+    }
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 4, hour, 0, 0).unwrap()
+    }
+
+    fn open_rows(conn: &Connection, worker_id: i64) -> i64 {
+        conn.query_row(
+            "SELECT COUNT(*) FROM timesheets WHERE worker_id = ? AND clock_out IS NULL",
+            rusqlite::params![worker_id],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn a_second_scan_closes_the_open_session() {
+        let (mut conn, worker_id, ctx) = test_db();
+        let ClockEvent::ClockedIn { entry_id, .. } =
+            toggle_clock(&mut conn, worker_id, None, at(12), false, &ctx).unwrap()
+        else {
+            panic!("expected a clock-in");
+        };
+        assert_eq!(open_rows(&conn, worker_id), 1);
+
+        match toggle_clock(&mut conn, worker_id, None, at(20), false, &ctx).unwrap() {
+            ClockEvent::ClockedOut {
+                entry_id: closed, ..
+            } => assert_eq!(closed, entry_id),
+            other => panic!("expected a clock-out, got {:?}", other),
+        }
+        assert_eq!(open_rows(&conn, worker_id), 0);
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM timesheets", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 1);
+    }
+
+    #[test]
+    fn the_index_rejects_a_second_open_session() {
+        let (mut conn, worker_id, ctx) = test_db();
+        toggle_clock(&mut conn, worker_id, None, at(12), false, &ctx).unwrap();
+        let duplicate = conn.execute(
+            "INSERT INTO timesheets (worker_id, clock_in) VALUES (?, ?)",
+            rusqlite::params![worker_id, at(13).to_rfc3339()],
+        );
+        assert!(matches!(
+            duplicate,
+            Err(rusqlite::Error::SqliteFailure(ref e, _))
+                if e.code == rusqlite::ErrorCode::ConstraintViolation
+        ));
+        assert_eq!(open_rows(&conn, worker_id), 1);
+
+        // A closed row for the same worker is fine.
+        conn.execute(
+            "INSERT INTO timesheets (worker_id, clock_in, clock_out) VALUES (?, ?, ?)",
+            rusqlite::params![worker_id, at(8).to_rfc3339(), at(10).to_rfc3339()],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn an_unreadable_open_session_is_a_conflict_not_a_second_row() {
+        let (mut conn, worker_id, ctx) = test_db();
+        conn.execute(
+            "INSERT INTO timesheets (worker_id, clock_in) VALUES (?, 'ayer')",
+            rusqlite::params![worker_id],
+        )
+        .unwrap();
+        // Readers skip the row, so the scan tries to open a new session.
+        assert!(get_current_status(&conn, worker_id).unwrap().is_none());
+        match toggle_clock(&mut conn, worker_id, None, at(12), false, &ctx) {
+            Err(DbError::Conflict(message)) => assert!(message.contains("ilegible")),
+            other => panic!("expected a conflict, got {:?}", other),
+        }
+        assert_eq!(open_rows(&conn, worker_id), 1);
+    }
 }
@@ -1,5 +1,4 @@
-use chrono::{Datelike, Timelike};
-use chrono_tz::America::Santiago;
+use chrono::{Datelike, NaiveDate, NaiveTime, Timelike, Weekday};
 use std::cell::RefCell;
 use std::fmt;
 use std::fs;
@@ -10,9 +9,13 @@ use std::rc::Rc;
 
 use serde::Deserialize;
 
+use crate::audit::{self, AuditContext};
+use crate::payroll_export::{Encoding, ExportFormat, HoursFormat, Separator};
+use crate::periods::ReportRange;
 use crate::{db, reports};
-use crate::utils::santiago_today_naive;
-use slint::ComponentHandle;
+use crate::timezone;
+use crate::utils::local_today_naive;
+use slint::{ComponentHandle, Model};
 
 static LAST_SCAN_TIME: std::sync::Mutex<Option<chrono::DateTime<chrono::Utc>>> =
     std::sync::Mutex::new(None);
@@ -34,7 +37,7 @@ pub fn setup_event_handlers(conn: Rc<RefCell<rusqlite::Connection>>, ui: &crate:
     let ui_handle_open_dir = ui_handle.clone();
 
     ui.on_barcode_scanned(move |barcode_str| {
-        println!("Barcode scanned callback triggered with: '{}'", barcode_str);
+        crate::config::log_event(&format!("Barcode scanned: '{}'", barcode_str));
         let trimmed_barcode = crate::barcode::normalize(&barcode_str);
         // Check if scan should be ignored: too fast AND same barcode as last
         let now = chrono::Utc::now();
@@ -46,75 +49,77 @@ pub fn setup_event_handlers(conn: Rc<RefCell<rusqlite::Connection>>, ui: &crate:
                 && now.signed_duration_since(last_time) < chrono::Duration::seconds(2)
                 && *last_barcode == trimmed_barcode
             {
-                println!("Scan ignored - too soon after last scan and same barcode");
+                crate::config::log_event("Scan ignored - too soon after last scan and same barcode");
                 return;
             }
             *last_scan_time = Some(now);
             *last_scan_barcode = Some(trimmed_barcode.to_string());
         }
 
-        let conn = conn_clone2.borrow();
+        // A session forgotten yesterday must not turn this scan into a clock-out.
+        if let Err(e) = crate::auto_close::run(&mut conn_clone2.borrow_mut()) {
+            crate::config::log_event(&format!("Error auto-closing sessions: {}", e));
+        }
+
         println!("Looking up worker with barcode: '{}'", trimmed_barcode);
-        let worker_result = db::get_worker_by_barcode(&conn, &trimmed_barcode);
+        let worker_result = db::get_worker_by_barcode(&conn_clone2.borrow(), &trimmed_barcode);
         match worker_result {
-            Ok(Some(worker)) => {
+            Ok(Some((worker, badge_id))) => {
                 println!("Worker found: {} (ID: {})", worker.name, worker.id);
-                let status_result = db::get_current_status(&conn, worker.id);
-                match status_result {
-                    Ok(Some(_)) => {
-                        // Worker is currently clocked in, perform clock out
-                        if let Err(e) = db::clock_out(&conn, worker.id) {
-                            if let Some(ui) = ui_handle_barcode.upgrade() {
-                                ui.set_error_dialog_message(
-                                    format!("Error al marcar salida: {}", e).into(),
-                                );
-                                ui.set_show_error_dialog(true);
-                                ui.set_trigger_error_dialog_show(true);
-                            }
-                            return;
-                        }
-                        // Show notification
-                        if let Some(ui) = ui_handle_barcode.upgrade() {
-                            println!("Showing notification dialog for clock out: {}", worker.name);
-                            ui.set_confirm_worker_name(worker.name.into());
-                            ui.set_confirm_action("Salida registrada".into());
-                            ui.set_confirm_is_check_in(false);
-                            ui.set_show_confirm_dialog(true);
-                            ui.set_trigger_dialog_show(true);
-                        }
+                // The break key applies to this scan only.
+                let break_key = match ui_handle_barcode.upgrade() {
+                    Some(ui) => {
+                        let armed = ui.get_break_key_armed();
+                        ui.set_break_key_armed(false);
+                        armed
                     }
-                    Ok(None) => {
-                        // Worker is not clocked in, perform clock in
-                        if let Err(e) = db::clock_in(&conn, worker.id) {
-                            if let Some(ui) = ui_handle_barcode.upgrade() {
-                                ui.set_error_dialog_message(
-                                    format!("Error al marcar entrada: {}", e).into(),
-                                );
-                                ui.set_show_error_dialog(true);
-                                ui.set_trigger_error_dialog_show(true);
-                            }
-                            return;
-                        }
-                        // Show notification
-                        if let Some(ui) = ui_handle_barcode.upgrade() {
-                            println!("Showing notification dialog for clock in: {}", worker.name);
-                            ui.set_confirm_worker_name(worker.name.into());
-                            ui.set_confirm_action("Entrada registrada".into());
-                            ui.set_confirm_is_check_in(true);
-                            ui.set_show_confirm_dialog(true);
-                            ui.set_trigger_dialog_show(true);
-                        }
+                    None => false,
+                };
+                let toggle_result = db::toggle_clock(
+                    &mut conn_clone2.borrow_mut(),
+                    worker.id,
+                    Some(badge_id),
+                    now,
+                    break_key,
+                    &AuditContext::kiosk(&worker.name),
+                );
+                let (action, is_check_in) = match toggle_result {
+                    Ok(event) => {
+                        let entry_id = match event {
+                            db::ClockEvent::ClockedIn { entry_id, .. }
+                            | db::ClockEvent::ClockedOut { entry_id, .. }
+                            | db::ClockEvent::BreakStarted { entry_id, .. }
+                            | db::ClockEvent::BreakEnded { entry_id, .. } => entry_id,
+                        };
+                        let punch = event.punch_type();
+                        crate::config::log_event(&format!(
+                            "{:?}: {} (ID: {}, entry {})",
+                            punch, worker.name, worker.id, entry_id
+                        ));
+                        (punch.label(), punch.is_start())
                     }
                     Err(e) => {
+                        crate::config::log_event(&format!(
+                            "Error toggling clock for {} (ID: {}): {}",
+                            worker.name, worker.id, e
+                        ));
                         if let Some(ui) = ui_handle_barcode.upgrade() {
                             ui.set_error_dialog_message(
-                                format!("Error al obtener estado: {}", e).into(),
+                                format!("Error al registrar marca: {}", e).into(),
                             );
                             ui.set_show_error_dialog(true);
                             ui.set_trigger_error_dialog_show(true);
                         }
                         return;
                     }
+                };
+                // Show notification
+                if let Some(ui) = ui_handle_barcode.upgrade() {
+                    ui.set_confirm_worker_name(worker.name.into());
+                    ui.set_confirm_action(action.into());
+                    ui.set_confirm_is_check_in(is_check_in);
+                    ui.set_show_confirm_dialog(true);
+                    ui.set_trigger_dialog_show(true);
                 }
                 if let Some(ui) = ui_handle_barcode.upgrade() {
                     ui.set_show_error_dialog(false);
@@ -122,7 +127,7 @@ pub fn setup_event_handlers(conn: Rc<RefCell<rusqlite::Connection>>, ui: &crate:
                 crate::worker_display::refresh_workers(&conn_clone2, &ui_handle_barcode);
             }
             Ok(None) => {
-                println!("Worker not found for barcode: '{}'", trimmed_barcode);
+                crate::config::log_event(&format!("Worker not found for barcode: '{}'", trimmed_barcode));
                 if let Some(ui) = ui_handle_barcode.upgrade() {
                     ui.set_error_dialog_message("Trabajador no encontrado".into());
                     ui.set_show_error_dialog(true);
@@ -130,7 +135,7 @@ pub fn setup_event_handlers(conn: Rc<RefCell<rusqlite::Connection>>, ui: &crate:
                 }
             }
             Err(e) => {
-                println!("Error looking up worker: {}", e);
+                crate::config::log_event(&format!("Error looking up worker: {}", e));
                 if let Some(ui) = ui_handle_barcode.upgrade() {
                     ui.set_error_dialog_message(
                         format!("Error al buscar trabajador: {}", e).into(),
@@ -146,8 +151,13 @@ pub fn setup_event_handlers(conn: Rc<RefCell<rusqlite::Connection>>, ui: &crate:
         let name = name.trim();
         let barcode = crate::barcode::normalize(&barcode);
         if !name.is_empty() && !barcode.is_empty() {
-            let conn = conn_clone3.borrow();
-            match db::add_worker(&conn, name, &barcode) {
+            let result = db::add_worker(
+                &mut conn_clone3.borrow_mut(),
+                name,
+                &barcode,
+                &admin_context(&ui_handle_add),
+            );
+            match result {
                 Ok(_) => {
                     if let Some(ui) = ui_handle_add.upgrade() {
                         ui.set_show_error_dialog(false);
@@ -171,45 +181,29 @@ pub fn setup_event_handlers(conn: Rc<RefCell<rusqlite::Connection>>, ui: &crate:
         }
     });
 
-    ui.on_edit_worker(move |old_name, new_name, new_barcode| {
-        let old_name = old_name.trim();
+    ui.on_edit_worker(move |worker_id, new_name, new_barcode| {
         let new_name = new_name.trim();
         let new_barcode = crate::barcode::normalize(&new_barcode);
-        if !old_name.is_empty() && !new_name.is_empty() && !new_barcode.is_empty() {
-            let conn = conn_clone4.borrow();
-            match db::get_workers(&conn) {
-                Ok(workers) => {
-                    if let Some(worker) = workers.into_iter().find(|w| w.name == old_name) {
-                        match db::update_worker(&conn, worker.id, new_name, &new_barcode) {
-                            Ok(_) => {
-                                if let Some(ui) = ui_handle_edit.upgrade() {
-                                    ui.set_show_error_dialog(false);
-                                }
-                                crate::worker_display::refresh_workers(
-                                    &conn_clone4,
-                                    &ui_handle_edit,
-                                );
-                            }
-                            Err(e) => {
-                                if let Some(ui) = ui_handle_edit.upgrade() {
-                                    ui.set_error_dialog_message(
-                                        format!("Error al actualizar trabajador: {}", e).into(),
-                                    );
-                                    ui.set_show_error_dialog(true);
-                                    ui.set_trigger_error_dialog_show(true);
-                                }
-                            }
-                        }
-                    } else if let Some(ui) = ui_handle_edit.upgrade() {
-                        ui.set_error_dialog_message("Trabajador no encontrado".into());
-                        ui.set_show_error_dialog(true);
-                        ui.set_trigger_error_dialog_show(true);
+        if worker_id >= 0 && !new_name.is_empty() {
+            // An empty barcode field keeps the current badge as it is.
+            let result = db::update_worker(
+                &mut conn_clone4.borrow_mut(),
+                worker_id as i64,
+                new_name,
+                (!new_barcode.is_empty()).then_some(new_barcode.as_str()),
+                &admin_context(&ui_handle_edit),
+            );
+            match result {
+                Ok(_) => {
+                    if let Some(ui) = ui_handle_edit.upgrade() {
+                        ui.set_show_error_dialog(false);
                     }
+                    crate::worker_display::refresh_workers(&conn_clone4, &ui_handle_edit);
                 }
                 Err(e) => {
                     if let Some(ui) = ui_handle_edit.upgrade() {
                         ui.set_error_dialog_message(
-                            format!("Error al obtener trabajadores: {}", e).into(),
+                            format!("Error al actualizar trabajador: {}", e).into(),
                         );
                         ui.set_show_error_dialog(true);
                         ui.set_trigger_error_dialog_show(true);
@@ -219,6 +213,139 @@ pub fn setup_event_handlers(conn: Rc<RefCell<rusqlite::Connection>>, ui: &crate:
         }
     });
 
+    let conn_clone_badges = conn.clone();
+    let ui_handle_badges = ui_handle.clone();
+    ui.on_load_worker_badges(move |worker_id| {
+        crate::worker_display::refresh_worker_badges(
+            &conn_clone_badges,
+            &ui_handle_badges,
+            worker_id as i64,
+        );
+        crate::worker_display::refresh_worker_assignments(
+            &conn_clone_badges,
+            &ui_handle_badges,
+            worker_id as i64,
+        );
+        crate::worker_display::refresh_worker_absences(
+            &conn_clone_badges,
+            &ui_handle_badges,
+            worker_id as i64,
+        );
+        crate::worker_display::refresh_worker_vacation(
+            &conn_clone_badges,
+            &ui_handle_badges,
+            worker_id as i64,
+        );
+    });
+
+    let conn_clone_revoke = conn.clone();
+    let ui_handle_revoke = ui_handle.clone();
+    ui.on_revoke_badge(move |worker_id, badge_id, reason| {
+        let result = crate::badges::revoke_badge(
+            &mut conn_clone_revoke.borrow_mut(),
+            badge_id as i64,
+            &reason,
+            &admin_context(&ui_handle_revoke),
+        );
+        if let Some(ui) = ui_handle_revoke.upgrade() {
+            match result {
+                Ok(()) => ui.set_workers_status_message("Tarjeta revocada".into()),
+                Err(e) => show_error(&ui, format!("Error al revocar tarjeta: {}", e)),
+            }
+        }
+        crate::worker_display::refresh_workers(&conn_clone_revoke, &ui_handle_revoke);
+        crate::worker_display::refresh_worker_badges(
+            &conn_clone_revoke,
+            &ui_handle_revoke,
+            worker_id as i64,
+        );
+    });
+
+    let conn_clone_replace = conn.clone();
+    let ui_handle_replace = ui_handle.clone();
+    ui.on_replace_badge(move |worker_id, barcode, reason| {
+        let barcode = crate::barcode::normalize(&barcode);
+        let Some(ui) = ui_handle_replace.upgrade() else {
+            return;
+        };
+        if barcode.is_empty() {
+            show_error(&ui, "Ingrese el código de la nueva tarjeta".to_string());
+            return;
+        }
+        let result = crate::badges::replace_badge(
+            &mut conn_clone_replace.borrow_mut(),
+            worker_id as i64,
+            &barcode,
+            &reason,
+            &admin_context(&ui_handle_replace),
+        );
+        match result {
+            Ok(_) => {
+                ui.set_selected_worker_barcode(barcode.as_str().into());
+                ui.set_workers_status_message("Tarjeta reemplazada".into());
+            }
+            Err(e) => show_error(&ui, format!("Error al reemplazar tarjeta: {}", e)),
+        }
+        crate::worker_display::refresh_workers(&conn_clone_replace, &ui_handle_replace);
+        crate::worker_display::refresh_worker_badges(
+            &conn_clone_replace,
+            &ui_handle_replace,
+            worker_id as i64,
+        );
+    });
+
+    let conn_clone_active = conn.clone();
+    let ui_handle_active = ui_handle.clone();
+    ui.on_set_worker_active(move |worker_id, active| {
+        let ctx = admin_context(&ui_handle_active);
+        let result = if active {
+            db::reactivate_worker(&mut conn_clone_active.borrow_mut(), worker_id as i64, &ctx)
+        } else {
+            db::deactivate_worker(&mut conn_clone_active.borrow_mut(), worker_id as i64, &ctx)
+        };
+        if let Some(ui) = ui_handle_active.upgrade() {
+            match result {
+                Ok(()) => ui.set_workers_status_message(
+                    if active {
+                        "Trabajador reactivado"
+                    } else {
+                        "Trabajador desactivado"
+                    }
+                    .into(),
+                ),
+                Err(e) => show_error(
+                    &ui,
+                    format!("Error al cambiar estado del trabajador: {}", e),
+                ),
+            }
+        }
+        crate::worker_display::refresh_workers(&conn_clone_active, &ui_handle_active);
+    });
+
+    let conn_clone_rut = conn.clone();
+    let ui_handle_rut = ui_handle.clone();
+    ui.on_save_worker_rut(move |worker_id, rut| {
+        let result = db::set_worker_rut(
+            &mut conn_clone_rut.borrow_mut(),
+            worker_id as i64,
+            &rut,
+            &admin_context(&ui_handle_rut),
+        );
+        if let Some(ui) = ui_handle_rut.upgrade() {
+            match result {
+                Ok(()) => ui.set_workers_status_message("RUT guardado".into()),
+                Err(e) => show_error(&ui, format!("Error al guardar el RUT: {}", e)),
+            }
+        }
+        crate::worker_display::refresh_workers(&conn_clone_rut, &ui_handle_rut);
+    });
+
+    let conn_clone_management = conn.clone();
+    let ui_handle_management = ui_handle.clone();
+    ui.on_refresh_management_workers(move || {
+        crate::worker_display::refresh_workers(&conn_clone_management, &ui_handle_management);
+    });
+
     let ui_handle_confirm = ui_handle.clone();
     ui.on_confirm_check_action(move |_| {
         // Hide dialog
@@ -238,18 +365,393 @@ pub fn setup_event_handlers(conn: Rc<RefCell<rusqlite::Connection>>, ui: &crate:
     let ui_handle_time = ui.as_weak();
     ui.on_update_current_time(move || {
         if let Some(ui) = ui_handle_time.upgrade() {
-            let now = chrono::Utc::now().with_timezone(&Santiago);
+            let now = timezone::to_local(chrono::Utc::now());
             ui.set_current_time_display(
                 format!("{}:{}:{}", now.hour(), now.minute(), now.second()).into(),
             );
         }
     });
 
+    let conn_clone_scan = conn.clone();
+    let ui_handle_scan = ui_handle.clone();
+    ui.on_scan_invalid_rows(move || {
+        let result = db::scan_invalid_rows(&conn_clone_scan.borrow());
+        if let Some(ui) = ui_handle_scan.upgrade() {
+            match result {
+                Ok(rows) => ui.set_data_integrity_message(
+                    format!("Revisión completa: {} registros inválidos", rows.len()).into(),
+                ),
+                Err(e) => {
+                    ui.set_error_dialog_message(
+                        format!("Error al revisar registros: {}", e).into(),
+                    );
+                    ui.set_show_error_dialog(true);
+                    ui.set_trigger_error_dialog_show(true);
+                }
+            }
+        }
+        crate::worker_display::refresh_workers(&conn_clone_scan, &ui_handle_scan);
+    });
+
+    let conn_clone_quarantine = conn.clone();
+    let ui_handle_quarantine = ui_handle.clone();
+    ui.on_quarantine_invalid_rows(move || {
+        let ctx = admin_context(&ui_handle_quarantine)
+            .with_reason("registros con marcas de tiempo ilegibles");
+        let result = db::quarantine_invalid_rows(&mut conn_clone_quarantine.borrow_mut(), &ctx);
+        if let Some(ui) = ui_handle_quarantine.upgrade() {
+            match result {
+                Ok(moved) => {
+                    crate::config::log_event(&format!(
+                        "Moved {} invalid rows to quarantine",
+                        moved
+                    ));
+                    ui.set_data_integrity_message(
+                        format!("{} registros movidos a cuarentena", moved).into(),
+                    );
+                }
+                Err(e) => {
+                    ui.set_error_dialog_message(
+                        format!("Error al mover registros a cuarentena: {}", e).into(),
+                    );
+                    ui.set_show_error_dialog(true);
+                    ui.set_trigger_error_dialog_show(true);
+                }
+            }
+        }
+        crate::worker_display::refresh_workers(&conn_clone_quarantine, &ui_handle_quarantine);
+    });
+
+    let conn_clone_audit = conn.clone();
+    let ui_handle_audit = ui_handle.clone();
+    ui.on_export_audit_log(move || {
+        if let Some(ui) = ui_handle_audit.upgrade() {
+            let base = ui.get_report_output_directory().trim().to_string();
+            let output_dir = if base.is_empty() {
+                crate::config::data_dir().reports_dir()
+            } else {
+                PathBuf::from(base)
+            };
+            let path = output_dir.join(format!(
+                "audit_log_{}.csv",
+                chrono::Local::now().format("%Y%m%d_%H%M%S")
+            ));
+            let result = fs::create_dir_all(&output_dir)
+                .map_err(reports::ReportError::from)
+                .and_then(|_| audit::export_csv(&conn_clone_audit.borrow(), &path));
+            match result {
+                Ok(count) => {
+                    ui.set_last_report_directory(output_dir.display().to_string().into());
+                    ui.set_audit_status_message(
+                        format!("{} registros exportados a {}", count, path.display()).into(),
+                    );
+                }
+                Err(e) => {
+                    ui.set_error_dialog_message(
+                        format!("Error al exportar auditoría: {}", e).into(),
+                    );
+                    ui.set_show_error_dialog(true);
+                    ui.set_trigger_error_dialog_show(true);
+                }
+            }
+        }
+    });
+
+    let conn_clone_auto_close = conn.clone();
+    let ui_handle_auto_close = ui_handle.clone();
+    ui.on_save_auto_close_policy(move |index, cutoff, max_shift, grace| {
+        let Some(ui) = ui_handle_auto_close.upgrade() else {
+            return;
+        };
+        let Some(cutoff) = crate::auto_close::parse_cutoff(&cutoff) else {
+            show_error(
+                &ui,
+                format!("Hora de corte inválida: '{}' (use HH:MM)", cutoff.trim()),
+            );
+            return;
+        };
+        let Some(limits) = crate::auto_close::StaleLimits::parse(&max_shift, &grace) else {
+            show_error(
+                &ui,
+                "Use de 1 a 24 horas de jornada y de 0 a 720 minutos de tolerancia".to_string(),
+            );
+            return;
+        };
+        let Some(policy) =
+            crate::auto_close::AutoClosePolicy::from_index(index.max(0) as usize, cutoff)
+        else {
+            return;
+        };
+        let result = crate::auto_close::apply_policy(
+            &mut conn_clone_auto_close.borrow_mut(),
+            policy,
+            limits,
+            &admin_context(&ui_handle_auto_close),
+        );
+        match result {
+            Ok(closed) => {
+                ui.set_auto_close_status_message(
+                    format!("Política guardada; {} sesiones cerradas", closed).into(),
+                );
+                crate::worker_display::refresh_workers(
+                    &conn_clone_auto_close,
+                    &ui_handle_auto_close,
+                );
+            }
+            Err(e) => show_error(&ui, format!("Error al guardar la política: {}", e)),
+        }
+    });
+
+    let conn_clone_overtime = conn.clone();
+    let ui_handle_overtime = ui_handle.clone();
+    ui.on_save_overtime_rules(move |limits, daily_cap| {
+        let Some(ui) = ui_handle_overtime.upgrade() else {
+            return;
+        };
+        let Some(weekly_limits) = crate::overtime::parse_weekly_limits(&limits) else {
+            show_error(
+                &ui,
+                "Límites semanales inválidos (use AAAA-MM-DD=horas; ...)".to_string(),
+            );
+            return;
+        };
+        let Some(daily_cap_minutes) = crate::overtime::parse_hours(&daily_cap) else {
+            show_error(
+                &ui,
+                format!("Tope diario inválido: '{}' (use HH:MM)", daily_cap.trim()),
+            );
+            return;
+        };
+        let rules = crate::overtime::OvertimeRules {
+            weekly_limits,
+            daily_cap_minutes,
+        };
+        match crate::overtime::save_rules(
+            &mut conn_clone_overtime.borrow_mut(),
+            &rules,
+            &admin_context(&ui_handle_overtime),
+        ) {
+            Ok(()) => {
+                crate::worker_display::show_overtime_rules(&ui, &rules);
+                ui.set_overtime_status_message("Límites guardados".into());
+            }
+            Err(e) => show_error(&ui, format!("Error al guardar los límites: {}", e)),
+        }
+    });
+
+    let conn_clone_breaks = conn.clone();
+    let ui_handle_breaks = ui_handle.clone();
+    ui.on_save_break_settings(move |window, paid| {
+        let Some(ui) = ui_handle_breaks.upgrade() else {
+            return;
+        };
+        let Some(scan_window) = crate::breaks::parse_scan_window(&window) else {
+            show_error(
+                &ui,
+                format!("Ventana inválida: '{}' (use HH:MM-HH:MM)", window.trim()),
+            );
+            return;
+        };
+        let settings = crate::breaks::BreakSettings { scan_window, paid };
+        match crate::breaks::save_settings(
+            &mut conn_clone_breaks.borrow_mut(),
+            settings,
+            &admin_context(&ui_handle_breaks),
+        ) {
+            Ok(()) => {
+                ui.set_break_scan_window(crate::breaks::format_scan_window(scan_window).into());
+                ui.set_break_status_message("Configuración de colación guardada".into());
+            }
+            Err(e) => show_error(&ui, format!("Error al guardar la colación: {}", e)),
+        }
+    });
+
+    let conn_clone_rounding = conn.clone();
+    let ui_handle_rounding = ui_handle.clone();
+    ui.on_save_rounding_policy(move |mode_index, increment_index, start_grace, end_grace| {
+        let Some(ui) = ui_handle_rounding.upgrade() else {
+            return;
+        };
+        let (Some(mode), Some(increment_minutes)) = (
+            crate::timekeeping::RoundingMode::ALL
+                .get(mode_index.max(0) as usize)
+                .copied(),
+            crate::timekeeping::INCREMENTS
+                .get(increment_index.max(0) as usize)
+                .copied(),
+        ) else {
+            return;
+        };
+        let parse_grace = |value: &str| -> Option<i64> {
+            let value = value.trim();
+            if value.is_empty() {
+                return Some(0);
+            }
+            value.parse().ok().filter(|minutes| *minutes >= 0)
+        };
+        let (Some(start_grace_minutes), Some(end_grace_minutes)) =
+            (parse_grace(&start_grace), parse_grace(&end_grace))
+        else {
+            show_error(&ui, "Tolerancia inválida (use minutos enteros)".to_string());
+            return;
+        };
+        let policy = crate::timekeeping::RoundingPolicy {
+            mode,
+            increment_minutes,
+            start_grace_minutes,
+            end_grace_minutes,
+        };
+        match crate::timekeeping::save_policy(
+            &mut conn_clone_rounding.borrow_mut(),
+            policy,
+            &admin_context(&ui_handle_rounding),
+        ) {
+            Ok(()) => {
+                crate::worker_display::show_rounding_policy(&ui, &policy);
+                ui.set_rounding_status_message("Redondeo guardado".into());
+                crate::worker_display::refresh_workers(&conn_clone_rounding, &ui_handle_rounding);
+            }
+            Err(e) => show_error(&ui, format!("Error al guardar el redondeo: {}", e)),
+        }
+    });
+
+    let conn_clone_time_zone = conn.clone();
+    let ui_handle_time_zone = ui_handle.clone();
+    ui.on_save_time_zone(move |name| {
+        let Some(ui) = ui_handle_time_zone.upgrade() else {
+            return;
+        };
+        match timezone::save(
+            &mut conn_clone_time_zone.borrow_mut(),
+            &name,
+            &admin_context(&ui_handle_time_zone),
+        ) {
+            Ok(tz) => {
+                ui.set_time_zone_label(timezone::display_name(tz).into());
+                ui.set_time_zone_name(tz.name().into());
+                ui.set_time_zone_status_message(format!("Zona horaria: {}", tz.name()).into());
+                crate::worker_display::refresh_workers(&conn_clone_time_zone, &ui_handle_time_zone);
+            }
+            Err(e) => show_error(&ui, format!("Error al guardar la zona horaria: {}", e)),
+        }
+    });
+
+    let ui_handle_export_templates = ui_handle.clone();
+    ui.on_export_report_templates(move || {
+        let Some(ui) = ui_handle_export_templates.upgrade() else {
+            return;
+        };
+        let dir = crate::report_templates::templates_dir();
+        match crate::report_templates::export_defaults(&dir) {
+            Ok(written) => ui.set_templates_status_message(
+                format!("{} plantillas copiadas a {}", written, dir.display()).into(),
+            ),
+            Err(e) => show_error(&ui, format!("Error al copiar las plantillas: {}", e)),
+        }
+    });
+
+    let conn_clone_check_templates = conn.clone();
+    let ui_handle_check_templates = ui_handle.clone();
+    ui.on_check_report_templates(move || {
+        let Some(ui) = ui_handle_check_templates.upgrade() else {
+            return;
+        };
+        ui.set_templates_status_message("".into());
+        let range =
+            selected_report_range(&ui).unwrap_or_else(|_| ReportRange::month(local_today_naive()));
+        let result = reports::check_templates(&conn_clone_check_templates.borrow(), range);
+        match result {
+            Ok(workers) => ui.set_templates_status_message(
+                format!(
+                    "Plantillas correctas ({} trabajadores, {})",
+                    workers,
+                    range.label()
+                )
+                .into(),
+            ),
+            Err(e) => show_error(&ui, format!("Error en la plantilla de reportes: {}", e)),
+        }
+    });
+
+    let conn_clone_attribution = conn.clone();
+    let ui_handle_attribution = ui_handle.clone();
+    ui.on_save_attribution(move |index| {
+        let Some(ui) = ui_handle_attribution.upgrade() else {
+            return;
+        };
+        let Some(attribution) = crate::timekeeping::Attribution::ALL
+            .get(index.max(0) as usize)
+            .copied()
+        else {
+            return;
+        };
+        match crate::timekeeping::save_attribution(
+            &mut conn_clone_attribution.borrow_mut(),
+            attribution,
+            &admin_context(&ui_handle_attribution),
+        ) {
+            Ok(()) => {
+                ui.set_attribution_status_message(
+                    format!("Turnos nocturnos: {}", attribution.label()).into(),
+                );
+                crate::worker_display::refresh_workers(
+                    &conn_clone_attribution,
+                    &ui_handle_attribution,
+                );
+            }
+            Err(e) => show_error(&ui, format!("Error al guardar la asignación: {}", e)),
+        }
+    });
+
+    let conn_clone_period = conn.clone();
+    let ui_handle_period = ui_handle.clone();
+    ui.on_select_report_period(move |index, closing_day| {
+        let Some(ui) = ui_handle_period.upgrade() else {
+            return;
+        };
+        let Some(kind) = crate::periods::PeriodKind::ALL
+            .get(index.max(0) as usize)
+            .copied()
+        else {
+            return;
+        };
+        let Ok(closing_day) = closing_day.trim().parse::<u32>() else {
+            show_error(&ui, "El día de cierre debe ser un número".to_string());
+            return;
+        };
+        let definition = crate::periods::PeriodDefinition { kind, closing_day };
+        if let Err(e) = crate::periods::save_definition(
+            &mut conn_clone_period.borrow_mut(),
+            definition,
+            &admin_context(&ui_handle_period),
+        ) {
+            show_error(&ui, format!("Error al guardar el periodo: {}", e));
+            return;
+        }
+        crate::worker_display::refresh_report_period(&conn_clone_period, &ui_handle_period);
+    });
+
     let ui_handle_date = ui_handle.clone();
     ui.on_date_changed(move || {
         crate::worker_display::refresh_workers(&conn_clone_date, &ui_handle_date);
+        crate::worker_display::refresh_report_period(&conn_clone_date, &ui_handle_date);
+        crate::worker_display::refresh_correction_sessions(&conn_clone_date, &ui_handle_date);
+        if ui_handle_date
+            .upgrade()
+            .is_some_and(|ui| ui.get_show_hour_bank())
+        {
+            crate::worker_display::refresh_hour_bank(&conn_clone_date, &ui_handle_date);
+        }
     });
 
+    setup_correction_handlers(&conn, ui);
+    setup_hour_bank_handlers(&conn, ui);
+    setup_schedule_handlers(&conn, ui);
+    setup_holiday_handlers(&conn, ui);
+    setup_absence_handlers(&conn, ui);
+    setup_vacation_handlers(&conn, ui);
+    setup_export_profile_handlers(&conn, ui);
+
     let ui_handle_test = ui.as_weak();
     let ui_handle_report = ui_handle.clone();
     ui.on_test_printer_connection(move || {
@@ -361,16 +863,17 @@ pub fn setup_event_handlers(conn: Rc<RefCell<rusqlite::Connection>>, ui: &crate:
         if let Some(ui) = ui_handle_report.upgrade() {
             ui.set_report_status_message("".into());
             ui.set_last_report_directory("".into());
-            let selected_date_str = ui.get_selected_date().to_string();
-            let selected_naive = chrono::NaiveDate::parse_from_str(&selected_date_str, "%Y-%m-%d")
-                .unwrap_or_else(|_| santiago_today_naive());
-            let month_start =
-                chrono::NaiveDate::from_ymd_opt(selected_naive.year(), selected_naive.month(), 1)
-                    .unwrap_or(selected_naive);
-            let month_label = month_start.format("%Y-%m").to_string();
-
-            // Use a standard accessible location for reports
-            let output_dir = PathBuf::from("/tmp/timesheet_reports").join(&month_label);
+            let range = match selected_report_range(&ui) {
+                Ok(range) => range,
+                Err(message) => {
+                    show_error(&ui, message);
+                    return;
+                }
+            };
+            let period_label = range.label();
+
+            let output_dir =
+                resolve_output_directory(ui.get_report_output_directory().as_ref(), &range.key());
             let output_dir_str = output_dir.display().to_string();
 
             // Ensure the directory exists
@@ -385,12 +888,7 @@ pub fn setup_event_handlers(conn: Rc<RefCell<rusqlite::Connection>>, ui: &crate:
 
             let result = {
                 let conn_ref = conn_clone_report.borrow();
-                reports::generate_monthly_reports(
-                    &conn_ref,
-                    month_start,
-                    selected_naive,
-                    &output_dir,
-                )
+                reports::generate_reports(&conn_ref, range, &output_dir)
             };
 
             match result {
@@ -399,11 +897,14 @@ pub fn setup_event_handlers(conn: Rc<RefCell<rusqlite::Connection>>, ui: &crate:
                     ui.set_report_status_message(
                         format!(
                             "Reportes generados para {} en {}",
-                            month_label, output_dir_str
+                            period_label, output_dir_str
                         )
                         .into(),
                     );
                 }
+                Err(reports::ReportError::Template(e)) => {
+                    show_error(&ui, format!("Error en la plantilla de reportes: {}", e));
+                }
                 Err(e) => {
                     ui.set_error_dialog_message(format!("Error al generar reportes: {}", e).into());
                     ui.set_show_error_dialog(true);
@@ -414,22 +915,1016 @@ pub fn setup_event_handlers(conn: Rc<RefCell<rusqlite::Connection>>, ui: &crate:
     });
 }
 
-fn resolve_output_directory(base: &str, month_label: &str) -> PathBuf {
+/// Handlers for the correction panel in the Reports tab. Times are typed as
+/// "HH:MM" on the selected date; a clock-out earlier than the clock-in is
+/// taken to be on the following day.
+fn setup_correction_handlers(conn: &Rc<RefCell<rusqlite::Connection>>, ui: &crate::ui::MainWindow) {
+    let conn_clone_load = conn.clone();
+    let ui_handle_load = ui.as_weak();
+    ui.on_load_correction_sessions(move || {
+        crate::worker_display::refresh_correction_sessions(&conn_clone_load, &ui_handle_load);
+    });
+
+    let conn_clone_add = conn.clone();
+    let ui_handle_add = ui.as_weak();
+    ui.on_add_missed_punch(move |clock_in, clock_out| {
+        let Some(ui) = ui_handle_add.upgrade() else {
+            return;
+        };
+        let Some(worker_id) = crate::worker_display::selected_correction_worker(&ui) else {
+            show_error(&ui, "Seleccione un trabajador".to_string());
+            return;
+        };
+        let result = parse_session_times(&ui, &clock_in, &clock_out).and_then(|(start, end)| {
+            let (reason, note) = correction_reason(&ui);
+            crate::corrections::insert_missed_punch(
+                &mut conn_clone_add.borrow_mut(),
+                worker_id,
+                start,
+                end,
+                reason,
+                &note,
+                &admin_context(&ui_handle_add),
+            )
+            .map_err(|e| e.to_string())
+        });
+        finish_correction(
+            &conn_clone_add,
+            &ui_handle_add,
+            result.map(|id| format!("Marca agregada (registro #{})", id)),
+        );
+    });
+
+    let conn_clone_adjust = conn.clone();
+    let ui_handle_adjust = ui.as_weak();
+    ui.on_adjust_session(move |entry_id, clock_in, clock_out| {
+        let Some(ui) = ui_handle_adjust.upgrade() else {
+            return;
+        };
+        let result = parse_session_times(&ui, &clock_in, &clock_out).and_then(|(start, end)| {
+            let (reason, note) = correction_reason(&ui);
+            crate::corrections::adjust_entry(
+                &mut conn_clone_adjust.borrow_mut(),
+                entry_id as i64,
+                start,
+                end,
+                reason,
+                &note,
+                &admin_context(&ui_handle_adjust),
+            )
+            .map_err(|e| e.to_string())
+        });
+        finish_correction(
+            &conn_clone_adjust,
+            &ui_handle_adjust,
+            result.map(|_| format!("Registro #{} corregido", entry_id)),
+        );
+    });
+
+    let conn_clone_split = conn.clone();
+    let ui_handle_split = ui.as_weak();
+    ui.on_split_session(move |entry_id, at| {
+        let Some(ui) = ui_handle_split.upgrade() else {
+            return;
+        };
+        let result = parse_correction_time(&ui, &at).and_then(|at| {
+            let (reason, note) = correction_reason(&ui);
+            crate::corrections::split_entry(
+                &mut conn_clone_split.borrow_mut(),
+                entry_id as i64,
+                at,
+                reason,
+                &note,
+                &admin_context(&ui_handle_split),
+            )
+            .map_err(|e| e.to_string())
+        });
+        finish_correction(
+            &conn_clone_split,
+            &ui_handle_split,
+            result.map(|new_id| format!("Registro #{} dividido (nuevo #{})", entry_id, new_id)),
+        );
+    });
+
+    let conn_clone_delete = conn.clone();
+    let ui_handle_delete = ui.as_weak();
+    ui.on_delete_session(move |entry_id| {
+        let Some(ui) = ui_handle_delete.upgrade() else {
+            return;
+        };
+        let (reason, note) = correction_reason(&ui);
+        let result = crate::corrections::delete_entry(
+            &mut conn_clone_delete.borrow_mut(),
+            entry_id as i64,
+            reason,
+            &note,
+            &admin_context(&ui_handle_delete),
+        )
+        .map_err(|e| e.to_string());
+        finish_correction(
+            &conn_clone_delete,
+            &ui_handle_delete,
+            result.map(|_| format!("Registro #{} eliminado", entry_id)),
+        );
+    });
+}
+
+fn setup_hour_bank_handlers(conn: &Rc<RefCell<rusqlite::Connection>>, ui: &crate::ui::MainWindow) {
+    let conn_clone_load = conn.clone();
+    let ui_handle_load = ui.as_weak();
+    ui.on_load_hour_bank(move || {
+        crate::worker_display::refresh_hour_bank(&conn_clone_load, &ui_handle_load);
+    });
+
+    let conn_clone_close = conn.clone();
+    let ui_handle_close = ui.as_weak();
+    ui.on_close_hour_bank_month(move || {
+        let Some(ui) = ui_handle_close.upgrade() else {
+            return;
+        };
+        let selected = chrono::NaiveDate::parse_from_str(&ui.get_selected_date(), "%Y-%m-%d")
+            .unwrap_or_else(|_| local_today_naive());
+        let result = reports::close_hour_bank(
+            &mut conn_clone_close.borrow_mut(),
+            selected,
+            &admin_context(&ui_handle_close),
+        );
+        match result {
+            Ok(count) => {
+                let message = format!(
+                    "Banco de horas de {} cerrado para {} trabajadores",
+                    selected.format("%Y-%m"),
+                    count
+                );
+                crate::config::log_event(&message);
+                ui.set_hour_bank_status_message(message.into());
+            }
+            Err(e) => {
+                ui.set_hour_bank_status_message("".into());
+                show_error(&ui, format!("Error al cerrar el mes: {}", e));
+            }
+        }
+        crate::worker_display::refresh_hour_bank(&conn_clone_close, &ui_handle_close);
+    });
+
+    let conn_clone_adjust = conn.clone();
+    let ui_handle_adjust = ui.as_weak();
+    ui.on_add_hour_bank_adjustment(move |minutes, reason| {
+        let Some(ui) = ui_handle_adjust.upgrade() else {
+            return;
+        };
+        let worker_id = usize::try_from(ui.get_hour_bank_worker_index())
+            .ok()
+            .and_then(|index| ui.get_correction_worker_ids().row_data(index));
+        let Some(worker_id) = worker_id else {
+            show_error(&ui, "Seleccione un trabajador".to_string());
+            return;
+        };
+        let Some(minutes) = crate::hour_bank::parse_minutes(&minutes) else {
+            show_error(
+                &ui,
+                format!("Ajuste inválido: {} (use +HH:MM o -HH:MM)", minutes),
+            );
+            return;
+        };
+        let date = chrono::NaiveDate::parse_from_str(&ui.get_selected_date(), "%Y-%m-%d")
+            .unwrap_or_else(|_| local_today_naive());
+        let adjustment = crate::hour_bank::Adjustment {
+            id: 0,
+            worker_id: i64::from(worker_id),
+            date,
+            minutes,
+            reason: reason.to_string(),
+        };
+        let result = crate::hour_bank::add_adjustment(
+            &mut conn_clone_adjust.borrow_mut(),
+            &adjustment,
+            &admin_context(&ui_handle_adjust),
+        );
+        match result {
+            Ok(id) => {
+                let message = format!(
+                    "Ajuste registrado (#{}): {}",
+                    id,
+                    crate::hour_bank::format_balance(minutes)
+                );
+                crate::config::log_event(&message);
+                ui.set_hour_bank_status_message(message.into());
+            }
+            Err(e) => {
+                ui.set_hour_bank_status_message("".into());
+                show_error(&ui, format!("Error al registrar el ajuste: {}", e));
+            }
+        }
+        crate::worker_display::refresh_hour_bank(&conn_clone_adjust, &ui_handle_adjust);
+    });
+}
+
+/// Handlers for the template editor in Settings and the schedule
+/// assignments in the Workers tab.
+fn setup_schedule_handlers(conn: &Rc<RefCell<rusqlite::Connection>>, ui: &crate::ui::MainWindow) {
+    let conn_clone_load = conn.clone();
+    let ui_handle_load = ui.as_weak();
+    ui.on_load_schedule(move || {
+        let Some(ui) = ui_handle_load.upgrade() else {
+            return;
+        };
+        let schedule = crate::worker_display::selected_schedule(&ui)
+            .map(|id| crate::schedules::get_schedule(&conn_clone_load.borrow(), id));
+        match schedule.transpose() {
+            Ok(schedule) => {
+                crate::worker_display::show_schedule(&ui, schedule.flatten().as_ref());
+                ui.set_schedule_status_message("".into());
+            }
+            Err(e) => show_error(&ui, format!("Error al cargar horario: {}", e)),
+        }
+    });
+
+    let ui_handle_new = ui.as_weak();
+    ui.on_new_schedule(move || {
+        if let Some(ui) = ui_handle_new.upgrade() {
+            ui.set_schedule_index(-1);
+            crate::worker_display::show_schedule(&ui, None);
+            ui.set_schedule_status_message("".into());
+        }
+    });
+
+    let conn_clone_save = conn.clone();
+    let ui_handle_save = ui.as_weak();
+    ui.on_save_schedule(move || {
+        let Some(ui) = ui_handle_save.upgrade() else {
+            return;
+        };
+        let days = match parse_schedule_days(&ui) {
+            Ok(days) => days,
+            Err(message) => {
+                show_error(&ui, message);
+                return;
+            }
+        };
+        let valid_from = ui.get_schedule_valid_from();
+        let Ok(valid_from) = NaiveDate::parse_from_str(valid_from.trim(), "%Y-%m-%d") else {
+            show_error(
+                &ui,
+                format!(
+                    "Fecha de vigencia inválida: '{}' (use AAAA-MM-DD)",
+                    valid_from.trim()
+                ),
+            );
+            return;
+        };
+        let result = crate::schedules::save_schedule(
+            &mut conn_clone_save.borrow_mut(),
+            crate::worker_display::selected_schedule(&ui),
+            &ui.get_schedule_name(),
+            &days,
+            valid_from,
+            &admin_context(&ui_handle_save),
+        );
+        match result {
+            Ok(id) => {
+                crate::worker_display::refresh_schedules(&conn_clone_save, &ui_handle_save);
+                let index = ui.get_schedule_ids().iter().position(|i| i as i64 == id);
+                if let Some(index) = index {
+                    ui.set_schedule_index(index as i32);
+                    ui.invoke_load_schedule();
+                }
+                ui.set_schedule_status_message("Horario guardado".into());
+                crate::worker_display::refresh_workers(&conn_clone_save, &ui_handle_save);
+            }
+            Err(e) => show_error(&ui, format!("Error al guardar horario: {}", e)),
+        }
+    });
+
+    let conn_clone_default = conn.clone();
+    let ui_handle_default = ui.as_weak();
+    ui.on_make_default_schedule(move || {
+        let Some(ui) = ui_handle_default.upgrade() else {
+            return;
+        };
+        let Some(id) = crate::worker_display::selected_schedule(&ui) else {
+            return;
+        };
+        let result = crate::schedules::set_default_schedule(
+            &mut conn_clone_default.borrow_mut(),
+            id,
+            &admin_context(&ui_handle_default),
+        );
+        match result {
+            Ok(()) => {
+                crate::worker_display::refresh_schedules(&conn_clone_default, &ui_handle_default);
+                ui.set_schedule_status_message("Horario predeterminado actualizado".into());
+                crate::worker_display::refresh_workers(&conn_clone_default, &ui_handle_default);
+            }
+            Err(e) => show_error(
+                &ui,
+                format!("Error al cambiar horario predeterminado: {}", e),
+            ),
+        }
+    });
+
+    let conn_clone_assign = conn.clone();
+    let ui_handle_assign = ui.as_weak();
+    ui.on_assign_schedule(move |worker_id, from, to| {
+        let Some(ui) = ui_handle_assign.upgrade() else {
+            return;
+        };
+        let schedule_id = usize::try_from(ui.get_assignment_schedule_index())
+            .ok()
+            .and_then(|index| ui.get_schedule_ids().row_data(index));
+        let Some(schedule_id) = schedule_id else {
+            show_error(&ui, "Seleccione un horario".to_string());
+            return;
+        };
+        let Ok(from) = NaiveDate::parse_from_str(from.trim(), "%Y-%m-%d") else {
+            show_error(
+                &ui,
+                format!(
+                    "Fecha de inicio inválida: '{}' (use AAAA-MM-DD)",
+                    from.trim()
+                ),
+            );
+            return;
+        };
+        let to = match to.trim() {
+            "" => None,
+            value => match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+                Ok(date) => Some(date),
+                Err(_) => {
+                    show_error(
+                        &ui,
+                        format!("Fecha de término inválida: '{}' (use AAAA-MM-DD)", value),
+                    );
+                    return;
+                }
+            },
+        };
+        let result = crate::schedules::assign_schedule(
+            &mut conn_clone_assign.borrow_mut(),
+            worker_id as i64,
+            schedule_id as i64,
+            from,
+            to,
+            &admin_context(&ui_handle_assign),
+        );
+        match result {
+            Ok(_) => ui.set_workers_status_message("Horario asignado".into()),
+            Err(e) => show_error(&ui, format!("Error al asignar horario: {}", e)),
+        }
+        crate::worker_display::refresh_worker_assignments(
+            &conn_clone_assign,
+            &ui_handle_assign,
+            worker_id as i64,
+        );
+        crate::worker_display::refresh_workers(&conn_clone_assign, &ui_handle_assign);
+    });
+
+    let conn_clone_remove = conn.clone();
+    let ui_handle_remove = ui.as_weak();
+    ui.on_remove_assignment(move |worker_id, assignment_id| {
+        let result = crate::schedules::remove_assignment(
+            &mut conn_clone_remove.borrow_mut(),
+            assignment_id as i64,
+            &admin_context(&ui_handle_remove),
+        );
+        if let Some(ui) = ui_handle_remove.upgrade() {
+            match result {
+                Ok(()) => ui.set_workers_status_message("Asignación eliminada".into()),
+                Err(e) => show_error(&ui, format!("Error al quitar asignación: {}", e)),
+            }
+        }
+        crate::worker_display::refresh_worker_assignments(
+            &conn_clone_remove,
+            &ui_handle_remove,
+            worker_id as i64,
+        );
+        crate::worker_display::refresh_workers(&conn_clone_remove, &ui_handle_remove);
+    });
+}
+
+/// Handlers for the holiday calendar in Settings. Every change also
+/// refreshes the Time tab, whose schedule column shows holidays.
+fn setup_holiday_handlers(conn: &Rc<RefCell<rusqlite::Connection>>, ui: &crate::ui::MainWindow) {
+    let conn_clone_year = conn.clone();
+    let ui_handle_year = ui.as_weak();
+    ui.on_change_holiday_year(move |delta| {
+        if let Some(ui) = ui_handle_year.upgrade() {
+            ui.set_holiday_year(ui.get_holiday_year() + delta);
+            ui.set_holiday_status_message("".into());
+        }
+        crate::worker_display::refresh_holidays(&conn_clone_year, &ui_handle_year);
+    });
+
+    let conn_clone_chile = conn.clone();
+    let ui_handle_chile = ui.as_weak();
+    ui.on_load_chile_holidays(move || {
+        let result = crate::holidays::save_holidays(
+            &mut conn_clone_chile.borrow_mut(),
+            &crate::holidays::chile_holidays(),
+            &admin_context(&ui_handle_chile).with_reason("feriados de Chile incluidos"),
+        );
+        finish_holiday_change(
+            &conn_clone_chile,
+            &ui_handle_chile,
+            result.map_err(|e| e.to_string()),
+        );
+    });
+
+    let conn_clone_import = conn.clone();
+    let ui_handle_import = ui.as_weak();
+    ui.on_import_holidays(move |path| {
+        let path = path.trim();
+        if path.is_empty() {
+            if let Some(ui) = ui_handle_import.upgrade() {
+                show_error(&ui, "Ingrese la ruta del archivo de feriados".to_string());
+            }
+            return;
+        }
+        let result = crate::holidays::import_file(
+            &mut conn_clone_import.borrow_mut(),
+            Path::new(path),
+            &admin_context(&ui_handle_import),
+        );
+        finish_holiday_change(
+            &conn_clone_import,
+            &ui_handle_import,
+            result.map_err(|e| e.to_string()),
+        );
+    });
+
+    let conn_clone_add = conn.clone();
+    let ui_handle_add = ui.as_weak();
+    ui.on_add_holiday(move |date, name| {
+        let Ok(date) = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d") else {
+            if let Some(ui) = ui_handle_add.upgrade() {
+                show_error(
+                    &ui,
+                    format!("Fecha inválida: '{}' (use AAAA-MM-DD)", date.trim()),
+                );
+            }
+            return;
+        };
+        let holiday = crate::holidays::Holiday {
+            date,
+            name: name.trim().to_string(),
+            source: "manual".to_string(),
+        };
+        let result = crate::holidays::save_holidays(
+            &mut conn_clone_add.borrow_mut(),
+            &[holiday],
+            &admin_context(&ui_handle_add),
+        );
+        if let Some(ui) = ui_handle_add.upgrade()
+            && result.is_ok()
+        {
+            ui.set_holiday_year(date.year());
+        }
+        finish_holiday_change(
+            &conn_clone_add,
+            &ui_handle_add,
+            result.map_err(|e| e.to_string()),
+        );
+    });
+
+    let conn_clone_remove = conn.clone();
+    let ui_handle_remove = ui.as_weak();
+    ui.on_remove_holiday(move |date| {
+        let Ok(date) = NaiveDate::parse_from_str(date.as_str(), "%Y-%m-%d") else {
+            return;
+        };
+        let result = crate::holidays::delete_holiday(
+            &mut conn_clone_remove.borrow_mut(),
+            date,
+            &admin_context(&ui_handle_remove),
+        );
+        if let Some(ui) = ui_handle_remove.upgrade() {
+            match result {
+                Ok(()) => {
+                    ui.set_holiday_status_message(format!("Feriado {} eliminado", date).into())
+                }
+                Err(e) => show_error(&ui, format!("Error al quitar feriado: {}", e)),
+            }
+        }
+        crate::worker_display::refresh_holidays(&conn_clone_remove, &ui_handle_remove);
+        crate::worker_display::refresh_workers(&conn_clone_remove, &ui_handle_remove);
+    });
+}
+
+/// Handlers for the absence list under the worker being edited. The form
+/// edits `absence_edit_id`, or adds a new absence when it is -1; an empty
+/// end date means a single day.
+fn setup_absence_handlers(conn: &Rc<RefCell<rusqlite::Connection>>, ui: &crate::ui::MainWindow) {
+    let conn_clone_save = conn.clone();
+    let ui_handle_save = ui.as_weak();
+    ui.on_save_absence(move |worker_id| {
+        let Some(ui) = ui_handle_save.upgrade() else {
+            return;
+        };
+        let absence = match absence_from_form(&ui, worker_id as i64) {
+            Ok(absence) => absence,
+            Err(message) => {
+                show_error(&ui, message);
+                return;
+            }
+        };
+        let ctx = admin_context(&ui_handle_save);
+        let result = if absence.id >= 0 {
+            crate::absences::update_absence(&mut conn_clone_save.borrow_mut(), &absence, &ctx)
+        } else {
+            crate::absences::add_absence(&mut conn_clone_save.borrow_mut(), &absence, &ctx)
+                .map(|_| ())
+        };
+        match result {
+            Ok(()) => {
+                ui.set_absence_edit_id(-1);
+                ui.set_absence_from("".into());
+                ui.set_absence_to("".into());
+                ui.set_absence_note("".into());
+                ui.set_workers_status_message("Ausencia guardada".into());
+            }
+            Err(e) => show_error(&ui, format!("Error al guardar ausencia: {}", e)),
+        }
+        crate::worker_display::refresh_worker_absences(
+            &conn_clone_save,
+            &ui_handle_save,
+            worker_id as i64,
+        );
+        crate::worker_display::refresh_worker_vacation(
+            &conn_clone_save,
+            &ui_handle_save,
+            worker_id as i64,
+        );
+        crate::worker_display::refresh_workers(&conn_clone_save, &ui_handle_save);
+    });
+
+    let conn_clone_delete = conn.clone();
+    let ui_handle_delete = ui.as_weak();
+    ui.on_delete_absence(move |worker_id, absence_id| {
+        let result = crate::absences::delete_absence(
+            &mut conn_clone_delete.borrow_mut(),
+            absence_id as i64,
+            &admin_context(&ui_handle_delete),
+        );
+        if let Some(ui) = ui_handle_delete.upgrade() {
+            match result {
+                Ok(()) => {
+                    if ui.get_absence_edit_id() == absence_id {
+                        ui.set_absence_edit_id(-1);
+                    }
+                    ui.set_workers_status_message("Ausencia eliminada".into());
+                }
+                Err(e) => show_error(&ui, format!("Error al eliminar ausencia: {}", e)),
+            }
+        }
+        crate::worker_display::refresh_worker_absences(
+            &conn_clone_delete,
+            &ui_handle_delete,
+            worker_id as i64,
+        );
+        crate::worker_display::refresh_worker_vacation(
+            &conn_clone_delete,
+            &ui_handle_delete,
+            worker_id as i64,
+        );
+        crate::worker_display::refresh_workers(&conn_clone_delete, &ui_handle_delete);
+    });
+}
+
+/// Handlers for the feriado legal block under the worker being edited.
+fn setup_vacation_handlers(conn: &Rc<RefCell<rusqlite::Connection>>, ui: &crate::ui::MainWindow) {
+    let conn_clone_employment = conn.clone();
+    let ui_handle_employment = ui.as_weak();
+    ui.on_save_worker_employment(move |worker_id, hire_date, prior_years| {
+        let Some(ui) = ui_handle_employment.upgrade() else {
+            return;
+        };
+        let hire_date = match hire_date.trim() {
+            "" => None,
+            value => match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+                Ok(date) => Some(date),
+                Err(_) => {
+                    show_error(
+                        &ui,
+                        format!("Fecha de ingreso inválida: '{}' (use AAAA-MM-DD)", value),
+                    );
+                    return;
+                }
+            },
+        };
+        let prior_years = match prior_years.trim() {
+            "" => 0,
+            value => match value.parse::<i64>() {
+                Ok(years) => years,
+                Err(_) => {
+                    show_error(
+                        &ui,
+                        format!("Años con otros empleadores inválidos: '{}'", value),
+                    );
+                    return;
+                }
+            },
+        };
+        let result = db::set_worker_employment(
+            &mut conn_clone_employment.borrow_mut(),
+            worker_id as i64,
+            hire_date,
+            prior_years,
+            &admin_context(&ui_handle_employment),
+        );
+        match result {
+            Ok(()) => ui.set_workers_status_message("Datos de contrato guardados".into()),
+            Err(e) => show_error(&ui, format!("Error al guardar datos de contrato: {}", e)),
+        }
+        crate::worker_display::refresh_worker_vacation(
+            &conn_clone_employment,
+            &ui_handle_employment,
+            worker_id as i64,
+        );
+        crate::worker_display::refresh_workers(&conn_clone_employment, &ui_handle_employment);
+    });
+
+    let conn_clone_statement = conn.clone();
+    let ui_handle_statement = ui.as_weak();
+    ui.on_export_vacation_statement(move |worker_id| {
+        let Some(ui) = ui_handle_statement.upgrade() else {
+            return;
+        };
+        let base = ui.get_report_output_directory().trim().to_string();
+        let output_dir = if base.is_empty() {
+            crate::config::data_dir().reports_dir()
+        } else {
+            PathBuf::from(base)
+        };
+        let result = reports::export_vacation_statement(
+            &conn_clone_statement.borrow(),
+            worker_id as i64,
+            local_today_naive(),
+            &output_dir,
+        );
+        match result {
+            Ok(path) => {
+                ui.set_last_report_directory(output_dir.display().to_string().into());
+                ui.set_workers_status_message(
+                    format!("Estado de vacaciones exportado a {}", path.display()).into(),
+                );
+            }
+            Err(e) => show_error(
+                &ui,
+                format!("Error al exportar estado de vacaciones: {}", e),
+            ),
+        }
+    });
+}
+
+fn absence_from_form(
+    ui: &crate::ui::MainWindow,
+    worker_id: i64,
+) -> Result<crate::absences::Absence, String> {
+    let kind = usize::try_from(ui.get_absence_kind_index())
+        .ok()
+        .and_then(|index| crate::absences::AbsenceKind::ALL.get(index).copied())
+        .ok_or_else(|| "Seleccione el tipo de ausencia".to_string())?;
+    let from = ui.get_absence_from();
+    let start_date = NaiveDate::parse_from_str(from.trim(), "%Y-%m-%d").map_err(|_| {
+        format!(
+            "Fecha de inicio inválida: '{}' (use AAAA-MM-DD)",
+            from.trim()
+        )
+    })?;
+    let to = ui.get_absence_to();
+    let end_date = match to.trim() {
+        "" => start_date,
+        value => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map_err(|_| format!("Fecha de término inválida: '{}' (use AAAA-MM-DD)", value))?,
+    };
+    Ok(crate::absences::Absence {
+        id: ui.get_absence_edit_id() as i64,
+        worker_id,
+        kind,
+        start_date,
+        end_date,
+        paid: ui.get_absence_paid(),
+        note: ui.get_absence_note().to_string(),
+    })
+}
+
+/// Reports how many dates a holiday save added and reloads the views.
+fn finish_holiday_change(
+    conn: &Rc<RefCell<rusqlite::Connection>>,
+    ui_handle: &slint::Weak<crate::ui::MainWindow>,
+    result: Result<usize, String>,
+) {
+    if let Some(ui) = ui_handle.upgrade() {
+        match result {
+            Ok(added) => ui.set_holiday_status_message(
+                format!("Feriados guardados; {} fechas nuevas", added).into(),
+            ),
+            Err(e) => show_error(&ui, format!("Error al guardar feriados: {}", e)),
+        }
+    }
+    crate::worker_display::refresh_holidays(conn, ui_handle);
+    crate::worker_display::refresh_workers(conn, ui_handle);
+}
+
+/// Reads the week typed into the template editor. A day with both times
+/// empty is a day off; an empty break means no break.
+fn parse_schedule_days(
+    ui: &crate::ui::MainWindow,
+) -> Result<Vec<crate::schedules::ScheduleDay>, String> {
+    let mut days = Vec::new();
+    for (i, item) in ui.get_schedule_days().iter().enumerate() {
+        let (start, end) = (item.start.trim(), item.end.trim());
+        if start.is_empty() && end.is_empty() {
+            continue;
+        }
+        let parse = |value: &str| {
+            NaiveTime::parse_from_str(value, "%H:%M")
+                .map_err(|_| format!("Hora inválida para {}: '{}' (use HH:MM)", item.label, value))
+        };
+        let break_minutes = match item.break_minutes.trim() {
+            "" => 0,
+            value => value
+                .parse()
+                .map_err(|_| format!("Colación inválida para {}: '{}'", item.label, value))?,
+        };
+        days.push(crate::schedules::ScheduleDay {
+            weekday: Weekday::try_from(i as u8).map_err(|e| e.to_string())?,
+            start: parse(start)?,
+            end: parse(end)?,
+            break_minutes,
+        });
+    }
+    Ok(days)
+}
+
+fn correction_reason(ui: &crate::ui::MainWindow) -> (crate::corrections::ReasonCode, String) {
+    let reasons = crate::corrections::ReasonCode::ALL;
+    let index = usize::try_from(ui.get_correction_reason_index()).unwrap_or(0);
+    let reason = reasons.get(index).copied().unwrap_or(reasons[0]);
+    (reason, ui.get_correction_note().to_string())
+}
+
+fn parse_correction_time(
+    ui: &crate::ui::MainWindow,
+    time: &str,
+) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    let date = chrono::NaiveDate::parse_from_str(ui.get_selected_date().as_ref(), "%Y-%m-%d")
+        .unwrap_or_else(|_| local_today_naive());
+    crate::utils::local_time_on(date, time)
+        .ok_or_else(|| format!("Hora inválida: '{}' (use HH:MM)", time.trim()))
+}
+
+/// Parses the clock-in and optional clock-out typed in the correction panel.
+fn parse_session_times(
+    ui: &crate::ui::MainWindow,
+    clock_in: &str,
+    clock_out: &str,
+) -> Result<
+    (
+        chrono::DateTime<chrono::Utc>,
+        Option<chrono::DateTime<chrono::Utc>>,
+    ),
+    String,
+> {
+    let start = parse_correction_time(ui, clock_in)?;
+    if clock_out.trim().is_empty() {
+        return Ok((start, None));
+    }
+    let mut end = parse_correction_time(ui, clock_out)?;
+    if end <= start {
+        end += chrono::Duration::days(1);
+    }
+    Ok((start, Some(end)))
+}
+
+fn finish_correction(
+    conn: &Rc<RefCell<rusqlite::Connection>>,
+    ui_handle: &slint::Weak<crate::ui::MainWindow>,
+    result: Result<String, String>,
+) {
+    if let Some(ui) = ui_handle.upgrade() {
+        match result {
+            Ok(message) => {
+                crate::config::log_event(&message);
+                ui.set_correction_status_message(message.into());
+            }
+            Err(e) => {
+                ui.set_correction_status_message("".into());
+                show_error(&ui, format!("Error al corregir marcas: {}", e));
+                return;
+            }
+        }
+    }
+    crate::worker_display::refresh_workers(conn, ui_handle);
+    crate::worker_display::refresh_correction_sessions(conn, ui_handle);
+}
+
+/// Handlers for the payroll export profiles edited in Settings and the
+/// export button in the Reports tab.
+fn setup_export_profile_handlers(
+    conn: &Rc<RefCell<rusqlite::Connection>>,
+    ui: &crate::ui::MainWindow,
+) {
+    let conn_clone_load = conn.clone();
+    let ui_handle_load = ui.as_weak();
+    ui.on_load_export_profile(move || {
+        let Some(ui) = ui_handle_load.upgrade() else {
+            return;
+        };
+        let profile =
+            crate::worker_display::selected_export_profile(&ui, ui.get_export_profile_index())
+                .map(|id| crate::payroll_export::get_profile(&conn_clone_load.borrow(), id));
+        match profile.transpose() {
+            Ok(profile) => {
+                crate::worker_display::show_export_profile(&ui, profile.flatten().as_ref());
+                ui.set_export_profile_status_message("".into());
+            }
+            Err(e) => show_error(&ui, format!("Error al cargar el perfil: {}", e)),
+        }
+    });
+
+    let ui_handle_new = ui.as_weak();
+    ui.on_new_export_profile(move || {
+        if let Some(ui) = ui_handle_new.upgrade() {
+            ui.set_export_profile_index(-1);
+            crate::worker_display::show_export_profile(&ui, None);
+            ui.set_export_profile_status_message("".into());
+        }
+    });
+
+    let conn_clone_save = conn.clone();
+    let ui_handle_save = ui.as_weak();
+    ui.on_save_export_profile(move || {
+        let Some(ui) = ui_handle_save.upgrade() else {
+            return;
+        };
+        let columns = match crate::payroll_export::parse_columns(&ui.get_export_profile_columns()) {
+            Ok(columns) => columns,
+            Err(e) => {
+                show_error(&ui, format!("Error al guardar el perfil: {}", e));
+                return;
+            }
+        };
+        let pick = |index: i32, len: usize| usize::try_from(index).ok().filter(|i| *i < len);
+        let (Some(separator), Some(hours), Some(encoding)) = (
+            pick(ui.get_export_separator_index(), Separator::ALL.len()).map(|i| Separator::ALL[i]),
+            pick(ui.get_export_hours_index(), HoursFormat::ALL.len()).map(|i| HoursFormat::ALL[i]),
+            pick(ui.get_export_encoding_index(), Encoding::ALL.len()).map(|i| Encoding::ALL[i]),
+        ) else {
+            return;
+        };
+        let result = crate::payroll_export::save_profile(
+            &mut conn_clone_save.borrow_mut(),
+            crate::worker_display::selected_export_profile(&ui, ui.get_export_profile_index()),
+            &ui.get_export_profile_name(),
+            &columns,
+            ExportFormat {
+                separator,
+                hours,
+                encoding,
+                header: ui.get_export_header(),
+            },
+            &admin_context(&ui_handle_save),
+        );
+        match result {
+            Ok(id) => {
+                crate::worker_display::refresh_export_profiles(&conn_clone_save, &ui_handle_save);
+                let index = ui
+                    .get_export_profile_ids()
+                    .iter()
+                    .position(|i| i as i64 == id);
+                if let Some(index) = index {
+                    ui.set_export_profile_index(index as i32);
+                    ui.invoke_load_export_profile();
+                }
+                ui.set_export_profile_status_message("Perfil guardado".into());
+            }
+            Err(e) => show_error(&ui, format!("Error al guardar el perfil: {}", e)),
+        }
+    });
+
+    let conn_clone_delete = conn.clone();
+    let ui_handle_delete = ui.as_weak();
+    ui.on_delete_export_profile(move || {
+        let Some(ui) = ui_handle_delete.upgrade() else {
+            return;
+        };
+        let Some(id) =
+            crate::worker_display::selected_export_profile(&ui, ui.get_export_profile_index())
+        else {
+            return;
+        };
+        let result = crate::payroll_export::delete_profile(
+            &mut conn_clone_delete.borrow_mut(),
+            id,
+            &admin_context(&ui_handle_delete),
+        );
+        match result {
+            Ok(()) => {
+                ui.set_export_profile_index(-1);
+                crate::worker_display::refresh_export_profiles(
+                    &conn_clone_delete,
+                    &ui_handle_delete,
+                );
+                ui.set_export_profile_status_message("Perfil eliminado".into());
+            }
+            Err(e) => show_error(&ui, format!("Error al eliminar el perfil: {}", e)),
+        }
+    });
+
+    let conn_clone_export = conn.clone();
+    let ui_handle_export = ui.as_weak();
+    ui.on_export_payroll(move |index| {
+        let Some(ui) = ui_handle_export.upgrade() else {
+            return;
+        };
+        ui.set_report_status_message("".into());
+        let range = match selected_report_range(&ui) {
+            Ok(range) => range,
+            Err(message) => {
+                show_error(&ui, message);
+                return;
+            }
+        };
+        let Some(id) = crate::worker_display::selected_export_profile(&ui, index) else {
+            return;
+        };
+        let output_dir =
+            resolve_output_directory(ui.get_report_output_directory().as_ref(), &range.key());
+        let profile = crate::payroll_export::get_profile(&conn_clone_export.borrow(), id);
+        let profile = match profile {
+            Ok(Some(profile)) => profile,
+            Ok(None) => return,
+            Err(e) => {
+                show_error(&ui, format!("Error al cargar el perfil: {}", e));
+                return;
+            }
+        };
+        let result =
+            reports::export_payroll(&conn_clone_export.borrow(), range, &profile, &output_dir);
+        match result {
+            Ok(export) => {
+                ui.set_last_report_directory(output_dir.display().to_string().into());
+                let mut message = format!(
+                    "{} trabajadores exportados a {}",
+                    export.workers,
+                    export.path.display()
+                );
+                if !export.missing_rut.is_empty() {
+                    message.push_str(&format!("; sin RUT: {}", export.missing_rut.join(", ")));
+                }
+                ui.set_report_status_message(message.into());
+            }
+            Err(e) => show_error(&ui, format!("Error al exportar remuneraciones: {}", e)),
+        }
+    });
+}
+
+/// The range picked with "Desde" and "Hasta" in the Reports tab.
+fn selected_report_range(ui: &crate::ui::MainWindow) -> Result<ReportRange, String> {
+    let parse = |date: slint::SharedString| NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok();
+    match (
+        parse(ui.get_report_start_date()),
+        parse(ui.get_report_end_date()),
+    ) {
+        (Some(start), Some(end)) => ReportRange::new(start, end)
+            .ok_or_else(|| "La fecha de término es anterior a la de inicio".to_string()),
+        _ => Err("Elija las fechas de inicio y término".to_string()),
+    }
+}
+
+fn show_error(ui: &crate::ui::MainWindow, message: String) {
+    ui.set_error_dialog_message(message.into());
+    ui.set_show_error_dialog(true);
+    ui.set_trigger_error_dialog_show(true);
+}
+
+/// Audit context for changes made from the admin tabs, attributed to the
+/// operator name entered in Settings.
+fn admin_context(ui_handle: &slint::Weak<crate::ui::MainWindow>) -> AuditContext {
+    let operator = ui_handle
+        .upgrade()
+        .map(|ui| ui.get_operator_name().to_string())
+        .unwrap_or_default();
+    AuditContext::admin(&operator)
+}
+
+fn resolve_output_directory(base: &str, range_key: &str) -> PathBuf {
     let trimmed = base.trim();
     if trimmed.is_empty() {
-        return PathBuf::from(format!("reports/{}", month_label));
+        return crate::config::data_dir().reports_dir().join(range_key);
     }
 
     let base_path = PathBuf::from(trimmed);
     if base_path
         .file_name()
         .and_then(|name| name.to_str())
-        .map(|name| name == month_label)
+        .map(|name| name == range_key)
         .unwrap_or(false)
     {
         base_path
     } else {
-        base_path.join(month_label)
+        base_path.join(range_key)
     }
 }
 
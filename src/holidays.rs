@@ -0,0 +1,410 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use chrono::NaiveDate;
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::audit::{self, AuditContext};
+use crate::db::{DbError, Result};
+
+/// National public holidays (feriados legales) in Chile, with the moves
+/// required by law already applied. Regional holidays are not included;
+/// import them from a file where they apply.
+pub const CHILE_HOLIDAYS: &[(&str, &str)] = &[
+    ("2024-01-01", "Año Nuevo"),
+    ("2024-03-29", "Viernes Santo"),
+    ("2024-03-30", "Sábado Santo"),
+    ("2024-05-01", "Día Nacional del Trabajo"),
+    ("2024-05-21", "Día de las Glorias Navales"),
+    ("2024-06-20", "Día Nacional de los Pueblos Indígenas"),
+    ("2024-06-29", "San Pedro y San Pablo"),
+    ("2024-07-16", "Día de la Virgen del Carmen"),
+    ("2024-08-15", "Asunción de la Virgen"),
+    ("2024-09-18", "Independencia Nacional"),
+    ("2024-09-19", "Día de las Glorias del Ejército"),
+    ("2024-09-20", "Feriado adicional Fiestas Patrias"),
+    ("2024-10-12", "Encuentro de Dos Mundos"),
+    ("2024-10-26", "Elecciones municipales y regionales"),
+    ("2024-10-27", "Elecciones municipales y regionales"),
+    (
+        "2024-10-31",
+        "Día de las Iglesias Evangélicas y Protestantes",
+    ),
+    ("2024-11-01", "Día de Todos los Santos"),
+    ("2024-11-24", "Segunda vuelta de gobernadores regionales"),
+    ("2024-12-08", "Inmaculada Concepción"),
+    ("2024-12-25", "Navidad"),
+    ("2025-01-01", "Año Nuevo"),
+    ("2025-04-18", "Viernes Santo"),
+    ("2025-04-19", "Sábado Santo"),
+    ("2025-05-01", "Día Nacional del Trabajo"),
+    ("2025-05-21", "Día de las Glorias Navales"),
+    ("2025-06-20", "Día Nacional de los Pueblos Indígenas"),
+    ("2025-06-29", "San Pedro y San Pablo"),
+    ("2025-07-16", "Día de la Virgen del Carmen"),
+    ("2025-08-15", "Asunción de la Virgen"),
+    ("2025-09-18", "Independencia Nacional"),
+    ("2025-09-19", "Día de las Glorias del Ejército"),
+    ("2025-10-12", "Encuentro de Dos Mundos"),
+    (
+        "2025-10-31",
+        "Día de las Iglesias Evangélicas y Protestantes",
+    ),
+    ("2025-11-01", "Día de Todos los Santos"),
+    ("2025-11-16", "Elecciones presidenciales y parlamentarias"),
+    ("2025-12-08", "Inmaculada Concepción"),
+    ("2025-12-14", "Segunda vuelta presidencial"),
+    ("2025-12-25", "Navidad"),
+    ("2026-01-01", "Año Nuevo"),
+    ("2026-04-03", "Viernes Santo"),
+    ("2026-04-04", "Sábado Santo"),
+    ("2026-05-01", "Día Nacional del Trabajo"),
+    ("2026-05-21", "Día de las Glorias Navales"),
+    ("2026-06-21", "Día Nacional de los Pueblos Indígenas"),
+    ("2026-06-29", "San Pedro y San Pablo"),
+    ("2026-07-16", "Día de la Virgen del Carmen"),
+    ("2026-08-15", "Asunción de la Virgen"),
+    ("2026-09-18", "Independencia Nacional"),
+    ("2026-09-19", "Día de las Glorias del Ejército"),
+    ("2026-10-12", "Encuentro de Dos Mundos"),
+    (
+        "2026-10-31",
+        "Día de las Iglesias Evangélicas y Protestantes",
+    ),
+    ("2026-11-01", "Día de Todos los Santos"),
+    ("2026-12-08", "Inmaculada Concepción"),
+    ("2026-12-25", "Navidad"),
+    ("2027-01-01", "Año Nuevo"),
+    ("2027-03-26", "Viernes Santo"),
+    ("2027-03-27", "Sábado Santo"),
+    ("2027-05-01", "Día Nacional del Trabajo"),
+    ("2027-05-21", "Día de las Glorias Navales"),
+    ("2027-06-21", "Día Nacional de los Pueblos Indígenas"),
+    ("2027-06-28", "San Pedro y San Pablo"),
+    ("2027-07-16", "Día de la Virgen del Carmen"),
+    ("2027-08-15", "Asunción de la Virgen"),
+    ("2027-09-18", "Independencia Nacional"),
+    ("2027-09-19", "Día de las Glorias del Ejército"),
+    ("2027-10-11", "Encuentro de Dos Mundos"),
+    (
+        "2027-10-31",
+        "Día de las Iglesias Evangélicas y Protestantes",
+    ),
+    ("2027-11-01", "Día de Todos los Santos"),
+    ("2027-12-08", "Inmaculada Concepción"),
+    ("2027-12-25", "Navidad"),
+];
+
+/// A day on which nobody is required to work.
+#[derive(Clone, Debug, Serialize)]
+pub struct Holiday {
+    pub date: NaiveDate,
+    pub name: String,
+    /// Where the row came from: "chile", "csv", "ics" or "manual".
+    pub source: String,
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(std::io::Error),
+    Format(String),
+    Database(DbError),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(e) => write!(f, "no se pudo leer el archivo: {}", e),
+            ImportError::Format(m) => write!(f, "formato inválido: {}", m),
+            ImportError::Database(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<std::io::Error> for ImportError {
+    fn from(value: std::io::Error) -> Self {
+        ImportError::Io(value)
+    }
+}
+
+impl From<DbError> for ImportError {
+    fn from(value: DbError) -> Self {
+        ImportError::Database(value)
+    }
+}
+
+fn holiday_from_row(row: &rusqlite::Row) -> rusqlite::Result<Option<Holiday>> {
+    let date: String = row.get(0)?;
+    Ok(NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .ok()
+        .map(|date| Holiday {
+            date,
+            name: row.get(1).unwrap_or_default(),
+            source: row.get(2).unwrap_or_default(),
+        }))
+}
+
+pub fn get_holiday(conn: &Connection, date: NaiveDate) -> Result<Option<Holiday>> {
+    Ok(conn
+        .query_row(
+            "SELECT date, name, source FROM holidays WHERE date = ?",
+            rusqlite::params![date.to_string()],
+            holiday_from_row,
+        )
+        .optional()?
+        .flatten())
+}
+
+/// Holidays from `from` through `to` (inclusive), keyed by date.
+pub fn holidays_between(
+    conn: &Connection,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<BTreeMap<NaiveDate, Holiday>> {
+    let mut stmt = conn.prepare(
+        "SELECT date, name, source FROM holidays WHERE date BETWEEN ? AND ? ORDER BY date",
+    )?;
+    let rows = stmt.query_map(
+        rusqlite::params![from.to_string(), to.to_string()],
+        holiday_from_row,
+    )?;
+    let mut holidays = BTreeMap::new();
+    for row in rows {
+        if let Some(holiday) = row? {
+            holidays.insert(holiday.date, holiday);
+        }
+    }
+    Ok(holidays)
+}
+
+/// Adds or renames holidays and records one audit entry for the batch.
+/// Returns how many dates were new.
+pub fn save_holidays(
+    conn: &mut Connection,
+    holidays: &[Holiday],
+    ctx: &AuditContext,
+) -> Result<usize> {
+    if let Some(holiday) = holidays.iter().find(|h| h.name.trim().is_empty()) {
+        return Err(DbError::Validation(format!(
+            "el feriado del {} necesita un nombre",
+            holiday.date
+        )));
+    }
+    let tx = conn.transaction()?;
+    let mut added = 0;
+    for holiday in holidays {
+        // Renaming is fine; a new holiday changes the required hours.
+        if get_holiday(&tx, holiday.date)?.is_none() {
+            crate::hour_bank::check_open(&tx, holiday.date)?;
+            added += 1;
+        }
+        tx.execute(
+            "INSERT INTO holidays (date, name, source) VALUES (?, ?, ?)
+             ON CONFLICT(date) DO UPDATE SET name = excluded.name, source = excluded.source",
+            rusqlite::params![
+                holiday.date.to_string(),
+                holiday.name.trim(),
+                holiday.source
+            ],
+        )?;
+    }
+    audit::record(
+        &tx,
+        ctx,
+        "holidays",
+        None,
+        "import",
+        None,
+        serde_json::to_value(holidays).ok(),
+    )?;
+    tx.commit()?;
+    Ok(added)
+}
+
+pub fn delete_holiday(conn: &mut Connection, date: NaiveDate, ctx: &AuditContext) -> Result<()> {
+    let tx = conn.transaction()?;
+    let before = get_holiday(&tx, date)?
+        .ok_or_else(|| DbError::Validation(format!("no hay feriado el {}", date)))?;
+    crate::hour_bank::check_open(&tx, date)?;
+    tx.execute(
+        "DELETE FROM holidays WHERE date = ?",
+        rusqlite::params![date.to_string()],
+    )?;
+    audit::record(
+        &tx,
+        ctx,
+        "holidays",
+        None,
+        "delete",
+        audit::snapshot(&before),
+        None,
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// The bundled Chilean list as rows ready to save.
+pub fn chile_holidays() -> Vec<Holiday> {
+    CHILE_HOLIDAYS
+        .iter()
+        .filter_map(|(date, name)| {
+            Some(Holiday {
+                date: NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?,
+                name: (*name).to_string(),
+                source: "chile".to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Reads a holiday file, choosing the format from the extension, and saves
+/// its contents. Returns how many dates were new.
+pub fn import_file(
+    conn: &mut Connection,
+    path: &Path,
+    ctx: &AuditContext,
+) -> Result<usize, ImportError> {
+    let contents = std::fs::read_to_string(path)?;
+    let is_ics = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ics"));
+    let holidays = if is_ics {
+        parse_ics(&contents)?
+    } else {
+        parse_csv(&contents)?
+    };
+    if holidays.is_empty() {
+        return Err(ImportError::Format(
+            "el archivo no contiene feriados".to_string(),
+        ));
+    }
+    let ctx = ctx
+        .clone()
+        .with_reason(&format!("importado desde {}", path.display()));
+    Ok(save_holidays(conn, &holidays, &ctx)?)
+}
+
+/// Parses "fecha,nombre" lines with dates as YYYY-MM-DD or DD-MM-YYYY
+/// (also with slashes). A first line that does not start with a date is
+/// taken as a header; `;` is accepted as separator.
+pub fn parse_csv(contents: &str) -> Result<Vec<Holiday>, ImportError> {
+    let mut holidays = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() {
+            continue;
+        }
+        let (date, name) = line.split_once([',', ';']).unwrap_or((line, "Feriado"));
+        let Some(date) = parse_csv_date(date) else {
+            if index == 0 {
+                continue;
+            }
+            return Err(ImportError::Format(format!(
+                "línea {}: fecha '{}' no reconocida",
+                index + 1,
+                date.trim()
+            )));
+        };
+        let name = name.trim().trim_matches('"').trim();
+        holidays.push(Holiday {
+            date,
+            name: if name.is_empty() { "Feriado" } else { name }.to_string(),
+            source: "csv".to_string(),
+        });
+    }
+    Ok(holidays)
+}
+
+fn parse_csv_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim().trim_matches('"');
+    ["%Y-%m-%d", "%d-%m-%Y", "%Y/%m/%d", "%d/%m/%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+}
+
+/// Extracts all-day events from an iCalendar file. An event spanning
+/// several days (DTEND is exclusive) yields one holiday per day.
+pub fn parse_ics(contents: &str) -> Result<Vec<Holiday>, ImportError> {
+    // Continuation lines start with a space or tab and belong to the
+    // previous line.
+    let mut lines: Vec<String> = Vec::new();
+    for raw in contents.lines() {
+        match raw.strip_prefix([' ', '\t']) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().expect("non-empty").push_str(rest),
+            _ => lines.push(raw.trim_end().to_string()),
+        }
+    }
+
+    let mut holidays = Vec::new();
+    let mut event: Option<(Option<NaiveDate>, Option<NaiveDate>, String)> = None;
+    for line in &lines {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let name = key.split(';').next().unwrap_or(key).to_ascii_uppercase();
+        match (name.as_str(), value) {
+            ("BEGIN", "VEVENT") => event = Some((None, None, String::new())),
+            ("DTSTART", _) => {
+                if let Some(event) = event.as_mut() {
+                    event.0 = parse_ics_date(value);
+                }
+            }
+            ("DTEND", _) => {
+                if let Some(event) = event.as_mut() {
+                    event.1 = parse_ics_date(value);
+                }
+            }
+            ("SUMMARY", _) => {
+                if let Some(event) = event.as_mut() {
+                    event.2 = unescape_ics(value);
+                }
+            }
+            ("END", "VEVENT") => {
+                let Some((Some(start), end, summary)) = event.take() else {
+                    return Err(ImportError::Format(
+                        "evento sin fecha de inicio".to_string(),
+                    ));
+                };
+                let end = end
+                    .filter(|end| *end > start)
+                    .unwrap_or(start.succ_opt().unwrap_or(start));
+                let mut day = start;
+                while day < end {
+                    holidays.push(Holiday {
+                        date: day,
+                        name: if summary.trim().is_empty() {
+                            "Feriado".to_string()
+                        } else {
+                            summary.trim().to_string()
+                        },
+                        source: "ics".to_string(),
+                    });
+                    let Some(next) = day.succ_opt() else {
+                        break;
+                    };
+                    day = next;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(holidays)
+}
+
+fn parse_ics_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim().get(..8)?, "%Y%m%d").ok()
+}
+
+fn unescape_ics(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
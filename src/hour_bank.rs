@@ -0,0 +1,428 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::audit::{self, AuditContext};
+use crate::db::{DbError, Result};
+
+/// A worker's banco de horas for a closed month. `month` is "YYYY-MM".
+/// The closing balance is the opening of the next month.
+#[derive(Clone, Debug, Serialize)]
+pub struct MonthClose {
+    pub worker_id: i64,
+    pub month: String,
+    pub opening_minutes: i64,
+    /// Sum of the month's daily balances (worked and credited time minus
+    /// scheduled time).
+    pub delta_minutes: i64,
+    pub adjustment_minutes: i64,
+    pub closing_minutes: i64,
+    pub closed_at: DateTime<Utc>,
+}
+
+/// A manual change to a worker's bank, e.g. hours paid out (negative) or a
+/// compensation day taken. It counts in the month of `date`.
+#[derive(Clone, Debug, Serialize)]
+pub struct Adjustment {
+    pub id: i64,
+    pub worker_id: i64,
+    pub date: NaiveDate,
+    pub minutes: i64,
+    pub reason: String,
+}
+
+/// A worker's bank for one month, closed or still provisional.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MonthBalance {
+    pub opening: i64,
+    pub delta: i64,
+    pub adjustments: i64,
+    pub closing: i64,
+    pub closed: bool,
+}
+
+/// Parses "+2:30", "-1:15", "90" or "-90" into signed minutes.
+pub fn parse_minutes(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (sign, rest) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let minutes = match rest.split_once(':') {
+        Some((hours, minutes)) => {
+            let hours: i64 = hours.trim().parse().ok()?;
+            let minutes: i64 = minutes.trim().parse().ok()?;
+            if hours < 0 || !(0..60).contains(&minutes) {
+                return None;
+            }
+            hours * 60 + minutes
+        }
+        None => rest.trim().parse::<u32>().ok()?.into(),
+    };
+    Some(sign * minutes)
+}
+
+/// Signed "HH:MM", e.g. "+02:30" or "-01:15".
+pub fn format_balance(minutes: i64) -> String {
+    let sign = if minutes < 0 { '-' } else { '+' };
+    let abs = minutes.abs();
+    format!("{}{:02}:{:02}", sign, abs / 60, abs % 60)
+}
+
+const CLOSE_COLUMNS: &str = "worker_id, month, opening_minutes, delta_minutes, adjustment_minutes, closing_minutes, closed_at";
+
+fn close_from_row(row: &rusqlite::Row) -> rusqlite::Result<Option<MonthClose>> {
+    let closed_at: String = row.get(6)?;
+    let Ok(closed_at) = DateTime::parse_from_rfc3339(&closed_at) else {
+        return Ok(None);
+    };
+    Ok(Some(MonthClose {
+        worker_id: row.get(0)?,
+        month: row.get(1)?,
+        opening_minutes: row.get(2)?,
+        delta_minutes: row.get(3)?,
+        adjustment_minutes: row.get(4)?,
+        closing_minutes: row.get(5)?,
+        closed_at: closed_at.with_timezone(&Utc),
+    }))
+}
+
+pub fn get_close(conn: &Connection, worker_id: i64, month: &str) -> Result<Option<MonthClose>> {
+    Ok(conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM hour_bank_closes WHERE worker_id = ? AND month = ?",
+                CLOSE_COLUMNS
+            ),
+            rusqlite::params![worker_id, month],
+            close_from_row,
+        )
+        .optional()?
+        .flatten())
+}
+
+/// The balance carried into `month`: the closing of the worker's last
+/// closed month before it, or zero before the first close.
+pub fn opening_balance(conn: &Connection, worker_id: i64, month: &str) -> Result<i64> {
+    Ok(conn
+        .query_row(
+            "SELECT closing_minutes FROM hour_bank_closes
+             WHERE worker_id = ? AND month < ? ORDER BY month DESC LIMIT 1",
+            rusqlite::params![worker_id, month],
+            |row| row.get(0),
+        )
+        .optional()?
+        .unwrap_or(0))
+}
+
+/// The most recent month closed for any worker.
+pub fn last_closed_month(conn: &Connection) -> Result<Option<String>> {
+    Ok(
+        conn.query_row("SELECT MAX(month) FROM hour_bank_closes", [], |row| {
+            row.get(0)
+        })?,
+    )
+}
+
+/// Rejects changes to `day` once its month is closed: the stored balance
+/// would no longer match the timesheets. Every write that changes worked or
+/// required hours checks this.
+pub fn check_open(conn: &Connection, day: NaiveDate) -> Result<()> {
+    let month = day.format("%Y-%m").to_string();
+    if last_closed_month(conn)?.is_some_and(|last| last >= month) {
+        return Err(DbError::Validation(format!(
+            "el banco de horas de {} ya está cerrado",
+            month
+        )));
+    }
+    Ok(())
+}
+
+const ADJUSTMENT_COLUMNS: &str = "id, worker_id, date, minutes, reason";
+
+fn adjustment_from_row(row: &rusqlite::Row) -> rusqlite::Result<Option<Adjustment>> {
+    let date: String = row.get(2)?;
+    let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") else {
+        return Ok(None);
+    };
+    Ok(Some(Adjustment {
+        id: row.get(0)?,
+        worker_id: row.get(1)?,
+        date,
+        minutes: row.get(3)?,
+        reason: row.get(4)?,
+    }))
+}
+
+/// The worker's adjustments dated in `month`, oldest first.
+pub fn month_adjustments(
+    conn: &Connection,
+    worker_id: i64,
+    month: &str,
+) -> Result<Vec<Adjustment>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM hour_bank_adjustments
+         WHERE worker_id = ? AND substr(date, 1, 7) = ? ORDER BY date, id",
+        ADJUSTMENT_COLUMNS
+    ))?;
+    let rows = stmt.query_map(rusqlite::params![worker_id, month], adjustment_from_row)?;
+    let mut adjustments = Vec::new();
+    for row in rows {
+        if let Some(adjustment) = row? {
+            adjustments.push(adjustment);
+        }
+    }
+    Ok(adjustments)
+}
+
+fn get_adjustment(conn: &Connection, id: i64) -> Result<Option<Adjustment>> {
+    Ok(conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM hour_bank_adjustments WHERE id = ?",
+                ADJUSTMENT_COLUMNS
+            ),
+            rusqlite::params![id],
+            adjustment_from_row,
+        )
+        .optional()?
+        .flatten())
+}
+
+/// The worker's bank for `month`. A closed month reports what was stored
+/// at the close; an open one adds `delta` (the month's balance so far) and
+/// the adjustments to the opening balance.
+pub fn month_balance(
+    conn: &Connection,
+    worker_id: i64,
+    month: &str,
+    delta: i64,
+) -> Result<MonthBalance> {
+    if let Some(close) = get_close(conn, worker_id, month)? {
+        return Ok(MonthBalance {
+            opening: close.opening_minutes,
+            delta: close.delta_minutes,
+            adjustments: close.adjustment_minutes,
+            closing: close.closing_minutes,
+            closed: true,
+        });
+    }
+    let opening = opening_balance(conn, worker_id, month)?;
+    let adjustments = month_adjustments(conn, worker_id, month)?
+        .iter()
+        .map(|a| a.minutes)
+        .sum();
+    Ok(MonthBalance {
+        opening,
+        delta,
+        adjustments,
+        closing: opening + delta + adjustments,
+        closed: false,
+    })
+}
+
+/// Records a manual adjustment and returns its id. Closed months are
+/// final, so the adjustment must be dated after the last close.
+pub fn add_adjustment(
+    conn: &mut Connection,
+    adjustment: &Adjustment,
+    ctx: &AuditContext,
+) -> Result<i64> {
+    let reason = adjustment.reason.trim();
+    if reason.is_empty() {
+        return Err(DbError::Validation(
+            "indique el motivo del ajuste".to_string(),
+        ));
+    }
+    if adjustment.minutes == 0 {
+        return Err(DbError::Validation(
+            "el ajuste no puede ser de cero minutos".to_string(),
+        ));
+    }
+    let tx = conn.transaction()?;
+    check_open(&tx, adjustment.date)?;
+    tx.execute(
+        "INSERT INTO hour_bank_adjustments (worker_id, date, minutes, reason, created_at)
+         VALUES (?, ?, ?, ?, ?)",
+        rusqlite::params![
+            adjustment.worker_id,
+            adjustment.date.to_string(),
+            adjustment.minutes,
+            reason,
+            Utc::now().to_rfc3339()
+        ],
+    )?;
+    let id = tx.last_insert_rowid();
+    let after = get_adjustment(&tx, id)?;
+    audit::record(
+        &tx,
+        ctx,
+        "hour_bank_adjustments",
+        Some(id),
+        "create",
+        None,
+        after.as_ref().and_then(audit::snapshot),
+    )?;
+    tx.commit()?;
+    Ok(id)
+}
+
+/// Months close in order: only the month after the last close, or the last
+/// closed month again (to pick up sessions closed after it), can be closed.
+pub fn check_close_order(conn: &Connection, month: &str) -> Result<()> {
+    let Some(last) = last_closed_month(conn)? else {
+        return Ok(());
+    };
+    if last.as_str() > month {
+        return Err(DbError::Validation(format!(
+            "ya existe un cierre posterior ({})",
+            last
+        )));
+    }
+    let next = NaiveDate::parse_from_str(&format!("{}-01", last), "%Y-%m-%d")
+        .ok()
+        .and_then(|first| first.checked_add_months(chrono::Months::new(1)))
+        .map(|next| next.format("%Y-%m").to_string());
+    if last != month && next.as_deref() != Some(month) {
+        return Err(DbError::Validation(format!(
+            "cierre primero {}",
+            next.unwrap_or(last)
+        )));
+    }
+    Ok(())
+}
+
+/// Stores the close of `month` for a worker, replacing an earlier close of
+/// the same month. Runs inside the caller's transaction.
+pub(crate) fn record_close(
+    tx: &rusqlite::Transaction,
+    worker_id: i64,
+    month: &str,
+    delta: i64,
+    ctx: &AuditContext,
+) -> Result<MonthClose> {
+    let before = get_close(tx, worker_id, month)?;
+    let opening = opening_balance(tx, worker_id, month)?;
+    let adjustments: i64 = month_adjustments(tx, worker_id, month)?
+        .iter()
+        .map(|a| a.minutes)
+        .sum();
+    let close = MonthClose {
+        worker_id,
+        month: month.to_string(),
+        opening_minutes: opening,
+        delta_minutes: delta,
+        adjustment_minutes: adjustments,
+        closing_minutes: opening + delta + adjustments,
+        closed_at: Utc::now(),
+    };
+    tx.execute(
+        "INSERT INTO hour_bank_closes
+            (worker_id, month, opening_minutes, delta_minutes, adjustment_minutes, closing_minutes, closed_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(worker_id, month) DO UPDATE SET
+            opening_minutes = excluded.opening_minutes,
+            delta_minutes = excluded.delta_minutes,
+            adjustment_minutes = excluded.adjustment_minutes,
+            closing_minutes = excluded.closing_minutes,
+            closed_at = excluded.closed_at",
+        rusqlite::params![
+            worker_id,
+            month,
+            close.opening_minutes,
+            close.delta_minutes,
+            close.adjustment_minutes,
+            close.closing_minutes,
+            close.closed_at.to_rfc3339()
+        ],
+    )?;
+    audit::record(
+        tx,
+        ctx,
+        "hour_bank_closes",
+        Some(worker_id),
+        "close",
+        before.as_ref().and_then(audit::snapshot),
+        audit::snapshot(&close),
+    )?;
+    Ok(close)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::absences::{Absence, AbsenceKind, add_absence, delete_absence, update_absence};
+    use crate::holidays::{Holiday, delete_holiday, save_holidays};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn absences_and_holidays_stay_out_of_closed_months() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&mut conn, Path::new("backups")).unwrap();
+        let ctx = AuditContext::admin("test");
+        let worker_id = crate::db::add_worker(&mut conn, "Prueba", "1000", &ctx).unwrap();
+        let mut absence = Absence {
+            id: 0,
+            worker_id,
+            kind: AbsenceKind::LicenciaMedica,
+            start_date: date(2026, 2, 25),
+            end_date: date(2026, 3, 3),
+            paid: true,
+            note: String::new(),
+        };
+        absence.id = add_absence(&mut conn, &absence, &ctx).unwrap();
+        let holiday = |day: NaiveDate| Holiday {
+            date: day,
+            name: "Feriado local".to_string(),
+            source: "manual".to_string(),
+        };
+        save_holidays(&mut conn, &[holiday(date(2026, 2, 20))], &ctx).unwrap();
+        let tx = conn.transaction().unwrap();
+        record_close(&tx, worker_id, "2026-02", 0, &ctx).unwrap();
+        tx.commit().unwrap();
+
+        let closed = |result: Result<()>| {
+            assert!(
+                matches!(&result, Err(DbError::Validation(message)) if message.contains("2026-02")),
+                "{:?}",
+                result
+            );
+        };
+        let march = Absence {
+            start_date: date(2026, 2, 27),
+            end_date: date(2026, 3, 5),
+            ..absence.clone()
+        };
+        closed(add_absence(&mut conn, &march, &ctx).map(|_| ()));
+        closed(update_absence(&mut conn, &march, &ctx));
+        closed(delete_absence(&mut conn, absence.id, &ctx));
+        closed(save_holidays(&mut conn, &[holiday(date(2026, 2, 18))], &ctx).map(|_| ()));
+        closed(delete_holiday(&mut conn, date(2026, 2, 20), &ctx));
+
+        // The note and the name of a holiday change no hours.
+        let noted = Absence {
+            note: "certificado recibido".to_string(),
+            ..absence.clone()
+        };
+        update_absence(&mut conn, &noted, &ctx).unwrap();
+        let mut renamed = holiday(date(2026, 2, 20));
+        renamed.name = "Aniversario comunal".to_string();
+        assert_eq!(save_holidays(&mut conn, &[renamed], &ctx).unwrap(), 0);
+        add_absence(
+            &mut conn,
+            &Absence {
+                start_date: date(2026, 3, 10),
+                end_date: date(2026, 3, 12),
+                ..absence
+            },
+            &ctx,
+        )
+        .unwrap();
+        save_holidays(&mut conn, &[holiday(date(2026, 3, 18))], &ctx).unwrap();
+    }
+}
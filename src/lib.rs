@@ -1,10 +1,30 @@
+pub mod absences;
+pub mod audit;
+pub mod auto_close;
+pub mod badges;
 pub mod barcode;
+pub mod breaks;
+pub mod config;
+pub mod corrections;
 pub mod db;
 pub mod event_handlers;
+pub mod holidays;
+pub mod hour_bank;
+pub mod migrations;
+pub mod overtime;
+pub mod payroll_export;
+pub mod pdf;
+pub mod periods;
+pub mod report_templates;
 pub mod reports;
+pub mod schedules;
+pub mod settings;
+pub mod timekeeping;
 pub mod timers;
+pub mod timezone;
 pub mod types;
 pub mod ui;
 pub mod ui_setup;
 pub mod utils;
+pub mod vacations;
 pub mod worker_display;
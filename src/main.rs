@@ -3,17 +3,57 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let conn = timesheet::db::init_db()?;
+    let data_dir = timesheet::config::DataDir::resolve(std::env::args().skip(1))?;
+    data_dir.ensure_dirs()?;
+    let data_dir = timesheet::config::init(data_dir);
+    let db_path = data_dir.database_path();
+    let legacy = data_dir.adopt_legacy_database();
+    let db_existed = db_path.exists();
+    let conn = timesheet::db::init_db(&db_path, &data_dir.backups_dir())?;
+    timesheet::config::log_event(&format!(
+        "Base de datos abierta: {} ({}, desde {})",
+        db_path.display(),
+        if db_existed { "existente" } else { "nueva" },
+        data_dir.source()
+    ));
+    let startup_warning = match legacy {
+        timesheet::config::LegacyDatabase::None => None,
+        timesheet::config::LegacyDatabase::Adopted(path) => {
+            timesheet::config::log_event(&format!(
+                "Base de datos anterior copiada desde {}",
+                path.display()
+            ));
+            None
+        }
+        timesheet::config::LegacyDatabase::Failed(path, e) => {
+            let message = format!(
+                "No se pudo copiar la base de datos anterior ({}): {}. Se abrió una base \
+                 de datos vacía en {}; cierre el programa y copie el archivo a mano o use \
+                 --data-dir para abrirla.",
+                path.display(),
+                e,
+                db_path.display()
+            );
+            timesheet::config::log_event(&message);
+            Some(message)
+        }
+    };
+    let time_zone = timesheet::timezone::init(&conn)?;
+    timesheet::config::log_event(&format!("Zona horaria: {}", time_zone.name()));
     let conn = Rc::new(RefCell::new(conn));
 
     let ui = timesheet::ui::MainWindow::new()?;
 
     let ui_handle = ui.as_weak();
     timesheet::ui_setup::initialize_ui_and_data(&ui, &conn, &ui_handle)?;
+    if let Some(message) = startup_warning {
+        ui.set_startup_warning(message.into());
+        ui.set_trigger_startup_warning_show(true);
+    }
 
     timesheet::event_handlers::setup_event_handlers(conn.clone(), &ui);
 
-    timesheet::timers::setup_timers(conn, ui_handle);
+    let _timers = timesheet::timers::setup_timers(conn, ui_handle);
 
     ui.run()?;
     Ok(())
@@ -0,0 +1,710 @@
+use chrono::Utc;
+use rusqlite::{Connection, Transaction, TransactionBehavior};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A single ordered schema upgrade. `up` runs inside its own transaction and
+/// the `user_version` pragma is bumped in that same transaction, so a failed
+/// step leaves the database exactly at the previous version.
+struct Migration {
+    version: i64,
+    description: &'static str,
+    up: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "baseline workers and timesheets tables",
+        up: v1_baseline,
+    },
+    Migration {
+        version: 2,
+        description: "quarantine table for unreadable timesheet rows",
+        up: v2_timesheets_quarantine,
+    },
+    Migration {
+        version: 3,
+        description: "at most one open session per worker",
+        up: v3_one_open_session,
+    },
+    Migration {
+        version: 4,
+        description: "append-only audit log",
+        up: v4_audit_log,
+    },
+    Migration {
+        version: 5,
+        description: "correction reason on timesheet rows",
+        up: v5_correction_reason,
+    },
+    Migration {
+        version: 6,
+        description: "settings table and auto-close marker on timesheet rows",
+        up: v6_settings_and_auto_close,
+    },
+    Migration {
+        version: 7,
+        description: "badge history with validity ranges",
+        up: v7_worker_badges,
+    },
+    Migration {
+        version: 8,
+        description: "weekly schedule templates and assignments",
+        up: v8_schedules,
+    },
+    Migration {
+        version: 9,
+        description: "holiday calendar seeded with Chilean public holidays",
+        up: v9_holidays,
+    },
+    Migration {
+        version: 10,
+        description: "worker absences and leave",
+        up: v10_absences,
+    },
+    Migration {
+        version: 11,
+        description: "hire date and prior service on workers",
+        up: v11_worker_employment,
+    },
+    Migration {
+        version: 12,
+        description: "breaks inside timesheet sessions",
+        up: v12_breaks,
+    },
+    Migration {
+        version: 13,
+        description: "hour bank closes and adjustments",
+        up: v13_hour_bank,
+    },
+    Migration {
+        version: 14,
+        description: "RUT on workers",
+        up: v14_worker_rut,
+    },
+    Migration {
+        version: 15,
+        description: "payroll export profiles seeded with a generic one",
+        up: v15_export_profiles,
+    },
+    Migration {
+        version: 16,
+        description: "dated versions of schedule templates",
+        up: v16_schedule_versions,
+    },
+    Migration {
+        version: 17,
+        description: "quarantine table for unreadable break rows",
+        up: v17_breaks_quarantine,
+    },
+];
+
+#[derive(Debug)]
+pub enum MigrationError {
+    Database(rusqlite::Error),
+    Backup(String),
+    NewerSchema {
+        found: i64,
+        supported: i64,
+    },
+    Step {
+        version: i64,
+        description: &'static str,
+        source: rusqlite::Error,
+    },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Database(e) => write!(f, "database error: {}", e),
+            MigrationError::Backup(m) => write!(f, "could not back up database: {}", m),
+            MigrationError::NewerSchema { found, supported } => write!(
+                f,
+                "database schema version {} is newer than this program supports ({}); refusing to open it",
+                found, supported
+            ),
+            MigrationError::Step {
+                version,
+                description,
+                source,
+            } => write!(
+                f,
+                "migration {} ({}) failed: {}",
+                version, description, source
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<rusqlite::Error> for MigrationError {
+    fn from(value: rusqlite::Error) -> Self {
+        MigrationError::Database(value)
+    }
+}
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub fn current_version(conn: &Connection) -> rusqlite::Result<i64> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+/// Brings the schema up to `latest_version()`.
+///
+/// Databases created before versioning existed report `user_version = 0`
+/// but already contain tables; the baseline step uses `IF NOT EXISTS` so
+/// it adopts them unchanged. Any non-empty database is copied to
+/// `backup_dir` before the first step runs.
+pub fn migrate(conn: &mut Connection, backup_dir: &Path) -> Result<i64, MigrationError> {
+    let current = current_version(conn)?;
+    let latest = latest_version();
+    if current > latest {
+        return Err(MigrationError::NewerSchema {
+            found: current,
+            supported: latest,
+        });
+    }
+    if current == latest {
+        return Ok(current);
+    }
+
+    if has_user_tables(conn)? {
+        let backup_path = backup_database(conn, backup_dir, current)?;
+        crate::config::log_event(&format!(
+            "Database backed up to {} before upgrading from schema version {} to {}",
+            backup_path.display(),
+            current,
+            latest
+        ));
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        (migration.up)(&tx).map_err(|source| MigrationError::Step {
+            version: migration.version,
+            description: migration.description,
+            source,
+        })?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+        crate::config::log_event(&format!(
+            "Applied migration {}: {}",
+            migration.version, migration.description
+        ));
+    }
+
+    Ok(latest)
+}
+
+fn has_user_tables(conn: &Connection) -> rusqlite::Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+fn backup_database(
+    conn: &Connection,
+    backup_dir: &Path,
+    from_version: i64,
+) -> Result<PathBuf, MigrationError> {
+    std::fs::create_dir_all(backup_dir)
+        .map_err(|e| MigrationError::Backup(format!("{}: {}", backup_dir.display(), e)))?;
+    let file_name = format!(
+        "timesheet-v{}-{}.db",
+        from_version,
+        Utc::now().format("%Y%m%d%H%M%S")
+    );
+    let backup_path = backup_dir.join(file_name);
+    // VACUUM INTO writes a consistent snapshot even if other readers are attached.
+    conn.execute(
+        "VACUUM INTO ?",
+        rusqlite::params![backup_path.display().to_string()],
+    )
+    .map_err(|e| MigrationError::Backup(format!("{}: {}", backup_path.display(), e)))?;
+    Ok(backup_path)
+}
+
+fn v1_baseline(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS workers (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            barcode TEXT NOT NULL UNIQUE,
+            active BOOLEAN DEFAULT 1
+        );
+        CREATE TABLE IF NOT EXISTS timesheets (
+            id INTEGER PRIMARY KEY,
+            worker_id INTEGER NOT NULL,
+            clock_in TEXT NOT NULL,
+            clock_out TEXT,
+            FOREIGN KEY (worker_id) REFERENCES workers(id)
+        );",
+    )
+}
+
+fn v2_timesheets_quarantine(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE timesheets_quarantine (
+            id INTEGER PRIMARY KEY,
+            original_id INTEGER NOT NULL,
+            worker_id INTEGER,
+            clock_in TEXT,
+            clock_out TEXT,
+            reason TEXT NOT NULL,
+            quarantined_at TEXT NOT NULL
+        );",
+    )
+}
+
+fn v3_one_open_session(tx: &Transaction) -> rusqlite::Result<()> {
+    // Older builds could leave several open rows for one worker. Keep the
+    // newest open and move the rest to quarantine, as with unreadable rows,
+    // so they can still be found and re-entered by hand.
+    let moved = tx.execute(
+        "INSERT INTO timesheets_quarantine (original_id, worker_id, clock_in, clock_out, reason, quarantined_at)
+         SELECT id, worker_id, clock_in, clock_out,
+                'timesheets id ' || id || ': clock_out = ''NULL'' (sesión abierta duplicada)',
+                strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now')
+         FROM timesheets
+         WHERE clock_out IS NULL
+           AND id NOT IN (
+               SELECT MAX(id) FROM timesheets WHERE clock_out IS NULL GROUP BY worker_id
+           )",
+        [],
+    )?;
+    if moved > 0 {
+        crate::config::log_event(&format!(
+            "Moved {} duplicate open sessions to quarantine",
+            moved
+        ));
+    }
+    tx.execute_batch(
+        "DELETE FROM timesheets
+         WHERE clock_out IS NULL
+           AND id NOT IN (
+               SELECT MAX(id) FROM timesheets WHERE clock_out IS NULL GROUP BY worker_id
+           );
+        CREATE UNIQUE INDEX idx_timesheets_one_open_session
+            ON timesheets(worker_id) WHERE clock_out IS NULL;",
+    )
+}
+
+fn v4_audit_log(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE audit_log (
+            id INTEGER PRIMARY KEY,
+            at TEXT NOT NULL,
+            actor TEXT NOT NULL,
+            source TEXT NOT NULL,
+            entity TEXT NOT NULL,
+            entity_id INTEGER,
+            action TEXT NOT NULL,
+            before_json TEXT,
+            after_json TEXT,
+            reason TEXT
+        );
+        CREATE INDEX idx_audit_log_entity ON audit_log(entity, entity_id);
+        CREATE TRIGGER audit_log_no_update BEFORE UPDATE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, 'audit_log is append-only');
+        END;
+        CREATE TRIGGER audit_log_no_delete BEFORE DELETE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, 'audit_log is append-only');
+        END;",
+    )
+}
+
+fn v5_correction_reason(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE timesheets ADD COLUMN correction_reason TEXT;")
+}
+
+fn v6_settings_and_auto_close(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+        ALTER TABLE timesheets ADD COLUMN auto_close TEXT;",
+    )
+}
+
+fn v7_worker_badges(tx: &Transaction) -> rusqlite::Result<()> {
+    // Each worker's current barcode becomes their first badge, valid from
+    // their first punch. Punches that came from a scan (not corrected, not
+    // auto-closed) are attributed to that badge.
+    tx.execute_batch(
+        "CREATE TABLE worker_badges (
+            id INTEGER PRIMARY KEY,
+            worker_id INTEGER NOT NULL,
+            barcode TEXT NOT NULL,
+            valid_from TEXT NOT NULL,
+            valid_to TEXT,
+            revoked_reason TEXT,
+            FOREIGN KEY (worker_id) REFERENCES workers(id)
+        );
+        INSERT INTO worker_badges (worker_id, barcode, valid_from)
+            SELECT id, barcode, COALESCE(
+                (SELECT MIN(clock_in) FROM timesheets WHERE timesheets.worker_id = workers.id),
+                strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+            )
+            FROM workers;
+        CREATE UNIQUE INDEX idx_worker_badges_active_barcode
+            ON worker_badges(barcode) WHERE valid_to IS NULL;
+        CREATE INDEX idx_worker_badges_worker ON worker_badges(worker_id);
+
+        ALTER TABLE timesheets ADD COLUMN clock_in_badge_id INTEGER REFERENCES worker_badges(id);
+        ALTER TABLE timesheets ADD COLUMN clock_out_badge_id INTEGER REFERENCES worker_badges(id);
+        UPDATE timesheets SET
+            clock_in_badge_id = (
+                SELECT id FROM worker_badges WHERE worker_badges.worker_id = timesheets.worker_id
+            ),
+            clock_out_badge_id = CASE WHEN clock_out IS NULL OR auto_close IS NOT NULL THEN NULL ELSE (
+                SELECT id FROM worker_badges WHERE worker_badges.worker_id = timesheets.worker_id
+            ) END
+        WHERE correction_reason IS NULL;
+
+        CREATE TABLE workers_new (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            active BOOLEAN DEFAULT 1
+        );
+        INSERT INTO workers_new (id, name, active) SELECT id, name, active FROM workers;
+        DROP TABLE workers;
+        ALTER TABLE workers_new RENAME TO workers;",
+    )
+}
+
+fn v8_schedules(tx: &Transaction) -> rusqlite::Result<()> {
+    // The default template reproduces the minutes that used to be hardcoded
+    // for everyone, so existing balances do not change.
+    tx.execute_batch(
+        "CREATE TABLE schedules (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            is_default BOOLEAN NOT NULL DEFAULT 0
+        );
+        CREATE TABLE schedule_days (
+            schedule_id INTEGER NOT NULL,
+            weekday INTEGER NOT NULL CHECK (weekday BETWEEN 0 AND 6),
+            start_time TEXT NOT NULL,
+            end_time TEXT NOT NULL,
+            break_minutes INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (schedule_id, weekday),
+            FOREIGN KEY (schedule_id) REFERENCES schedules(id)
+        );
+        CREATE TABLE schedule_assignments (
+            id INTEGER PRIMARY KEY,
+            worker_id INTEGER NOT NULL,
+            schedule_id INTEGER NOT NULL,
+            effective_from TEXT NOT NULL,
+            effective_to TEXT,
+            FOREIGN KEY (worker_id) REFERENCES workers(id),
+            FOREIGN KEY (schedule_id) REFERENCES schedules(id)
+        );
+        CREATE INDEX idx_schedule_assignments_worker ON schedule_assignments(worker_id);
+
+        INSERT INTO schedules (id, name, is_default) VALUES (1, 'Jornada general', 1);
+        INSERT INTO schedule_days (schedule_id, weekday, start_time, end_time, break_minutes) VALUES
+            (1, 0, '09:00', '17:30', 0),
+            (1, 1, '09:00', '17:30', 0),
+            (1, 2, '09:00', '16:30', 0),
+            (1, 3, '09:00', '16:30', 0),
+            (1, 4, '09:00', '16:30', 0),
+            (1, 5, '10:00', '12:30', 0);",
+    )
+}
+
+fn v9_holidays(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE holidays (
+            date TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            source TEXT NOT NULL DEFAULT 'manual'
+        );",
+    )?;
+    let mut insert =
+        tx.prepare("INSERT INTO holidays (date, name, source) VALUES (?, ?, 'chile')")?;
+    for (date, name) in crate::holidays::CHILE_HOLIDAYS {
+        insert.execute(rusqlite::params![date, name])?;
+    }
+    Ok(())
+}
+
+fn v10_absences(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE absences (
+            id INTEGER PRIMARY KEY,
+            worker_id INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            start_date TEXT NOT NULL,
+            end_date TEXT NOT NULL,
+            paid BOOLEAN NOT NULL,
+            note TEXT,
+            FOREIGN KEY (worker_id) REFERENCES workers(id)
+        );
+        CREATE INDEX idx_absences_worker ON absences(worker_id, start_date);",
+    )
+}
+
+fn v11_worker_employment(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE workers ADD COLUMN hire_date TEXT;
+        ALTER TABLE workers ADD COLUMN prior_service_years INTEGER NOT NULL DEFAULT 0;",
+    )
+}
+
+fn v12_breaks(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE breaks (
+            id INTEGER PRIMARY KEY,
+            timesheet_id INTEGER NOT NULL,
+            break_start TEXT NOT NULL,
+            break_end TEXT,
+            paid BOOLEAN NOT NULL DEFAULT 0,
+            start_badge_id INTEGER REFERENCES worker_badges(id),
+            end_badge_id INTEGER REFERENCES worker_badges(id),
+            FOREIGN KEY (timesheet_id) REFERENCES timesheets(id)
+        );
+        CREATE INDEX idx_breaks_timesheet ON breaks(timesheet_id, break_start);
+        CREATE UNIQUE INDEX idx_breaks_one_open_break
+            ON breaks(timesheet_id) WHERE break_end IS NULL;",
+    )
+}
+
+fn v13_hour_bank(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE hour_bank_closes (
+            worker_id INTEGER NOT NULL,
+            month TEXT NOT NULL,
+            opening_minutes INTEGER NOT NULL,
+            delta_minutes INTEGER NOT NULL,
+            adjustment_minutes INTEGER NOT NULL,
+            closing_minutes INTEGER NOT NULL,
+            closed_at TEXT NOT NULL,
+            PRIMARY KEY (worker_id, month),
+            FOREIGN KEY (worker_id) REFERENCES workers(id)
+        );
+        CREATE TABLE hour_bank_adjustments (
+            id INTEGER PRIMARY KEY,
+            worker_id INTEGER NOT NULL,
+            date TEXT NOT NULL,
+            minutes INTEGER NOT NULL,
+            reason TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (worker_id) REFERENCES workers(id)
+        );
+        CREATE INDEX idx_hour_bank_adjustments_worker
+            ON hour_bank_adjustments(worker_id, date);",
+    )
+}
+
+fn v14_worker_rut(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE workers ADD COLUMN rut TEXT;")
+}
+
+fn v15_export_profiles(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE export_profiles (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            columns TEXT NOT NULL,
+            separator TEXT NOT NULL,
+            hours_format TEXT NOT NULL,
+            encoding TEXT NOT NULL,
+            header BOOLEAN NOT NULL DEFAULT 1
+        );
+
+        INSERT INTO export_profiles (name, columns, separator, hours_format, encoding, header)
+        VALUES (
+            'Genérico',
+            'rut=RUT; nombre=Nombre; periodo=Periodo; horas_ordinarias=Horas ordinarias; horas_extras=Horas extras; dias_ausencia=Días de ausencia',
+            'semicolon',
+            'decimal_comma',
+            'utf8',
+            1
+        );",
+    )
+}
+
+fn v16_schedule_versions(tx: &Transaction) -> rusqlite::Result<()> {
+    // Existing days become each template's first version, which has no
+    // start date and so keeps covering every past day.
+    tx.execute_batch(
+        "CREATE TABLE schedule_versions (
+            id INTEGER PRIMARY KEY,
+            schedule_id INTEGER NOT NULL,
+            valid_from TEXT,
+            FOREIGN KEY (schedule_id) REFERENCES schedules(id),
+            UNIQUE (schedule_id, valid_from)
+        );
+        INSERT INTO schedule_versions (schedule_id, valid_from) SELECT id, NULL FROM schedules;
+
+        CREATE TABLE schedule_version_days (
+            version_id INTEGER NOT NULL,
+            weekday INTEGER NOT NULL CHECK (weekday BETWEEN 0 AND 6),
+            start_time TEXT NOT NULL,
+            end_time TEXT NOT NULL,
+            break_minutes INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (version_id, weekday),
+            FOREIGN KEY (version_id) REFERENCES schedule_versions(id)
+        );
+        INSERT INTO schedule_version_days (version_id, weekday, start_time, end_time, break_minutes)
+        SELECT v.id, d.weekday, d.start_time, d.end_time, d.break_minutes
+        FROM schedule_days d JOIN schedule_versions v ON v.schedule_id = d.schedule_id;
+        DROP TABLE schedule_days;
+        ALTER TABLE schedule_version_days RENAME TO schedule_days;",
+    )
+}
+
+fn v17_breaks_quarantine(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE breaks_quarantine (
+            id INTEGER PRIMARY KEY,
+            original_id INTEGER NOT NULL,
+            timesheet_id INTEGER,
+            break_start TEXT,
+            break_end TEXT,
+            paid BOOLEAN,
+            reason TEXT NOT NULL,
+            quarantined_at TEXT NOT NULL
+        );",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A database as the first release left it, before versioning: one
+    /// worker with a finished session and two sessions left open.
+    fn legacy_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE workers (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                barcode TEXT NOT NULL UNIQUE,
+                active BOOLEAN DEFAULT 1
+            );
+            CREATE TABLE timesheets (
+                id INTEGER PRIMARY KEY,
+                worker_id INTEGER NOT NULL,
+                clock_in TEXT NOT NULL,
+                clock_out TEXT,
+                FOREIGN KEY (worker_id) REFERENCES workers(id)
+            );
+            INSERT INTO workers (id, name, barcode) VALUES (1, 'Ana', '1000');
+            INSERT INTO timesheets (id, worker_id, clock_in, clock_out) VALUES
+                (1, 1, '2024-03-04T12:00:00+00:00', '2024-03-04T20:00:00+00:00'),
+                (2, 1, '2024-03-05T12:00:00+00:00', NULL),
+                (3, 1, '2024-03-06T12:00:00+00:00', NULL);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn backup_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "timesheet-migrations-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn upgrades_a_legacy_database() {
+        let mut conn = legacy_db();
+        let dir = backup_dir("upgrade");
+        assert_eq!(migrate(&mut conn, &dir).unwrap(), latest_version());
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+
+        let worker = crate::db::get_worker(&conn, 1).unwrap().unwrap();
+        assert_eq!(worker.barcode.as_deref(), Some("1000"));
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM timesheets"), 2);
+        // The older open session is kept in quarantine, not zeroed.
+        let (original_id, reason): (i64, String) = conn
+            .query_row(
+                "SELECT original_id, reason FROM timesheets_quarantine",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(original_id, 2);
+        assert!(reason.contains("sesión abierta duplicada"));
+        let open = crate::db::get_current_status(&conn, 1).unwrap().unwrap();
+        assert_eq!(open.id, 3);
+
+        // Running again is a no-op.
+        assert_eq!(migrate(&mut conn, &dir).unwrap(), latest_version());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn backs_up_before_the_first_step() {
+        let mut conn = legacy_db();
+        let dir = backup_dir("backup");
+        migrate(&mut conn, &dir).unwrap();
+
+        let backups: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(backups.len(), 1);
+        let name = backups[0]
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        assert!(name.starts_with("timesheet-v0-") && name.ends_with(".db"));
+        let backup = Connection::open(&backups[0]).unwrap();
+        assert_eq!(current_version(&backup).unwrap(), 0);
+        assert_eq!(
+            count(
+                &backup,
+                "SELECT COUNT(*) FROM timesheets WHERE clock_out IS NULL"
+            ),
+            2
+        );
+        drop(backup);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn empty_databases_are_not_backed_up() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let dir = backup_dir("empty");
+        migrate(&mut conn, &dir).unwrap();
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn refuses_a_newer_schema() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", latest_version() + 1)
+            .unwrap();
+        let dir = backup_dir("newer");
+        match migrate(&mut conn, &dir) {
+            Err(MigrationError::NewerSchema { found, supported }) => {
+                assert_eq!((found, supported), (latest_version() + 1, latest_version()));
+            }
+            other => panic!("expected NewerSchema, got {:?}", other),
+        }
+        assert_eq!(current_version(&conn).unwrap(), latest_version() + 1);
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration, NaiveDate};
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::audit::AuditContext;
+use crate::db::{DbError, Result};
+use crate::timekeeping::Timekeeper;
+
+const WEEKLY_LIMITS_KEY: &str = "overtime.weekly_limits";
+const DAILY_CAP_KEY: &str = "overtime.daily_cap";
+
+/// Ordinary weekly hours under the Ley 40 horas timeline. Weeks before the
+/// first date use the first limit.
+const DEFAULT_WEEKLY_LIMITS: &str = "2005-01-01=45; 2024-04-26=44; 2026-04-26=42; 2028-04-26=40";
+
+/// Longest ordinary working day; anything beyond it is overtime.
+const DEFAULT_DAILY_CAP: &str = "10:00";
+
+/// Ordinary weekly minutes in force from `effective_from`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct WeeklyLimit {
+    pub effective_from: NaiveDate,
+    pub minutes: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct OvertimeRules {
+    /// Sorted by `effective_from`; never empty.
+    pub weekly_limits: Vec<WeeklyLimit>,
+    pub daily_cap_minutes: i64,
+}
+
+impl OvertimeRules {
+    /// The weekly limit for the week starting on `monday`.
+    pub fn weekly_limit_on(&self, monday: NaiveDate) -> i64 {
+        self.weekly_limits
+            .iter()
+            .rev()
+            .find(|limit| limit.effective_from <= monday)
+            .or(self.weekly_limits.first())
+            .map(|limit| limit.minutes)
+            .unwrap_or(i64::MAX)
+    }
+}
+
+impl Default for OvertimeRules {
+    fn default() -> Self {
+        OvertimeRules {
+            weekly_limits: parse_weekly_limits(DEFAULT_WEEKLY_LIMITS)
+                .expect("valid default weekly limits"),
+            daily_cap_minutes: parse_hours(DEFAULT_DAILY_CAP).expect("valid default daily cap"),
+        }
+    }
+}
+
+/// Parses "YYYY-MM-DD=hours" entries separated by `;`, `,` or new lines.
+/// Hours may be whole ("44"), decimal ("42.5") or "HH:MM".
+pub fn parse_weekly_limits(value: &str) -> Option<Vec<WeeklyLimit>> {
+    let mut limits = Vec::new();
+    for part in value.split([';', ',', '\n']) {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let (date, hours) = part.split_once('=')?;
+        limits.push(WeeklyLimit {
+            effective_from: NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok()?,
+            minutes: parse_hours(hours)?,
+        });
+    }
+    limits.sort_by_key(|limit| limit.effective_from);
+    (!limits.is_empty()).then_some(limits)
+}
+
+pub fn format_weekly_limits(limits: &[WeeklyLimit]) -> String {
+    limits
+        .iter()
+        .map(|limit| format!("{}={}", limit.effective_from, format_hours(limit.minutes)))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Parses "44", "42.5" or "10:00" into minutes.
+pub fn parse_hours(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if let Some((hours, minutes)) = value.split_once(':') {
+        let hours: i64 = hours.trim().parse().ok()?;
+        let minutes: i64 = minutes.trim().parse().ok()?;
+        return (hours >= 0 && (0..60).contains(&minutes)).then_some(hours * 60 + minutes);
+    }
+    let hours: f64 = value.replace(',', ".").parse().ok()?;
+    (hours >= 0.0).then(|| (hours * 60.0).round() as i64)
+}
+
+/// Whole hours as "44", anything else as "HH:MM".
+pub fn format_hours(minutes: i64) -> String {
+    if minutes % 60 == 0 {
+        (minutes / 60).to_string()
+    } else {
+        format!("{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+}
+
+pub fn load_rules(conn: &Connection) -> Result<OvertimeRules> {
+    let defaults = OvertimeRules::default();
+    let weekly_limits = crate::settings::get(conn, WEEKLY_LIMITS_KEY)?
+        .and_then(|value| parse_weekly_limits(&value))
+        .unwrap_or(defaults.weekly_limits);
+    let daily_cap_minutes = crate::settings::get(conn, DAILY_CAP_KEY)?
+        .and_then(|value| parse_hours(&value))
+        .unwrap_or(defaults.daily_cap_minutes);
+    Ok(OvertimeRules {
+        weekly_limits,
+        daily_cap_minutes,
+    })
+}
+
+pub fn save_rules(conn: &mut Connection, rules: &OvertimeRules, ctx: &AuditContext) -> Result<()> {
+    if rules.weekly_limits.is_empty() {
+        return Err(DbError::Validation(
+            "se necesita al menos un límite semanal".to_string(),
+        ));
+    }
+    if rules.daily_cap_minutes <= 0 {
+        return Err(DbError::Validation(
+            "el tope diario debe ser mayor que cero".to_string(),
+        ));
+    }
+    crate::settings::set_many(
+        conn,
+        &[
+            (
+                WEEKLY_LIMITS_KEY,
+                &format_weekly_limits(&rules.weekly_limits),
+            ),
+            (
+                DAILY_CAP_KEY,
+                &format!(
+                    "{:02}:{:02}",
+                    rules.daily_cap_minutes / 60,
+                    rules.daily_cap_minutes % 60
+                ),
+            ),
+        ],
+        ctx,
+    )
+}
+
+/// How the minutes worked on one day split into ordinary time and overtime.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DayOvertime {
+    pub worked: i64,
+    pub ordinary: i64,
+    pub overtime: i64,
+}
+
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(i64::from(date.weekday().num_days_from_monday()))
+}
+
+/// Splits each day's worked minutes into ordinary time and overtime.
+///
+/// Minutes beyond the daily cap are overtime. The rest count towards the
+/// weekly limit (Monday to Sunday) in date order, and whatever exceeds it
+/// is overtime on the day the limit was crossed and the days after. Pass
+/// whole weeks to get exact results at the edges of a month.
+pub fn classify(
+    worked_by_day: &BTreeMap<NaiveDate, i64>,
+    rules: &OvertimeRules,
+) -> BTreeMap<NaiveDate, DayOvertime> {
+    let mut result = BTreeMap::new();
+    let mut current_week = None;
+    let mut week_ordinary = 0;
+    for (&date, &worked) in worked_by_day {
+        let monday = week_start(date);
+        if current_week != Some(monday) {
+            current_week = Some(monday);
+            week_ordinary = 0;
+        }
+        let limit = rules.weekly_limit_on(monday);
+        let within_cap = worked.min(rules.daily_cap_minutes);
+        let room = (limit - week_ordinary).max(0);
+        let ordinary = within_cap.min(room);
+        week_ordinary += ordinary;
+        result.insert(
+            date,
+            DayOvertime {
+                worked,
+                ordinary,
+                overtime: worked - ordinary,
+            },
+        );
+    }
+    result
+}
+
+/// Classifies a worker's days from `from` through `to`, reading the whole
+/// weeks around them so weekly limits are applied correctly.
+pub fn worker_overtime(
+    conn: &Connection,
+    worker_id: i64,
+    from: NaiveDate,
+    to: NaiveDate,
+    rules: &OvertimeRules,
+) -> Result<BTreeMap<NaiveDate, DayOvertime>> {
+    let first = week_start(from);
+    let entries = crate::db::get_timesheet_entries_overlapping(conn, worker_id, first, to)?;
+    let timekeeper = Timekeeper::load(conn, worker_id, &entries)?;
+    let mut worked = timekeeper.minutes_by_day(&entries);
+    worked.retain(|date, _| *date >= first);
+    let mut classified = classify(&worked, rules);
+    classified.retain(|date, _| *date >= from && *date <= to);
+    Ok(classified)
+}
+
+/// Overtime in one week of a worker's month, for the summary report.
+#[derive(Clone, Debug, Serialize)]
+pub struct WeekOvertime {
+    pub week_start: NaiveDate,
+    pub limit_minutes: i64,
+    pub ordinary: i64,
+    pub overtime: i64,
+}
+
+/// Groups classified days by week.
+pub fn weekly_totals(
+    days: &BTreeMap<NaiveDate, DayOvertime>,
+    rules: &OvertimeRules,
+) -> Vec<WeekOvertime> {
+    let mut weeks: BTreeMap<NaiveDate, WeekOvertime> = BTreeMap::new();
+    for (date, day) in days {
+        let monday = week_start(*date);
+        let week = weeks.entry(monday).or_insert_with(|| WeekOvertime {
+            week_start: monday,
+            limit_minutes: rules.weekly_limit_on(monday),
+            ordinary: 0,
+            overtime: 0,
+        });
+        week.ordinary += day.ordinary;
+        week.overtime += day.overtime;
+    }
+    weeks.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    /// `minutes` worked on each of the days starting at `monday`.
+    fn week(monday: NaiveDate, minutes: &[i64]) -> BTreeMap<NaiveDate, i64> {
+        minutes
+            .iter()
+            .enumerate()
+            .map(|(i, &m)| (monday + Duration::days(i as i64), m))
+            .collect()
+    }
+
+    fn totals(days: &BTreeMap<NaiveDate, DayOvertime>) -> (i64, i64) {
+        days.values()
+            .fold((0, 0), |(o, x), d| (o + d.ordinary, x + d.overtime))
+    }
+
+    #[test]
+    fn weekly_limit_changes_with_the_week_that_starts_after_each_step() {
+        let rules = OvertimeRules::default();
+        // 2024-04-26 is a Friday: its week still has 45 hours.
+        assert_eq!(rules.weekly_limit_on(date(2024, 4, 22)), 45 * 60);
+        assert_eq!(rules.weekly_limit_on(date(2024, 4, 29)), 44 * 60);
+        // 2026-04-26 is a Sunday.
+        assert_eq!(rules.weekly_limit_on(date(2026, 4, 20)), 44 * 60);
+        assert_eq!(rules.weekly_limit_on(date(2026, 4, 27)), 42 * 60);
+        assert_eq!(rules.weekly_limit_on(date(2028, 5, 1)), 40 * 60);
+        // Before the first date the first limit applies.
+        assert_eq!(rules.weekly_limit_on(date(2000, 1, 3)), 45 * 60);
+    }
+
+    #[test]
+    fn nine_hour_weeks_around_2024_04_26() {
+        let rules = OvertimeRules::default();
+        let before = classify(&week(date(2024, 4, 22), &[540; 5]), &rules);
+        assert_eq!(totals(&before), (45 * 60, 0));
+
+        let after = classify(&week(date(2024, 4, 29), &[540; 5]), &rules);
+        assert_eq!(totals(&after), (44 * 60, 60));
+        // The hour over the limit falls on the Friday that crossed it.
+        assert_eq!(after[&date(2024, 5, 3)].overtime, 60);
+        assert_eq!(after[&date(2024, 5, 2)].overtime, 0);
+    }
+
+    #[test]
+    fn nine_hour_weeks_around_2026_04_26() {
+        let rules = OvertimeRules::default();
+        let before = classify(&week(date(2026, 4, 20), &[540; 5]), &rules);
+        assert_eq!(totals(&before), (44 * 60, 60));
+
+        let after = classify(&week(date(2026, 4, 27), &[540; 5]), &rules);
+        assert_eq!(totals(&after), (42 * 60, 180));
+        assert_eq!(
+            after[&date(2026, 5, 1)],
+            DayOvertime {
+                worked: 540,
+                ordinary: 360,
+                overtime: 180,
+            }
+        );
+    }
+
+    #[test]
+    fn minutes_beyond_ten_hours_a_day_are_overtime() {
+        let rules = OvertimeRules::default();
+        let days = classify(&week(date(2026, 6, 1), &[690, 480]), &rules);
+        assert_eq!(
+            days[&date(2026, 6, 1)],
+            DayOvertime {
+                worked: 690,
+                ordinary: 600,
+                overtime: 90,
+            }
+        );
+        assert_eq!(days[&date(2026, 6, 2)].overtime, 0);
+    }
+
+    #[test]
+    fn daily_overtime_does_not_use_up_the_weekly_limit() {
+        let rules = OvertimeRules::default();
+        // Four 11-hour days and a 4-hour Friday in a 42-hour week: four
+        // hours over the daily cap, and 40 + 4 = 44 ordinary hours of which
+        // two exceed the week.
+        let days = classify(&week(date(2026, 6, 1), &[660, 660, 660, 660, 240]), &rules);
+        assert_eq!(totals(&days), (42 * 60, 4 * 60 + 2 * 60));
+        assert_eq!(days[&date(2026, 6, 5)].ordinary, 120);
+    }
+
+    #[test]
+    fn weekly_limits_parse_and_sort() {
+        let limits = parse_weekly_limits("2026-04-26=42; 2024-04-26=44,2005-01-01=45:30").unwrap();
+        assert_eq!(
+            format_weekly_limits(&limits),
+            "2005-01-01=45:30; 2024-04-26=44; 2026-04-26=42"
+        );
+        assert_eq!(parse_weekly_limits("2026-04-26"), None);
+        assert_eq!(parse_weekly_limits(""), None);
+        assert_eq!(parse_hours("42,5"), Some(2550));
+        assert_eq!(parse_hours("10:75"), None);
+    }
+}
@@ -0,0 +1,566 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::audit::{self, AuditContext};
+use crate::db::{DbError, Result};
+
+/// A value a payroll import file can carry for each worker, computed over
+/// the report period.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum PayrollField {
+    Rut,
+    Name,
+    /// The period as shown in reports, e.g. "2025-10".
+    Period,
+    PeriodStart,
+    PeriodEnd,
+    /// Days with any time worked.
+    DaysWorked,
+    TotalHours,
+    OrdinaryHours,
+    OvertimeHours,
+    /// Hours worked on public holidays, also part of the total.
+    HolidayHours,
+    /// Working days on leave of any kind, paid or not; weekends and
+    /// holidays inside a leave are not counted.
+    AbsenceDays,
+    /// Hours credited by paid leave.
+    AbsenceHours,
+    LateMinutes,
+    /// Banco de horas closing balance; blank unless the period is a month.
+    HourBank,
+}
+
+impl PayrollField {
+    pub const ALL: [PayrollField; 14] = [
+        PayrollField::Rut,
+        PayrollField::Name,
+        PayrollField::Period,
+        PayrollField::PeriodStart,
+        PayrollField::PeriodEnd,
+        PayrollField::DaysWorked,
+        PayrollField::TotalHours,
+        PayrollField::OrdinaryHours,
+        PayrollField::OvertimeHours,
+        PayrollField::HolidayHours,
+        PayrollField::AbsenceDays,
+        PayrollField::AbsenceHours,
+        PayrollField::LateMinutes,
+        PayrollField::HourBank,
+    ];
+
+    /// Name used in the column list typed in Settings.
+    pub fn code(self) -> &'static str {
+        match self {
+            PayrollField::Rut => "rut",
+            PayrollField::Name => "nombre",
+            PayrollField::Period => "periodo",
+            PayrollField::PeriodStart => "desde",
+            PayrollField::PeriodEnd => "hasta",
+            PayrollField::DaysWorked => "dias_trabajados",
+            PayrollField::TotalHours => "horas_totales",
+            PayrollField::OrdinaryHours => "horas_ordinarias",
+            PayrollField::OvertimeHours => "horas_extras",
+            PayrollField::HolidayHours => "horas_feriados",
+            PayrollField::AbsenceDays => "dias_ausencia",
+            PayrollField::AbsenceHours => "horas_ausencia",
+            PayrollField::LateMinutes => "minutos_atraso",
+            PayrollField::HourBank => "saldo_banco",
+        }
+    }
+
+    fn from_code(code: &str) -> Option<PayrollField> {
+        PayrollField::ALL.into_iter().find(|f| f.code() == code)
+    }
+}
+
+/// One column of the file: what goes in it and its header.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PayrollColumn {
+    pub field: PayrollField,
+    pub header: String,
+}
+
+/// Parses "rut=RUT; horas_extras=HH.EE." in file order. A column without
+/// "=header" uses its code as the header.
+pub fn parse_columns(value: &str) -> Result<Vec<PayrollColumn>> {
+    let mut columns = Vec::new();
+    for part in value.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+        let (code, header) = match part.split_once('=') {
+            Some((code, header)) => (code.trim(), header.trim()),
+            None => (part, part),
+        };
+        let field = PayrollField::from_code(&code.to_lowercase()).ok_or_else(|| {
+            DbError::Validation(format!(
+                "columna desconocida: {} (use {})",
+                code,
+                PayrollField::ALL.map(PayrollField::code).join(", ")
+            ))
+        })?;
+        columns.push(PayrollColumn {
+            field,
+            header: header.to_string(),
+        });
+    }
+    if columns.is_empty() {
+        return Err(DbError::Validation(
+            "el perfil necesita al menos una columna".to_string(),
+        ));
+    }
+    Ok(columns)
+}
+
+pub fn format_columns(columns: &[PayrollColumn]) -> String {
+    columns
+        .iter()
+        .map(|c| format!("{}={}", c.field.code(), c.header))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub enum Separator {
+    Comma,
+    #[default]
+    Semicolon,
+    Tab,
+    Pipe,
+}
+
+impl Separator {
+    pub const ALL: [Separator; 4] = [
+        Separator::Comma,
+        Separator::Semicolon,
+        Separator::Tab,
+        Separator::Pipe,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Separator::Comma => "Coma (,)",
+            Separator::Semicolon => "Punto y coma (;)",
+            Separator::Tab => "Tabulación",
+            Separator::Pipe => "Barra (|)",
+        }
+    }
+
+    pub fn as_char(self) -> char {
+        match self {
+            Separator::Comma => ',',
+            Separator::Semicolon => ';',
+            Separator::Tab => '\t',
+            Separator::Pipe => '|',
+        }
+    }
+
+    fn code(self) -> &'static str {
+        match self {
+            Separator::Comma => "comma",
+            Separator::Semicolon => "semicolon",
+            Separator::Tab => "tab",
+            Separator::Pipe => "pipe",
+        }
+    }
+
+    fn from_code(code: &str) -> Option<Separator> {
+        Separator::ALL.into_iter().find(|s| s.code() == code)
+    }
+
+    pub fn index(self) -> usize {
+        Separator::ALL.iter().position(|s| *s == self).unwrap_or(0)
+    }
+}
+
+/// How hour amounts are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub enum HoursFormat {
+    /// "160.50"
+    DecimalPoint,
+    /// "160,50"
+    #[default]
+    DecimalComma,
+    /// "160:30"
+    HoursMinutes,
+}
+
+impl HoursFormat {
+    pub const ALL: [HoursFormat; 3] = [
+        HoursFormat::DecimalPoint,
+        HoursFormat::DecimalComma,
+        HoursFormat::HoursMinutes,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HoursFormat::DecimalPoint => "Horas decimales (160.50)",
+            HoursFormat::DecimalComma => "Horas decimales (160,50)",
+            HoursFormat::HoursMinutes => "HH:MM (160:30)",
+        }
+    }
+
+    fn code(self) -> &'static str {
+        match self {
+            HoursFormat::DecimalPoint => "decimal_point",
+            HoursFormat::DecimalComma => "decimal_comma",
+            HoursFormat::HoursMinutes => "hh_mm",
+        }
+    }
+
+    fn from_code(code: &str) -> Option<HoursFormat> {
+        HoursFormat::ALL.into_iter().find(|f| f.code() == code)
+    }
+
+    pub fn index(self) -> usize {
+        HoursFormat::ALL
+            .iter()
+            .position(|f| *f == self)
+            .unwrap_or(0)
+    }
+
+    /// `minutes` as hours in this format; negative balances keep a "-".
+    pub fn format(self, minutes: i64) -> String {
+        match self {
+            HoursFormat::DecimalPoint => format!("{:.2}", minutes as f64 / 60.0),
+            HoursFormat::DecimalComma => format!("{:.2}", minutes as f64 / 60.0).replace('.', ","),
+            HoursFormat::HoursMinutes => format!(
+                "{}{:02}:{:02}",
+                if minutes < 0 { "-" } else { "" },
+                minutes.abs() / 60,
+                minutes.abs() % 60
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// ISO-8859-1, still expected by older payroll software. Text with
+    /// characters outside it is refused rather than written as "?".
+    Latin1,
+}
+
+impl Encoding {
+    pub const ALL: [Encoding; 2] = [Encoding::Utf8, Encoding::Latin1];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Latin1 => "Latin-1 (ISO-8859-1)",
+        }
+    }
+
+    fn code(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf8",
+            Encoding::Latin1 => "latin1",
+        }
+    }
+
+    fn from_code(code: &str) -> Option<Encoding> {
+        Encoding::ALL.into_iter().find(|e| e.code() == code)
+    }
+
+    pub fn index(self) -> usize {
+        Encoding::ALL.iter().position(|e| *e == self).unwrap_or(0)
+    }
+
+    /// `text` as bytes in this encoding. Fails on the first line with a
+    /// character the encoding cannot hold, so a name is never corrupted.
+    pub fn encode(self, text: &str) -> Result<Vec<u8>> {
+        match self {
+            Encoding::Utf8 => Ok(text.as_bytes().to_vec()),
+            Encoding::Latin1 => {
+                let mut bytes = Vec::with_capacity(text.len());
+                for line in text.split_inclusive('\n') {
+                    for c in line.chars() {
+                        let byte = u8::try_from(u32::from(c)).map_err(|_| {
+                            DbError::Validation(format!(
+                                "'{}' no existe en Latin-1 (en \"{}\"); corrija el texto o use UTF-8",
+                                c,
+                                line.trim_end()
+                            ))
+                        })?;
+                        bytes.push(byte);
+                    }
+                }
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+/// Everything about the file's layout except its columns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct ExportFormat {
+    pub separator: Separator,
+    pub hours: HoursFormat,
+    pub encoding: Encoding,
+    /// Whether the first line holds the column headers.
+    pub header: bool,
+}
+
+impl Default for ExportFormat {
+    fn default() -> Self {
+        ExportFormat {
+            separator: Separator::default(),
+            hours: HoursFormat::default(),
+            encoding: Encoding::default(),
+            header: true,
+        }
+    }
+}
+
+impl ExportFormat {
+    /// One line of the file, ending in CRLF. Values containing the
+    /// separator, a quote or a line break are quoted.
+    pub fn record<I: IntoIterator<Item = String>>(&self, values: I) -> String {
+        let separator = self.separator.as_char();
+        let mut line = values
+            .into_iter()
+            .map(|value| {
+                if value.contains([separator, '"', '\n', '\r']) {
+                    format!("\"{}\"", value.replace('"', "\"\""))
+                } else {
+                    value
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(&separator.to_string());
+        line.push_str("\r\n");
+        line
+    }
+}
+
+/// A named layout for a payroll provider's import file.
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportProfile {
+    pub id: i64,
+    pub name: String,
+    pub columns: Vec<PayrollColumn>,
+    pub format: ExportFormat,
+}
+
+const PROFILE_COLUMNS: &str = "id, name, columns, separator, hours_format, encoding, header";
+
+fn profile_from_row(row: &rusqlite::Row) -> rusqlite::Result<Option<ExportProfile>> {
+    let columns: String = row.get(2)?;
+    let separator: String = row.get(3)?;
+    let hours: String = row.get(4)?;
+    let encoding: String = row.get(5)?;
+    let (Ok(columns), Some(separator), Some(hours), Some(encoding)) = (
+        parse_columns(&columns),
+        Separator::from_code(&separator),
+        HoursFormat::from_code(&hours),
+        Encoding::from_code(&encoding),
+    ) else {
+        return Ok(None);
+    };
+    Ok(Some(ExportProfile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        columns,
+        format: ExportFormat {
+            separator,
+            hours,
+            encoding,
+            header: row.get(6)?,
+        },
+    }))
+}
+
+pub fn get_profile(conn: &Connection, id: i64) -> Result<Option<ExportProfile>> {
+    Ok(conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM export_profiles WHERE id = ?",
+                PROFILE_COLUMNS
+            ),
+            rusqlite::params![id],
+            profile_from_row,
+        )
+        .optional()?
+        .flatten())
+}
+
+/// All profiles, ordered by name.
+pub fn list_profiles(conn: &Connection) -> Result<Vec<ExportProfile>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM export_profiles ORDER BY name",
+        PROFILE_COLUMNS
+    ))?;
+    let rows = stmt.query_map([], profile_from_row)?;
+    let mut profiles = Vec::new();
+    for row in rows {
+        if let Some(profile) = row? {
+            profiles.push(profile);
+        }
+    }
+    Ok(profiles)
+}
+
+/// Creates a profile (`id = None`) or replaces an existing one. Returns
+/// the profile id.
+pub fn save_profile(
+    conn: &mut Connection,
+    id: Option<i64>,
+    name: &str,
+    columns: &[PayrollColumn],
+    format: ExportFormat,
+    ctx: &AuditContext,
+) -> Result<i64> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(DbError::Validation(
+            "el perfil necesita un nombre".to_string(),
+        ));
+    }
+    if columns.is_empty() {
+        return Err(DbError::Validation(
+            "el perfil necesita al menos una columna".to_string(),
+        ));
+    }
+    if format.separator == Separator::Comma && format.hours == HoursFormat::DecimalComma {
+        return Err(DbError::Validation(
+            "con horas decimales con coma use otro separador".to_string(),
+        ));
+    }
+    let tx = conn.transaction()?;
+    let taken: Option<i64> = tx
+        .query_row(
+            "SELECT id FROM export_profiles WHERE name = ?",
+            rusqlite::params![name],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if taken.is_some_and(|taken| Some(taken) != id) {
+        return Err(DbError::Validation(format!(
+            "ya existe un perfil llamado {}",
+            name
+        )));
+    }
+    let before = match id {
+        Some(id) => Some(
+            get_profile(&tx, id)?
+                .ok_or_else(|| DbError::Validation(format!("no existe el perfil {}", id)))?,
+        ),
+        None => None,
+    };
+    let params = rusqlite::params![
+        name,
+        format_columns(columns),
+        format.separator.code(),
+        format.hours.code(),
+        format.encoding.code(),
+        format.header,
+        id
+    ];
+    let id = match id {
+        Some(id) => {
+            tx.execute(
+                "UPDATE export_profiles SET name = ?1, columns = ?2, separator = ?3,
+                 hours_format = ?4, encoding = ?5, header = ?6 WHERE id = ?7",
+                params,
+            )?;
+            id
+        }
+        None => {
+            tx.execute(
+                "INSERT INTO export_profiles (name, columns, separator, hours_format, encoding, header)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                &params[..6],
+            )?;
+            tx.last_insert_rowid()
+        }
+    };
+    let after = get_profile(&tx, id)?;
+    audit::record(
+        &tx,
+        ctx,
+        "export_profiles",
+        Some(id),
+        if before.is_some() { "update" } else { "create" },
+        before.as_ref().and_then(audit::snapshot),
+        after.as_ref().and_then(audit::snapshot),
+    )?;
+    tx.commit()?;
+    Ok(id)
+}
+
+pub fn delete_profile(conn: &mut Connection, id: i64, ctx: &AuditContext) -> Result<()> {
+    let tx = conn.transaction()?;
+    let before = get_profile(&tx, id)?
+        .ok_or_else(|| DbError::Validation(format!("no existe el perfil {}", id)))?;
+    tx.execute(
+        "DELETE FROM export_profiles WHERE id = ?",
+        rusqlite::params![id],
+    )?;
+    audit::record(
+        &tx,
+        ctx,
+        "export_profiles",
+        Some(id),
+        "delete",
+        audit::snapshot(&before),
+        None,
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_columns_with_and_without_headers() {
+        let columns = parse_columns(" RUT=Rut trabajador; horas_extras = HH.EE. ;nombre;").unwrap();
+        assert_eq!(
+            columns,
+            vec![
+                PayrollColumn {
+                    field: PayrollField::Rut,
+                    header: "Rut trabajador".to_string(),
+                },
+                PayrollColumn {
+                    field: PayrollField::OvertimeHours,
+                    header: "HH.EE.".to_string(),
+                },
+                PayrollColumn {
+                    field: PayrollField::Name,
+                    header: "nombre".to_string(),
+                },
+            ]
+        );
+        assert_eq!(parse_columns(&format_columns(&columns)).unwrap(), columns);
+    }
+
+    #[test]
+    fn rejects_unknown_and_empty_column_lists() {
+        assert!(matches!(
+            parse_columns("rut; sueldo=Sueldo"),
+            Err(DbError::Validation(message)) if message.contains("sueldo")
+        ));
+        assert!(matches!(parse_columns(" ; "), Err(DbError::Validation(_))));
+    }
+
+    #[test]
+    fn latin1_encodes_spanish_text() {
+        assert_eq!(
+            Encoding::Latin1.encode("Núñez;Peña\r\n").unwrap(),
+            b"N\xfa\xf1ez;Pe\xf1a\r\n".to_vec()
+        );
+        assert_eq!(Encoding::Utf8.encode("Núñez").unwrap(), "Núñez".as_bytes());
+    }
+
+    #[test]
+    fn latin1_refuses_characters_it_cannot_hold() {
+        let result = Encoding::Latin1.encode("rut;nombre\r\n1-9;Zoë Łukasz\r\n");
+        assert!(matches!(
+            result,
+            Err(DbError::Validation(message))
+                if message.contains('Ł') && message.contains("Zoë Łukasz")
+        ));
+    }
+}
@@ -0,0 +1,441 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// US Letter ("tamaño carta"), the paper loaded in the office printers.
+pub const LETTER: (f32, f32) = (612.0, 792.0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Color(pub f32, pub f32, pub f32);
+
+impl Color {
+    pub const BLACK: Color = Color(0.0, 0.0, 0.0);
+    pub const GREY: Color = Color(0.4, 0.4, 0.4);
+    pub const LIGHT_GREY: Color = Color(0.93, 0.93, 0.93);
+    pub const RED: Color = Color(0.89, 0.24, 0.24);
+    pub const BLUE: Color = Color(0.16, 0.43, 0.73);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextStyle {
+    pub font: Font,
+    pub size: f32,
+    pub align: Align,
+    pub color: Color,
+}
+
+impl TextStyle {
+    pub const fn new(font: Font, size: f32) -> TextStyle {
+        TextStyle {
+            font,
+            size,
+            align: Align::Left,
+            color: Color::BLACK,
+        }
+    }
+
+    pub const fn align(self, align: Align) -> TextStyle {
+        TextStyle { align, ..self }
+    }
+
+    pub const fn color(self, color: Color) -> TextStyle {
+        TextStyle { color, ..self }
+    }
+}
+
+/// One page's drawing operations.
+pub struct Page {
+    height: f32,
+    content: String,
+}
+
+impl Page {
+    /// Draws `text` on the baseline `y`. With [`Align::Center`] or
+    /// [`Align::Right`], `x` is the center or the right edge.
+    pub fn text(&mut self, x: f32, y: f32, text: &str, style: TextStyle) {
+        if text.is_empty() {
+            return;
+        }
+        let x = match style.align {
+            Align::Left => x,
+            Align::Center => x - text_width(text, style.font, style.size) / 2.0,
+            Align::Right => x - text_width(text, style.font, style.size),
+        };
+        let Color(r, g, b) = style.color;
+        writeln!(
+            self.content,
+            "BT {:.3} {:.3} {:.3} rg /{} {:.2} Tf {:.2} {:.2} Td ({}) Tj ET",
+            r,
+            g,
+            b,
+            style.font.resource(),
+            style.size,
+            x,
+            self.height - y,
+            encode_text(text)
+        )
+        .expect("write to string");
+    }
+
+    pub fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, width: f32) {
+        writeln!(
+            self.content,
+            "0 0 0 RG {:.2} w {:.2} {:.2} m {:.2} {:.2} l S",
+            width,
+            x1,
+            self.height - y1,
+            x2,
+            self.height - y2
+        )
+        .expect("write to string");
+    }
+
+    /// Fills the rectangle whose top-left corner is (`x`, `y`).
+    pub fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        let Color(r, g, b) = color;
+        writeln!(
+            self.content,
+            "{:.3} {:.3} {:.3} rg {:.2} {:.2} {:.2} {:.2} re f",
+            r,
+            g,
+            b,
+            x,
+            self.height - y - height,
+            width,
+            height
+        )
+        .expect("write to string");
+    }
+}
+
+/// A PDF for printed reports. It only draws text in the built-in Helvetica
+/// fonts, lines and filled rectangles, so it needs no fonts or programs on
+/// the machine. Coordinates are in points (1/72 inch) from the top-left
+/// corner of the page.
+pub struct PdfDocument {
+    title: String,
+    width: f32,
+    height: f32,
+    pages: Vec<Page>,
+}
+
+impl PdfDocument {
+    pub fn new(title: &str, (width, height): (f32, f32)) -> PdfDocument {
+        PdfDocument {
+            title: title.to_string(),
+            width,
+            height,
+            pages: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    /// Starts a new page and returns it.
+    pub fn add_page(&mut self) -> &mut Page {
+        self.pages.push(Page {
+            height: self.height,
+            content: String::new(),
+        });
+        self.pages.last_mut().expect("page was just added")
+    }
+
+    /// The page being drawn, starting one if there is none yet.
+    pub fn page(&mut self) -> &mut Page {
+        if self.pages.is_empty() {
+            return self.add_page();
+        }
+        self.pages.last_mut().expect("pages is not empty")
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Every page with its 1-based number, e.g. to add "page n of m"
+    /// footers once the document is complete.
+    pub fn pages_mut(&mut self) -> impl Iterator<Item = (usize, &mut Page)> {
+        self.pages
+            .iter_mut()
+            .enumerate()
+            .map(|(index, page)| (index + 1, page))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out: Vec<u8> = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = Vec::new();
+        let mut object = |out: &mut Vec<u8>, body: &str| {
+            offsets.push(out.len());
+            out.extend_from_slice(
+                format!("{} 0 obj\n{}\nendobj\n", offsets.len(), body).as_bytes(),
+            );
+        };
+
+        // Objects 1–5 are fixed; each page then takes two: the page and
+        // its content stream.
+        let page_ids: Vec<usize> = (0..self.pages.len()).map(|i| 6 + i * 2).collect();
+        object(&mut out, "<< /Type /Catalog /Pages 2 0 R >>");
+        object(
+            &mut out,
+            &format!(
+                "<< /Type /Pages /Kids [{}] /Count {} /MediaBox [0 0 {:.2} {:.2}] >>",
+                page_ids
+                    .iter()
+                    .map(|id| format!("{} 0 R", id))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                self.pages.len(),
+                self.width,
+                self.height
+            ),
+        );
+        object(
+            &mut out,
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>",
+        );
+        object(
+            &mut out,
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>",
+        );
+        object(
+            &mut out,
+            &format!(
+                "<< /Title ({}) /Producer (timesheet) >>",
+                encode_text(&self.title)
+            ),
+        );
+        for (page, id) in self.pages.iter().zip(&page_ids) {
+            object(
+                &mut out,
+                &format!(
+                    "<< /Type /Page /Parent 2 0 R /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                    id + 1
+                ),
+            );
+            object(
+                &mut out,
+                &format!(
+                    "<< /Length {} >>\nstream\n{}endstream",
+                    page.content.len(),
+                    page.content
+                ),
+            );
+        }
+
+        let xref = out.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1);
+        for offset in &offsets {
+            writeln!(table, "{:010} 00000 n ", offset).expect("write to string");
+        }
+        write!(
+            table,
+            "trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R >>\nstartxref\n{}\n%%EOF\n",
+            offsets.len() + 1,
+            xref
+        )
+        .expect("write to string");
+        out.extend_from_slice(table.as_bytes());
+        out
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+}
+
+/// Width of `text` in points.
+pub fn text_width(text: &str, font: Font, size: f32) -> f32 {
+    let units: u32 = text.chars().map(|c| char_width(c, font)).sum();
+    units as f32 * size / 1000.0
+}
+
+/// `text` cut short with "…" so that it fits in `max_width` points.
+pub fn fit_text(text: &str, max_width: f32, font: Font, size: f32) -> String {
+    if text_width(text, font, size) <= max_width {
+        return text.to_string();
+    }
+    let mut fitted: String = text.to_string();
+    while !fitted.is_empty() && text_width(&format!("{}…", fitted), font, size) > max_width {
+        fitted.pop();
+    }
+    format!("{}…", fitted.trim_end())
+}
+
+/// Helvetica widths (1/1000 em) for ' ' through '~'.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+fn char_width(c: char, font: Font) -> u32 {
+    let widths = match font {
+        Font::Regular => &HELVETICA_WIDTHS,
+        Font::Bold => &HELVETICA_BOLD_WIDTHS,
+    };
+    // Accented letters are as wide as the letter without the accent.
+    let base = match c {
+        'À'..='Å' => 'A',
+        'Ç' => 'C',
+        'È'..='Ë' => 'E',
+        'Ì'..='Ï' => 'I',
+        'Ñ' => 'N',
+        'Ò'..='Ö' | 'Ø' => 'O',
+        'Ù'..='Ü' => 'U',
+        'Ý' => 'Y',
+        'à'..='å' => 'a',
+        'ç' => 'c',
+        'è'..='ë' => 'e',
+        'ì'..='ï' => 'i',
+        'ñ' => 'n',
+        'ò'..='ö' | 'ø' => 'o',
+        'ù'..='ü' => 'u',
+        'ý' | 'ÿ' => 'y',
+        '—' => return 1000,
+        '•' => return 350,
+        '·' => return 278,
+        other => other,
+    };
+    match base {
+        ' '..='~' => widths[base as usize - 0x20].into(),
+        _ => 556,
+    }
+}
+
+/// A PDF string body in WinAnsiEncoding: the Latin-1 range plus the dashes,
+/// quotes and daggers the reports use. Other characters print as "?".
+fn encode_text(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for c in text.chars() {
+        let byte = match c {
+            '(' | ')' | '\\' => {
+                encoded.push('\\');
+                encoded.push(c);
+                continue;
+            }
+            ' '..='~' => {
+                encoded.push(c);
+                continue;
+            }
+            '\u{a0}'..='\u{ff}' => c as u32 as u8,
+            '€' => 0x80,
+            '…' => 0x85,
+            '†' => 0x86,
+            '‡' => 0x87,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            _ => b'?',
+        };
+        write!(encoded, "\\{:03o}", byte).expect("write to string");
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contains(haystack: &[u8], needle: &str) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle.as_bytes())
+    }
+
+    #[test]
+    fn writes_one_page_object_per_page() {
+        let mut doc = PdfDocument::new("Informe (marzo)", LETTER);
+        doc.page()
+            .text(40.0, 40.0, "Hola", TextStyle::new(Font::Bold, 12.0));
+        doc.add_page().line(40.0, 700.0, 200.0, 700.0, 0.5);
+        assert_eq!(doc.page_count(), 2);
+
+        let bytes = doc.to_bytes();
+        assert!(bytes.starts_with(b"%PDF-1.4\n"));
+        assert!(bytes.ends_with(b"%%EOF\n"));
+        assert!(contains(&bytes, "/Kids [6 0 R 8 0 R] /Count 2"));
+        assert!(contains(&bytes, "/Title (Informe \\(marzo\\))"));
+        assert!(contains(&bytes, "/F2 12.00 Tf 40.00 752.00 Td (Hola) Tj"));
+        assert!(contains(&bytes, "40.00 92.00 m 200.00 92.00 l S"));
+
+        // The cross-reference table points at each object's first byte.
+        let xref = bytes
+            .windows(5)
+            .position(|window| window == b"xref\n")
+            .unwrap();
+        let table = String::from_utf8(bytes[xref..].to_vec()).unwrap();
+        for (number, line) in table.lines().skip(3).take(9).enumerate() {
+            let offset: usize = line[..10].parse().unwrap();
+            assert!(bytes[offset..].starts_with(format!("{} 0 obj", number + 1).as_bytes()));
+        }
+    }
+
+    #[test]
+    fn encodes_text_for_win_ansi() {
+        assert_eq!(encode_text("a(b)c\\"), "a\\(b\\)c\\\\");
+        assert_eq!(encode_text("Peña"), "Pe\\361a");
+        assert_eq!(encode_text("8–17 …"), "8\\22617 \\205");
+        assert_eq!(encode_text("日"), "\\077");
+    }
+
+    #[test]
+    fn measures_and_fits_text() {
+        assert_eq!(text_width("AB", Font::Regular, 10.0), 13.34);
+        assert_eq!(text_width("Á", Font::Regular, 10.0), 6.67);
+        assert!(text_width("Hola", Font::Bold, 10.0) > text_width("Hola", Font::Regular, 10.0));
+
+        assert_eq!(fit_text("Corto", 100.0, Font::Regular, 10.0), "Corto");
+        let fitted = fit_text(
+            "Observación demasiado larga para la columna",
+            60.0,
+            Font::Regular,
+            10.0,
+        );
+        assert!(fitted.ends_with('…'));
+        assert!(text_width(&fitted, Font::Regular, 10.0) <= 60.0);
+        assert!("Observación demasiado larga".starts_with(fitted.trim_end_matches('…')));
+    }
+}
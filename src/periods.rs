@@ -0,0 +1,415 @@
+use chrono::{Datelike, Duration, Months, NaiveDate, Weekday};
+use rusqlite::Connection;
+
+use crate::audit::AuditContext;
+use crate::db::{DbError, Result};
+
+const KIND_KEY: &str = "periods.kind";
+const CLOSING_DAY_KEY: &str = "periods.closing_day";
+
+/// Payroll closes on the 20th unless Settings say otherwise.
+pub const DEFAULT_CLOSING_DAY: u32 = 20;
+
+/// Biweekly periods are counted in fourteen-day blocks from this Monday.
+const BIWEEKLY_ANCHOR: NaiveDate = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+
+/// The local days `start` through `end`, inclusive, that a report covers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReportRange {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl ReportRange {
+    /// `None` when `end` is before `start`.
+    pub fn new(start: NaiveDate, end: NaiveDate) -> Option<ReportRange> {
+        (start <= end).then_some(ReportRange { start, end })
+    }
+
+    /// The calendar month of `date`.
+    pub fn month(date: NaiveDate) -> ReportRange {
+        let start = date.with_day(1).unwrap_or(date);
+        let end = start
+            .checked_add_months(Months::new(1))
+            .and_then(|next| next.pred_opt())
+            .unwrap_or(date);
+        ReportRange { start, end }
+    }
+
+    /// "YYYY-MM" when the range is a whole calendar month.
+    pub fn month_key(&self) -> Option<String> {
+        self.is_full_month()
+            .then(|| self.start.format("%Y-%m").to_string())
+    }
+
+    /// "YYYY-MM" of the month whose banco de horas the range carries: a
+    /// whole calendar month, or its first days up to the selected date.
+    pub fn hour_bank_month(&self) -> Option<String> {
+        (self.start.day() == 1
+            && self.start.year() == self.end.year()
+            && self.start.month() == self.end.month())
+        .then(|| self.start.format("%Y-%m").to_string())
+    }
+
+    /// The whole calendar month, first to last day.
+    pub fn is_full_month(&self) -> bool {
+        *self == ReportRange::month(self.start)
+    }
+
+    /// Used in file and folder names: "YYYY-MM" for a whole calendar
+    /// month, or "YYYY-MM-DD_YYYY-MM-DD".
+    pub fn key(&self) -> String {
+        if let Some(key) = self.month_key() {
+            return key;
+        }
+        format!(
+            "{}_{}",
+            self.start.format("%Y-%m-%d"),
+            self.end.format("%Y-%m-%d")
+        )
+    }
+
+    /// Shown in report headings and status messages.
+    pub fn label(&self) -> String {
+        if let Some(key) = self.month_key() {
+            return key;
+        }
+        format!(
+            "{} a {}",
+            self.start.format("%Y-%m-%d"),
+            self.end.format("%Y-%m-%d")
+        )
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start <= date && date <= self.end
+    }
+}
+
+/// The named periods offered in the Reports tab.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PeriodKind {
+    #[default]
+    CalendarMonth,
+    /// Monday to Sunday.
+    Weekly,
+    /// Two weeks from Monday, counted from a fixed Monday.
+    Biweekly,
+    /// The 1st to the 15th and the 16th to the end of the month.
+    SemiMonthly,
+    /// From the day after the closing day to the closing day of the next
+    /// month.
+    ClosingDay,
+}
+
+impl PeriodKind {
+    /// Order of the options in the Reports picker.
+    pub const ALL: [PeriodKind; 5] = [
+        PeriodKind::CalendarMonth,
+        PeriodKind::Weekly,
+        PeriodKind::Biweekly,
+        PeriodKind::SemiMonthly,
+        PeriodKind::ClosingDay,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PeriodKind::CalendarMonth => "Mes calendario",
+            PeriodKind::Weekly => "Semanal",
+            PeriodKind::Biweekly => "Quincenal (14 días)",
+            PeriodKind::SemiMonthly => "Semimensual (1–15, 16–fin)",
+            PeriodKind::ClosingDay => "Mes con cierre el día N",
+        }
+    }
+
+    fn code(self) -> &'static str {
+        match self {
+            PeriodKind::CalendarMonth => "month",
+            PeriodKind::Weekly => "weekly",
+            PeriodKind::Biweekly => "biweekly",
+            PeriodKind::SemiMonthly => "semi_monthly",
+            PeriodKind::ClosingDay => "closing_day",
+        }
+    }
+
+    fn from_code(code: &str) -> Option<PeriodKind> {
+        PeriodKind::ALL.into_iter().find(|k| k.code() == code)
+    }
+
+    pub fn index(self) -> usize {
+        PeriodKind::ALL.iter().position(|k| *k == self).unwrap_or(0)
+    }
+}
+
+/// The period the Reports tab starts from. `closing_day` only matters for
+/// [`PeriodKind::ClosingDay`] and is kept at 28 or less so every month has
+/// it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PeriodDefinition {
+    pub kind: PeriodKind,
+    pub closing_day: u32,
+}
+
+impl Default for PeriodDefinition {
+    fn default() -> Self {
+        PeriodDefinition {
+            kind: PeriodKind::CalendarMonth,
+            closing_day: DEFAULT_CLOSING_DAY,
+        }
+    }
+}
+
+impl PeriodDefinition {
+    /// The period that `date` falls in.
+    pub fn range_containing(&self, date: NaiveDate) -> ReportRange {
+        match self.kind {
+            PeriodKind::CalendarMonth => ReportRange::month(date),
+            PeriodKind::Weekly => {
+                let week = date.week(Weekday::Mon);
+                ReportRange {
+                    start: week.first_day(),
+                    end: week.last_day(),
+                }
+            }
+            PeriodKind::Biweekly => {
+                let offset = (date - BIWEEKLY_ANCHOR).num_days().div_euclid(14) * 14;
+                let start = BIWEEKLY_ANCHOR + Duration::days(offset);
+                ReportRange {
+                    start,
+                    end: start + Duration::days(13),
+                }
+            }
+            PeriodKind::SemiMonthly => {
+                let month = ReportRange::month(date);
+                let fifteenth = month.start + Duration::days(14);
+                if date <= fifteenth {
+                    ReportRange {
+                        start: month.start,
+                        end: fifteenth,
+                    }
+                } else {
+                    ReportRange {
+                        start: fifteenth + Duration::days(1),
+                        end: month.end,
+                    }
+                }
+            }
+            PeriodKind::ClosingDay => {
+                let closing_day = self.closing_day.clamp(1, 28);
+                let Some(close) = date.with_day(closing_day) else {
+                    return ReportRange::month(date);
+                };
+                let (previous, end) = if date <= close {
+                    (close.checked_sub_months(Months::new(1)), Some(close))
+                } else {
+                    (Some(close), close.checked_add_months(Months::new(1)))
+                };
+                match (previous.and_then(|p| p.succ_opt()), end) {
+                    (Some(start), Some(end)) => ReportRange { start, end },
+                    _ => ReportRange::month(date),
+                }
+            }
+        }
+    }
+}
+
+pub fn load_definition(conn: &Connection) -> Result<PeriodDefinition> {
+    let defaults = PeriodDefinition::default();
+    let kind = crate::settings::get(conn, KIND_KEY)?
+        .as_deref()
+        .and_then(PeriodKind::from_code)
+        .unwrap_or(defaults.kind);
+    let closing_day = crate::settings::get(conn, CLOSING_DAY_KEY)?
+        .and_then(|v| v.parse().ok())
+        .filter(|day| (1..=28).contains(day))
+        .unwrap_or(defaults.closing_day);
+    Ok(PeriodDefinition { kind, closing_day })
+}
+
+pub fn save_definition(
+    conn: &mut Connection,
+    definition: PeriodDefinition,
+    ctx: &AuditContext,
+) -> Result<()> {
+    if !(1..=28).contains(&definition.closing_day) {
+        return Err(DbError::Validation(
+            "el día de cierre debe estar entre 1 y 28".to_string(),
+        ));
+    }
+    crate::settings::set_many(
+        conn,
+        &[
+            (KIND_KEY, definition.kind.code()),
+            (CLOSING_DAY_KEY, &definition.closing_day.to_string()),
+        ],
+        ctx,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn range(start: NaiveDate, end: NaiveDate) -> ReportRange {
+        ReportRange::new(start, end).unwrap()
+    }
+
+    fn period(kind: PeriodKind, closing_day: u32) -> PeriodDefinition {
+        PeriodDefinition { kind, closing_day }
+    }
+
+    #[test]
+    fn month_key_only_for_whole_months() {
+        let february = range(date(2024, 2, 1), date(2024, 2, 29));
+        assert_eq!(february.month_key().as_deref(), Some("2024-02"));
+        assert_eq!(february.key(), "2024-02");
+        assert_eq!(february.label(), "2024-02");
+
+        let to_date = range(date(2024, 2, 1), date(2024, 2, 10));
+        assert_eq!(to_date.month_key(), None);
+        assert_eq!(to_date.key(), "2024-02-01_2024-02-10");
+        assert_eq!(to_date.label(), "2024-02-01 a 2024-02-10");
+        assert_eq!(to_date.hour_bank_month().as_deref(), Some("2024-02"));
+
+        let mid_month = range(date(2024, 2, 2), date(2024, 2, 29));
+        assert_eq!(mid_month.month_key(), None);
+        assert_eq!(mid_month.hour_bank_month(), None);
+
+        let two_months = range(date(2024, 1, 1), date(2024, 2, 29));
+        assert_eq!(two_months.month_key(), None);
+        assert_eq!(two_months.hour_bank_month(), None);
+
+        // Leap years: the 28th is not the end of February 2024.
+        assert_eq!(range(date(2024, 2, 1), date(2024, 2, 28)).month_key(), None);
+        assert!(range(date(2023, 2, 1), date(2023, 2, 28)).is_full_month());
+        assert!(range(date(2023, 12, 1), date(2023, 12, 31)).is_full_month());
+    }
+
+    #[test]
+    fn calendar_weekly_and_semi_monthly_periods() {
+        let month = period(PeriodKind::CalendarMonth, DEFAULT_CLOSING_DAY);
+        assert_eq!(
+            month.range_containing(date(2024, 2, 29)),
+            range(date(2024, 2, 1), date(2024, 2, 29))
+        );
+
+        let weekly = period(PeriodKind::Weekly, DEFAULT_CLOSING_DAY);
+        assert_eq!(
+            weekly.range_containing(date(2025, 1, 1)),
+            range(date(2024, 12, 30), date(2025, 1, 5))
+        );
+        assert_eq!(
+            weekly.range_containing(date(2025, 1, 5)),
+            range(date(2024, 12, 30), date(2025, 1, 5))
+        );
+
+        let semi = period(PeriodKind::SemiMonthly, DEFAULT_CLOSING_DAY);
+        assert_eq!(
+            semi.range_containing(date(2023, 2, 15)),
+            range(date(2023, 2, 1), date(2023, 2, 15))
+        );
+        assert_eq!(
+            semi.range_containing(date(2023, 2, 16)),
+            range(date(2023, 2, 16), date(2023, 2, 28))
+        );
+        assert_eq!(
+            semi.range_containing(date(2024, 1, 31)),
+            range(date(2024, 1, 16), date(2024, 1, 31))
+        );
+    }
+
+    #[test]
+    fn biweekly_periods_count_from_the_anchor() {
+        let biweekly = period(PeriodKind::Biweekly, DEFAULT_CLOSING_DAY);
+        assert_eq!(
+            biweekly.range_containing(BIWEEKLY_ANCHOR),
+            range(date(2024, 1, 1), date(2024, 1, 14))
+        );
+        assert_eq!(
+            biweekly.range_containing(date(2024, 1, 15)),
+            range(date(2024, 1, 15), date(2024, 1, 28))
+        );
+        // Before the anchor the blocks continue backwards.
+        assert_eq!(
+            biweekly.range_containing(date(2023, 12, 31)),
+            range(date(2023, 12, 18), date(2023, 12, 31))
+        );
+    }
+
+    #[test]
+    fn closing_day_periods_across_month_and_year_ends() {
+        let closing = period(PeriodKind::ClosingDay, 20);
+        assert_eq!(
+            closing.range_containing(date(2024, 2, 20)),
+            range(date(2024, 1, 21), date(2024, 2, 20))
+        );
+        assert_eq!(
+            closing.range_containing(date(2024, 2, 21)),
+            range(date(2024, 2, 21), date(2024, 3, 20))
+        );
+        assert_eq!(
+            closing.range_containing(date(2024, 1, 10)),
+            range(date(2023, 12, 21), date(2024, 1, 20))
+        );
+        assert_eq!(
+            closing.range_containing(date(2024, 12, 31)),
+            range(date(2024, 12, 21), date(2025, 1, 20))
+        );
+
+        let end_of_february = period(PeriodKind::ClosingDay, 28);
+        assert_eq!(
+            end_of_february.range_containing(date(2024, 2, 29)),
+            range(date(2024, 2, 29), date(2024, 3, 28))
+        );
+        assert_eq!(
+            end_of_february.range_containing(date(2023, 3, 1)),
+            range(date(2023, 3, 1), date(2023, 3, 28))
+        );
+
+        // Out-of-range closing days are clamped to the 28th.
+        assert_eq!(
+            period(PeriodKind::ClosingDay, 31).range_containing(date(2024, 1, 31)),
+            range(date(2024, 1, 29), date(2024, 2, 28))
+        );
+    }
+
+    #[test]
+    fn a_failed_save_changes_no_setting() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&mut conn, std::path::Path::new("backups")).unwrap();
+        let ctx = AuditContext::admin("test");
+        conn.execute_batch(
+            "CREATE TRIGGER reject_closing_day BEFORE INSERT ON settings
+             WHEN NEW.key = 'periods.closing_day'
+             BEGIN SELECT RAISE(ABORT, 'rechazado'); END;",
+        )
+        .unwrap();
+
+        let audited = |conn: &Connection| -> i64 {
+            conn.query_row(
+                "SELECT COUNT(*) FROM audit_log WHERE entity = 'settings'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        let before = audited(&conn);
+        let result = save_definition(&mut conn, period(PeriodKind::Weekly, 20), &ctx);
+        assert!(matches!(result, Err(DbError::Sqlite(_))));
+        // The kind was written first and rolled back with the closing day.
+        assert_eq!(crate::settings::get(&conn, KIND_KEY).unwrap(), None);
+        assert_eq!(audited(&conn), before);
+
+        conn.execute_batch("DROP TRIGGER reject_closing_day")
+            .unwrap();
+        save_definition(&mut conn, period(PeriodKind::Weekly, 20), &ctx).unwrap();
+        assert_eq!(
+            load_definition(&conn).unwrap(),
+            period(PeriodKind::Weekly, 20)
+        );
+    }
+}
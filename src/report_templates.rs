@@ -0,0 +1,472 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use minijinja::{AutoEscape, Environment, UndefinedBehavior};
+use serde::Serialize;
+
+/// One worker's timesheet, e.g. `2025-10_Ana.html`. Gets a
+/// [`WorkerReportView`] as `worker` plus `period`.
+pub const WORKER_TEMPLATE: &str = "worker_report.html";
+/// Every worker in one file, `2025-10_all_workers.html`. Gets a
+/// [`MergedReportView`].
+pub const MERGED_TEMPLATE: &str = "all_workers.html";
+/// The table, totals and footnotes of one worker; included by both.
+pub const WORKER_SECTION_TEMPLATE: &str = "worker_section.html";
+
+/// Shipped with the binary. A file with the same name in the data
+/// directory's `templates` folder replaces the one here.
+const DEFAULT_TEMPLATES: [(&str, &str); 3] = [
+    (
+        WORKER_TEMPLATE,
+        include_str!("../templates/worker_report.html"),
+    ),
+    (
+        MERGED_TEMPLATE,
+        include_str!("../templates/all_workers.html"),
+    ),
+    (
+        WORKER_SECTION_TEMPLATE,
+        include_str!("../templates/worker_section.html"),
+    ),
+];
+
+/// Data given to [`WORKER_TEMPLATE`].
+#[derive(Clone, Debug, Serialize)]
+pub struct WorkerReportContext {
+    /// The report's period, e.g. "2025-10" or "2025-09-21 a 2025-10-20".
+    pub period: String,
+    pub worker: WorkerReportView,
+}
+
+/// Data given to [`MERGED_TEMPLATE`].
+#[derive(Clone, Debug, Serialize)]
+pub struct MergedReportView {
+    pub period: String,
+    pub workers: Vec<WorkerReportView>,
+}
+
+/// A worker's timesheet for the period. Durations come both as minutes
+/// (`*_minutes`) and as "HH:MM" labels (`*_label`).
+#[derive(Clone, Debug, Serialize)]
+pub struct WorkerReportView {
+    pub name: String,
+    /// Every day of the period, including days without sessions.
+    pub days: Vec<DayView>,
+    pub totals: TotalsView,
+    /// Only for ranges that are (the start of) a calendar month.
+    pub hour_bank: Option<HourBankView>,
+    /// Some session has no clock-out yet; its duration runs to now.
+    pub has_open_sessions: bool,
+    /// Some session was corrected by hand (marked †).
+    pub has_corrections: bool,
+    /// Some session was closed automatically (marked ‡).
+    pub has_auto_closed: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DayView {
+    /// "YYYY-MM-DD".
+    pub date: String,
+    /// "MM/DD", as in the built-in reports.
+    pub date_label: String,
+    /// Spanish weekday name, e.g. "lunes".
+    pub weekday: String,
+    /// "holiday", "absence", "sunday", "saturday" or "workday", in that
+    /// order of precedence; the default templates use it as a CSS class.
+    pub kind: &'static str,
+    /// Name of the public holiday.
+    pub holiday: Option<String>,
+    /// Leave the worker was on, e.g. "Vacaciones (con goce)".
+    pub absence: Option<String>,
+    /// At least one entry; a day without sessions has a single placeholder
+    /// with "--:--:--" punches.
+    pub sessions: Vec<SessionView>,
+    pub total_minutes: i64,
+    pub total_label: String,
+    pub minutes_needed: i64,
+    /// Minutes credited by a paid absence.
+    pub absence_minutes: i64,
+    /// Worked and credited minutes minus `minutes_needed`.
+    pub balance_minutes: i64,
+    pub ordinary_minutes: i64,
+    pub overtime_minutes: i64,
+    pub overtime_label: String,
+    pub late_minutes: i64,
+    pub early_leave_minutes: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SessionView {
+    /// Punches as scanned, "HH:MM:SS". A session split at midnight shows
+    /// "00:00:00" or "24:00:00" at the cut; an open one ends in "*".
+    pub clock_in: String,
+    pub clock_out: String,
+    /// Paid times ("HH:MM") when rounding or a grace window moved a punch.
+    pub clock_in_adjusted: Option<String>,
+    pub clock_out_adjusted: Option<String>,
+    /// Paid minutes after rounding and unpaid breaks.
+    pub duration_minutes: i64,
+    pub duration_label: String,
+    pub break_minutes: i64,
+    pub break_label: String,
+    pub unpaid_break_minutes: i64,
+    pub open: bool,
+    /// Reason for a manual correction, e.g. "Olvido de marca".
+    pub correction: Option<String>,
+    /// "Cierre automático" or "Requiere revisión".
+    pub auto_close: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TotalsView {
+    pub total_minutes: i64,
+    pub total_label: String,
+    pub ordinary_minutes: i64,
+    pub ordinary_label: String,
+    pub overtime_minutes: i64,
+    pub overtime_label: String,
+    /// Minutes worked on holidays, also part of the total.
+    pub holiday_minutes: i64,
+    pub holiday_label: String,
+    /// Minutes credited by paid absences, not part of the total.
+    pub absence_minutes: i64,
+    pub absence_label: String,
+    pub break_minutes: i64,
+    pub break_label: String,
+    pub unpaid_break_minutes: i64,
+    pub unpaid_break_label: String,
+    pub late_days: i64,
+    pub late_minutes: i64,
+    pub early_leave_days: i64,
+    pub early_leave_minutes: i64,
+}
+
+/// Banco de horas. Balances are signed "+HH:MM" labels next to minutes.
+#[derive(Clone, Debug, Serialize)]
+pub struct HourBankView {
+    /// False while the month is provisional.
+    pub closed: bool,
+    pub opening_minutes: i64,
+    pub opening: String,
+    pub delta_minutes: i64,
+    pub delta: String,
+    pub adjustments_minutes: i64,
+    pub adjustments: String,
+    pub closing_minutes: i64,
+    pub closing: String,
+    pub items: Vec<AdjustmentView>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AdjustmentView {
+    /// "MM/DD".
+    pub date_label: String,
+    pub minutes: i64,
+    pub amount: String,
+    pub reason: String,
+}
+
+/// A template that failed to load or render. Shown in the UI as is, so it
+/// names the file and, when known, the line.
+#[derive(Debug)]
+pub struct TemplateError(String);
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+impl From<minijinja::Error> for TemplateError {
+    fn from(err: minijinja::Error) -> Self {
+        let mut message = err.to_string();
+        let mut source = std::error::Error::source(&err);
+        while let Some(cause) = source {
+            message.push_str(&format!(": {}", cause));
+            source = cause.source();
+        }
+        TemplateError(message)
+    }
+}
+
+/// Where override templates are looked up.
+pub fn templates_dir() -> PathBuf {
+    crate::config::data_dir().root().join("templates")
+}
+
+/// The report templates, each read from `overrides_dir` when a file with
+/// its name exists there and from the built-in defaults otherwise.
+///
+/// Besides the minijinja built-ins, templates can use the `hhmm` filter
+/// ("HH:MM" from minutes, "-HH:MM" when negative) and `signed_hhmm`
+/// ("+HH:MM"/"-HH:MM").
+pub struct ReportTemplates {
+    env: Environment<'static>,
+}
+
+impl ReportTemplates {
+    pub fn load(overrides_dir: &Path) -> ReportTemplates {
+        let mut env = Environment::new();
+        // A misspelt field is an error rather than an empty cell.
+        env.set_undefined_behavior(UndefinedBehavior::SemiStrict);
+        let dir = overrides_dir.to_path_buf();
+        env.set_loader(move |name| {
+            if name.contains(['/', '\\']) || name.starts_with('.') {
+                return Ok(None);
+            }
+            let path = dir.join(name);
+            if path.is_file() {
+                return fs::read_to_string(&path).map(Some).map_err(|e| {
+                    minijinja::Error::new(
+                        minijinja::ErrorKind::InvalidOperation,
+                        format!("no se pudo leer {}", path.display()),
+                    )
+                    .with_source(e)
+                });
+            }
+            Ok(DEFAULT_TEMPLATES
+                .iter()
+                .find(|(default, _)| *default == name)
+                .map(|(_, source)| source.to_string()))
+        });
+        // Escape as the reports did before templates, leaving "/" in dates
+        // as is; minijinja's own HTML escaping writes it as "&#x2f;".
+        env.set_formatter(|out, state, value| match value.as_str() {
+            Some(text) if state.auto_escape() == AutoEscape::Html && !value.is_safe() => {
+                out.write_str(&crate::reports::escape_html(text))?;
+                Ok(())
+            }
+            _ => minijinja::escape_formatter(out, state, value),
+        });
+        env.add_filter("hhmm", |minutes: i64| {
+            let sign = if minutes < 0 { "-" } else { "" };
+            let abs = minutes.abs();
+            format!("{}{:02}:{:02}", sign, abs / 60, abs % 60)
+        });
+        env.add_filter("signed_hhmm", |minutes: i64| {
+            crate::hour_bank::format_balance(minutes)
+        });
+        ReportTemplates { env }
+    }
+
+    pub fn render_worker(&self, context: &WorkerReportContext) -> Result<String, TemplateError> {
+        Ok(self.env.get_template(WORKER_TEMPLATE)?.render(context)?)
+    }
+
+    pub fn render_merged(&self, view: &MergedReportView) -> Result<String, TemplateError> {
+        Ok(self.env.get_template(MERGED_TEMPLATE)?.render(view)?)
+    }
+
+    /// Compiles every template, catching syntax errors even in parts that
+    /// the current data would not reach.
+    pub fn compile(&self) -> Result<(), TemplateError> {
+        for (name, _) in DEFAULT_TEMPLATES {
+            self.env.get_template(name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the built-in templates into `dir` as a starting point for
+/// overrides. Existing files are kept. Returns how many were written.
+pub fn export_defaults(dir: &Path) -> std::io::Result<usize> {
+    fs::create_dir_all(dir)?;
+    let mut written = 0;
+    for (name, source) in DEFAULT_TEMPLATES {
+        let path = dir.join(name);
+        if !path.exists() {
+            fs::write(path, source)?;
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn templates() -> ReportTemplates {
+        ReportTemplates::load(&std::env::temp_dir().join("timesheet-no-template-overrides"))
+    }
+
+    fn session(clock_in: &str, clock_out: &str, minutes: i64, label: &str) -> SessionView {
+        SessionView {
+            clock_in: clock_in.to_string(),
+            clock_out: clock_out.to_string(),
+            clock_in_adjusted: None,
+            clock_out_adjusted: None,
+            duration_minutes: minutes,
+            duration_label: label.to_string(),
+            break_minutes: 0,
+            break_label: "00:00".to_string(),
+            unpaid_break_minutes: 0,
+            open: false,
+            correction: None,
+            auto_close: None,
+        }
+    }
+
+    fn day(date_label: &str, weekday: &str, kind: &'static str) -> DayView {
+        DayView {
+            date: format!("2024-{}", date_label.replace('/', "-")),
+            date_label: date_label.to_string(),
+            weekday: weekday.to_string(),
+            kind,
+            holiday: None,
+            absence: None,
+            sessions: vec![session("--:--:--", "--:--:--", 0, "00:00")],
+            total_minutes: 0,
+            total_label: "00:00".to_string(),
+            minutes_needed: 0,
+            absence_minutes: 0,
+            balance_minutes: 0,
+            ordinary_minutes: 0,
+            overtime_minutes: 0,
+            overtime_label: "00:00".to_string(),
+            late_minutes: 0,
+            early_leave_minutes: 0,
+        }
+    }
+
+    /// A worker with every optional part of the report filled in.
+    fn worker() -> WorkerReportView {
+        let mut workday = day("03/04", "lunes", "workday");
+        let mut morning = session("09:05:10", "13:00:00", 235, "03:55");
+        morning.clock_in_adjusted = Some("09:00".to_string());
+        morning.break_minutes = 15;
+        morning.break_label = "00:15".to_string();
+        morning.correction = Some("Olvido de marca".to_string());
+        let mut afternoon = session("14:00:00", "18:30:00", 270, "04:30");
+        afternoon.auto_close = Some("Cierre automático".to_string());
+        workday.sessions = vec![morning, afternoon];
+        workday.total_minutes = 505;
+        workday.total_label = "08:25".to_string();
+        workday.balance_minutes = -5;
+        workday.overtime_minutes = 30;
+        workday.overtime_label = "00:30".to_string();
+        workday.late_minutes = 5;
+
+        let mut holiday = day("03/29", "viernes", "holiday");
+        holiday.holiday = Some("Viernes Santo".to_string());
+        let mut absence = day("03/05", "martes", "absence");
+        absence.absence = Some("Vacaciones (con goce)".to_string());
+
+        WorkerReportView {
+            name: "Ana".to_string(),
+            days: vec![workday, absence, holiday],
+            totals: TotalsView {
+                total_minutes: 505,
+                total_label: "08:25".to_string(),
+                ordinary_minutes: 475,
+                ordinary_label: "07:55".to_string(),
+                overtime_minutes: 30,
+                overtime_label: "00:30".to_string(),
+                holiday_minutes: 60,
+                holiday_label: "01:00".to_string(),
+                absence_minutes: 480,
+                absence_label: "08:00".to_string(),
+                break_minutes: 15,
+                break_label: "00:15".to_string(),
+                unpaid_break_minutes: 0,
+                unpaid_break_label: "00:00".to_string(),
+                late_days: 1,
+                late_minutes: 5,
+                early_leave_days: 0,
+                early_leave_minutes: 0,
+            },
+            hour_bank: Some(HourBankView {
+                closed: false,
+                opening_minutes: 60,
+                opening: "+01:00".to_string(),
+                delta_minutes: -5,
+                delta: "-00:05".to_string(),
+                adjustments_minutes: -30,
+                adjustments: "-00:30".to_string(),
+                closing_minutes: 25,
+                closing: "+00:25".to_string(),
+                items: vec![AdjustmentView {
+                    date_label: "03/15".to_string(),
+                    minutes: -30,
+                    amount: "-00:30".to_string(),
+                    reason: "Permiso".to_string(),
+                }],
+            }),
+            has_open_sessions: true,
+            has_corrections: true,
+            has_auto_closed: true,
+        }
+    }
+
+    /// Fragments the reports wrote before they moved to templates, for the
+    /// data in [`worker`]. Line breaks are ignored on both sides.
+    const BASELINE_FRAGMENTS: [&str; 19] = [
+        "<table><thead><tr><th>Fecha</th><th>Entrada</th><th>Salida</th><th>Horas (Minutos)</th><th>Total Horas Dia (Minutos)</th><th>Saldo (Minutos)</th><th>Horas Extras</th><th>Atraso (Minutos)</th><th>Salida Anticipada (Minutos)</th></tr></thead><tbody>",
+        "<tr class=\"day-even\"><td rowspan=\"2\"><strong>03/04</strong><br/><small>lunes</small></td>",
+        "<td>09:05:10<br/><small>→ 09:00</small></td><td>13:00:00</td><td>03:55<br/><small>Colación 00:15 (pagada)</small> †<br/><small>Olvido de marca</small></td>",
+        "<td rowspan=\"2\">08:25 (505)</td><td rowspan=\"2\">-5</td><td rowspan=\"2\">00:30</td><td rowspan=\"2\">5</td><td rowspan=\"2\"></td></tr>",
+        "<tr class=\"day-even\"><td>14:00:00</td><td>18:30:00</td><td>04:30 ‡<br/><small>Cierre automático</small></td></tr>",
+        "<tr class=\"day-odd absence\"><td rowspan=\"1\"><strong>03/05</strong><br/><small>martes</small><br/><small>Vacaciones (con goce)</small></td>",
+        "<tr class=\"day-even holiday\"><td rowspan=\"1\"><strong>03/29</strong><br/><small>viernes</small><br/><small>Feriado: Viernes Santo</small></td>",
+        "</tbody></table>",
+        "<p><strong>Total:</strong> 08:25 (505 minutes)</p>",
+        "<p><strong>Horas ordinarias:</strong> 07:55 (475 minutes)<br/><strong>Horas extras:</strong> 00:30 (30 minutes)</p>",
+        "<p><strong>Banco de horas (provisional):</strong> saldo inicial +01:00 · saldo del mes -00:05 · ajustes -00:30 · saldo final <strong>+00:25</strong></p>",
+        "<li>03/15: -00:30 — Permiso</li>",
+        "<p><strong>Atrasos:</strong> 1 días (5 minutes)<br/><strong>Salidas anticipadas:</strong> 0 días (0 minutes)</p>",
+        "<p><strong>Colación:</strong> 00:15 (15 minutes); 00:00 sin goce descontadas del total</p>",
+        "<p><strong>Horas en feriados:</strong> 01:00 (60 minutes)</p>",
+        "<p><strong>Ausencias con goce:</strong> 08:00 (480 minutes)</p>",
+        "<p>* Entries marked with an asterisk do not have a recorded clock out; the current time was used to compute the duration.</p>",
+        "<p>† Entries marked with a dagger were corrected manually; the reason is shown under the duration.</p>",
+        "<p>‡ Entries marked with a double dagger were closed automatically because nobody clocked out; rows that require review count zero hours until they are corrected.</p>",
+    ];
+
+    fn assert_baseline_fields(html: &str) {
+        let html = html.replace('\n', "");
+        for fragment in BASELINE_FRAGMENTS {
+            assert!(html.contains(fragment), "missing {}\nin {}", fragment, html);
+        }
+    }
+
+    #[test]
+    fn default_templates_render_the_baseline_fields() {
+        let templates = templates();
+        templates.compile().unwrap();
+        let html = templates
+            .render_worker(&WorkerReportContext {
+                period: "2024-03".to_string(),
+                worker: worker(),
+            })
+            .unwrap();
+        assert!(html.contains("<title>Timesheet Ana 2024-03</title>"));
+        assert!(html.contains("<h1>Ana</h1><h2>Period: 2024-03</h2>"));
+        assert_baseline_fields(&html);
+
+        let html = templates
+            .render_merged(&MergedReportView {
+                period: "2024-03".to_string(),
+                workers: vec![worker(), worker()],
+            })
+            .unwrap();
+        assert!(html.contains("<h1>All Workers Timesheet</h1><h2>Period: 2024-03</h2>"));
+        assert!(html.contains("<div class=\"page-break\"></div>"));
+        assert_baseline_fields(&html);
+    }
+
+    #[test]
+    fn hhmm_filter_keeps_the_sign_in_front() {
+        let env = templates().env;
+        let render = |minutes: i64| {
+            env.render_str("{{ m|hhmm }}", minijinja::context! { m => minutes })
+                .unwrap()
+        };
+        assert_eq!(render(0), "00:00");
+        assert_eq!(render(90), "01:30");
+        assert_eq!(render(-90), "-01:30");
+        assert_eq!(render(-5), "-00:05");
+    }
+}
@@ -1,10 +1,28 @@
+use crate::audit::{AuditContext, csv_field};
+use crate::auto_close::AutoCloseMark;
+use crate::corrections::ReasonCode;
 use crate::db::{self, TimesheetEntry};
-use chrono::{Datelike, Duration, NaiveDate, Utc, Weekday};
-use chrono_tz::America::Santiago;
+use crate::hour_bank::{Adjustment, MonthBalance, format_balance};
+use crate::overtime::WeekOvertime;
+use crate::payroll_export::{ExportProfile, HoursFormat, PayrollField};
+use crate::pdf::{self, Align, Color, Font, Page, PdfDocument, TextStyle};
+use crate::periods::ReportRange;
+use crate::report_templates::{
+    self, AdjustmentView, DayView, HourBankView, MergedReportView, ReportTemplates, SessionView,
+    TemplateError, TotalsView, WorkerReportContext, WorkerReportView,
+};
+use crate::timekeeping::{DaySegment, PunctualitySummary, Timekeeper};
+use crate::timezone;
+use crate::utils::local_today_naive;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
 use lettre::message::{Attachment, MultiPart, SinglePart, header};
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::{Message, SmtpTransport, Transport};
 use rusqlite::Connection;
+use rust_xlsxwriter::utility::{quote_sheet_name, row_col_to_cell};
+use rust_xlsxwriter::{
+    Color as XlsxColor, ExcelDateTime, Format, FormatBorder, Formula, Workbook, XlsxError,
+};
 use std::collections::BTreeMap;
 use std::env;
 use std::fmt::{self, Write as _};
@@ -20,17 +38,17 @@ const REPORT_SMTP_PORT: u16 = 465;
 #[derive(Clone)]
 struct WorkerReportData {
     worker_name: String,
-    day_groups: Vec<DayGroup>,
-    total_minutes: i64,
-    has_open_sessions: bool,
+    rows: WorkerRows,
 }
 
 #[derive(Debug)]
 pub enum ReportError {
-    Database(rusqlite::Error),
+    Database(db::DbError),
     Io(std::io::Error),
     InvalidMonth(String),
     Email(String),
+    Spreadsheet(XlsxError),
+    Template(TemplateError),
 }
 
 impl fmt::Display for ReportError {
@@ -40,15 +58,23 @@ impl fmt::Display for ReportError {
             ReportError::Io(e) => write!(f, "io error: {}", e),
             ReportError::InvalidMonth(m) => write!(f, "invalid month value: {}", m),
             ReportError::Email(m) => write!(f, "email error: {}", m),
+            ReportError::Spreadsheet(e) => write!(f, "spreadsheet error: {}", e),
+            ReportError::Template(e) => write!(f, "template error: {}", e),
         }
     }
 }
 
 impl std::error::Error for ReportError {}
 
+impl From<db::DbError> for ReportError {
+    fn from(value: db::DbError) -> Self {
+        ReportError::Database(value)
+    }
+}
+
 impl From<rusqlite::Error> for ReportError {
     fn from(value: rusqlite::Error) -> Self {
-        ReportError::Database(value)
+        ReportError::Database(value.into())
     }
 }
 
@@ -58,103 +84,329 @@ impl From<std::io::Error> for ReportError {
     }
 }
 
+impl From<XlsxError> for ReportError {
+    fn from(value: XlsxError) -> Self {
+        ReportError::Spreadsheet(value)
+    }
+}
+
+impl From<TemplateError> for ReportError {
+    fn from(value: TemplateError) -> Self {
+        ReportError::Template(value)
+    }
+}
+
 #[derive(Clone)]
 struct ReportRow {
     date: NaiveDate,
     clock_in: String,
     clock_out: String,
+    /// Paid times ("HH:MM") when rounding or a grace window moved a punch.
+    clock_in_adjusted: Option<String>,
+    clock_out_adjusted: Option<String>,
     duration_minutes: i64,
     duration_label: String,
+    /// Breaks inside the session, paid or not.
+    break_minutes: i64,
+    unpaid_break_minutes: i64,
     is_open: bool,
+    correction: Option<ReasonCode>,
+    auto_close: Option<AutoCloseMark>,
 }
 
+#[allow(dead_code)]
 #[derive(Clone)]
 struct DayGroup {
     date: NaiveDate,
     weekday_name: String,
     rows: Vec<ReportRow>,
     is_weekend: bool,
+    /// Name of the public holiday on this date, if any.
+    holiday: Option<String>,
+    /// Leave the worker was on this date, e.g. "Vacaciones (con goce)".
+    absence: Option<String>,
+    /// Scheduled minutes credited by a paid absence.
+    absence_minutes: i64,
     daily_total_minutes: i64,
+    /// Split of `daily_total_minutes` under the overtime rules.
+    ordinary_minutes: i64,
+    overtime_minutes: i64,
+    /// Minutes late at the first clock-in and early at the last clock-out.
+    late_minutes: i64,
+    early_leave_minutes: i64,
     minutes_needed: i64,
     daily_balance: i64,
 }
 
-pub fn generate_monthly_reports(
+/// Writes the per-worker, merged and overtime reports for the days in
+/// `range`. File names start with [`ReportRange::key`].
+pub fn generate_reports(
     conn: &Connection,
-    month: NaiveDate,
-    selected_date: NaiveDate,
+    range: ReportRange,
     output_root: &Path,
 ) -> Result<(), ReportError> {
-    let month_key = month.format("%Y-%m").to_string();
+    let range_key = range.key();
+    let period = range.label();
+    let templates = ReportTemplates::load(&report_templates::templates_dir());
     fs::create_dir_all(output_root)?;
 
-    let workers = db::get_workers(conn)?;
+    let workers = db::get_workers_for_range(conn, range.start, range.end)?;
     let mut all_worker_data = Vec::new();
     let mut worker_html_paths = Vec::new();
 
     for worker in workers {
-        let worker_rows = build_rows(conn, worker.id, &month_key, selected_date)?;
+        let worker_rows = build_rows(conn, worker.id, range)?;
         let worker_dir = output_root;
         let sanitized_name = sanitize_filename(&worker.name);
 
-        let html_path = worker_dir.join(format!("{}_{}.html", month_key, sanitized_name));
-        let csv_path = worker_dir.join(format!("{}_{}.csv", month_key, sanitized_name));
+        let html_path = worker_dir.join(format!("{}_{}.html", range_key, sanitized_name));
+        let csv_path = worker_dir.join(format!("{}_{}.csv", range_key, sanitized_name));
 
-        write_html_report(
-            &html_path,
-            &worker.name,
-            &month_key,
-            &worker_rows.day_groups,
-            worker_rows.total_minutes,
-            worker_rows.has_open_sessions,
-        )?;
+        let worker_data = WorkerReportData {
+            worker_name: worker.name,
+            rows: worker_rows,
+        };
+        write_html_report(&html_path, &templates, &period, &worker_data)?;
         worker_html_paths.push(html_path);
         write_csv_report(
             &csv_path,
-            &worker.name,
-            &month_key,
-            &worker_rows.day_groups,
-            worker_rows.total_minutes,
+            &worker_data.worker_name,
+            &period,
+            &worker_data.rows,
+        )?;
+        write_pdf_report(
+            &worker_dir.join(format!("{}_{}.pdf", range_key, sanitized_name)),
+            &worker_data.worker_name,
+            &period,
+            &worker_data.rows,
         )?;
 
         // Collect data for merged report
-        all_worker_data.push(WorkerReportData {
-            worker_name: worker.name,
-            day_groups: worker_rows.day_groups,
-            total_minutes: worker_rows.total_minutes,
-            has_open_sessions: worker_rows.has_open_sessions,
-        });
+        all_worker_data.push(worker_data);
     }
 
     // Generate merged HTML report
-    let merged_html_path = output_root.join(format!("{}_all_workers.html", month_key));
-    write_merged_html_report(&merged_html_path, &month_key, &all_worker_data)?;
-    send_worker_html_reports_email(&month_key, &worker_html_paths)?;
+    let merged_html_path = output_root.join(format!("{}_all_workers.html", range_key));
+    write_merged_html_report(&merged_html_path, &templates, &period, &all_worker_data)?;
+    write_merged_pdf_report(
+        &output_root.join(format!("{}_all_workers.pdf", range_key)),
+        &period,
+        &all_worker_data,
+    )?;
+    write_xlsx_workbook(
+        &output_root.join(format!("{}_all_workers.xlsx", range_key)),
+        &period,
+        &all_worker_data,
+    )?;
+    write_overtime_summary_html(
+        &output_root.join(format!("{}_horas_extras.html", range_key)),
+        &period,
+        &all_worker_data,
+    )?;
+    write_overtime_summary_csv(
+        &output_root.join(format!("{}_horas_extras.csv", range_key)),
+        &period,
+        &all_worker_data,
+    )?;
+    send_worker_html_reports_email(&period, &worker_html_paths)?;
 
     Ok(())
 }
 
+/// Renders the HTML templates with the workers of `range` without writing
+/// any file, so a broken override shows up before reports are generated.
+/// Returns how many workers were rendered.
+pub fn check_templates(conn: &Connection, range: ReportRange) -> Result<usize, ReportError> {
+    let templates = ReportTemplates::load(&report_templates::templates_dir());
+    templates.compile()?;
+    let period = range.label();
+    let mut workers = Vec::new();
+    for worker in db::get_workers_for_range(conn, range.start, range.end)? {
+        let data = WorkerReportData {
+            rows: build_rows(conn, worker.id, range)?,
+            worker_name: worker.name,
+        };
+        templates.render_worker(&WorkerReportContext {
+            period: period.clone(),
+            worker: worker_view(&data),
+        })?;
+        workers.push(worker_view(&data));
+    }
+    let count = workers.len();
+    templates.render_merged(&MergedReportView { period, workers })?;
+    Ok(count)
+}
+
+/// A payroll file written by [`export_payroll`].
+pub struct PayrollExport {
+    pub path: PathBuf,
+    pub workers: usize,
+    /// Workers exported without a RUT, when the profile has that column.
+    pub missing_rut: Vec<String>,
+}
+
+/// Writes one line per worker with the totals for `range`, laid out as
+/// `profile` says, to `{key}_remuneraciones_{profile}.csv`.
+pub fn export_payroll(
+    conn: &Connection,
+    range: ReportRange,
+    profile: &ExportProfile,
+    output_root: &Path,
+) -> Result<PayrollExport, ReportError> {
+    fs::create_dir_all(output_root)?;
+    let format = profile.format;
+    let mut contents = String::new();
+    if format.header {
+        contents.push_str(&format.record(profile.columns.iter().map(|c| c.header.clone())));
+    }
+    let has_rut = profile.columns.iter().any(|c| c.field == PayrollField::Rut);
+    let workers = db::get_workers_for_range(conn, range.start, range.end)?;
+    let mut missing_rut = Vec::new();
+    for worker in &workers {
+        let rows = build_rows(conn, worker.id, range)?;
+        contents.push_str(
+            &format.record(
+                profile
+                    .columns
+                    .iter()
+                    .map(|c| payroll_value(c.field, worker, range, &rows, format.hours)),
+            ),
+        );
+        if has_rut && worker.rut.is_none() {
+            missing_rut.push(worker.name.clone());
+        }
+    }
+
+    let path = output_root.join(format!(
+        "{}_remuneraciones_{}.csv",
+        range.key(),
+        sanitize_filename(&profile.name)
+    ));
+    fs::write(&path, format.encoding.encode(&contents)?)?;
+    Ok(PayrollExport {
+        path,
+        workers: workers.len(),
+        missing_rut,
+    })
+}
+
+fn payroll_value(
+    field: PayrollField,
+    worker: &db::Worker,
+    range: ReportRange,
+    rows: &WorkerRows,
+    hours: HoursFormat,
+) -> String {
+    let count_days = |f: fn(&DayGroup) -> bool| rows.day_groups.iter().filter(|g| f(g)).count();
+    match field {
+        PayrollField::Rut => worker.rut.clone().unwrap_or_default(),
+        PayrollField::Name => worker.name.clone(),
+        PayrollField::Period => range.label(),
+        PayrollField::PeriodStart => range.start.format("%Y-%m-%d").to_string(),
+        PayrollField::PeriodEnd => range.end.format("%Y-%m-%d").to_string(),
+        PayrollField::DaysWorked => count_days(|g| g.daily_total_minutes > 0).to_string(),
+        PayrollField::TotalHours => hours.format(rows.total_minutes),
+        PayrollField::OrdinaryHours => hours.format(rows.ordinary_minutes),
+        PayrollField::OvertimeHours => hours.format(rows.overtime_minutes),
+        PayrollField::HolidayHours => hours.format(rows.holiday_minutes),
+        PayrollField::AbsenceDays => {
+            count_days(|g| g.absence.is_some() && g.minutes_needed > 0).to_string()
+        }
+        PayrollField::AbsenceHours => hours.format(rows.absence_minutes),
+        PayrollField::LateMinutes => rows.punctuality.late_minutes.to_string(),
+        PayrollField::HourBank => rows
+            .hour_bank
+            .as_ref()
+            .map(|bank| hours.format(bank.closing))
+            .unwrap_or_default(),
+    }
+}
+
+/// Closes the banco de horas for `month`: each worker's balance for the
+/// whole month is stored and becomes the opening of the next month.
+/// Returns how many workers were closed.
+pub fn close_hour_bank(
+    conn: &mut Connection,
+    month: NaiveDate,
+    ctx: &AuditContext,
+) -> Result<usize, ReportError> {
+    let range = ReportRange::month(month);
+    let month_key = month.format("%Y-%m").to_string();
+    if range.end >= local_today_naive() {
+        return Err(db::DbError::Validation(format!("el mes {} aún no termina", month_key)).into());
+    }
+    let tx = conn.transaction()?;
+    crate::hour_bank::check_close_order(&tx, &month_key)?;
+    let workers = db::get_workers_for_month(&tx, &month_key)?;
+    for worker in &workers {
+        let rows = build_rows(&tx, worker.id, range)?;
+        let delta = rows.day_groups.iter().map(|g| g.daily_balance).sum();
+        crate::hour_bank::record_close(&tx, worker.id, &month_key, delta, ctx)?;
+    }
+    tx.commit()?;
+    Ok(workers.len())
+}
+
+/// Each worker's banco de horas for the month of `selected_date`, counting
+/// the days up to that date while the month is open.
+pub fn hour_bank_balances(
+    conn: &Connection,
+    selected_date: NaiveDate,
+) -> Result<Vec<(db::Worker, MonthBalance)>, ReportError> {
+    let range = ReportRange {
+        start: ReportRange::month(selected_date).start,
+        end: selected_date,
+    };
+    let mut balances = Vec::new();
+    for worker in db::get_workers_for_range(conn, range.start, range.end)? {
+        let rows = build_rows(conn, worker.id, range)?;
+        balances.push((worker, rows.hour_bank.unwrap_or_default()));
+    }
+    Ok(balances)
+}
+
+#[derive(Clone)]
 struct WorkerRows {
     day_groups: Vec<DayGroup>,
     total_minutes: i64,
+    /// Minutes worked on public holidays, also included in `total_minutes`.
+    holiday_minutes: i64,
+    /// Minutes credited by paid absences; not part of `total_minutes`.
+    absence_minutes: i64,
+    /// Break minutes; the unpaid ones are already left out of `total_minutes`.
+    break_minutes: i64,
+    unpaid_break_minutes: i64,
+    ordinary_minutes: i64,
+    overtime_minutes: i64,
+    overtime_weeks: Vec<WeekOvertime>,
+    punctuality: PunctualitySummary,
+    /// Banco de horas: the month's balance carried on from earlier months.
+    /// Only reported when the range is (the start of) a calendar month.
+    hour_bank: Option<MonthBalance>,
+    hour_bank_adjustments: Vec<Adjustment>,
     has_open_sessions: bool,
 }
 
 fn build_rows(
     conn: &Connection,
     worker_id: i64,
-    month_key: &str,
-    selected_date: NaiveDate,
+    range: ReportRange,
 ) -> Result<WorkerRows, ReportError> {
-    let entries = db::get_monthly_timesheet_entries(conn, worker_id, month_key)?;
+    let entries = db::get_timesheet_entries_overlapping(conn, worker_id, range.start, range.end)?;
+    let timekeeper = Timekeeper::load(conn, worker_id, &entries)?;
+    let calendar = &timekeeper.calendar;
     let mut grouped: BTreeMap<NaiveDate, Vec<ReportRow>> = BTreeMap::new();
     let mut total_minutes = 0;
     let mut has_open_sessions = false;
 
-    for entry in entries {
-        let start_local = entry.clock_in.with_timezone(&Santiago).date_naive();
-        if start_local <= selected_date {
-            let row = to_report_row(&entry);
+    for entry in &entries {
+        for segment in timekeeper.segments(entry) {
+            // Parts of sessions from the previous or next period belong to
+            // that period's report.
+            if !range.contains(segment.date) {
+                continue;
+            }
+            let row = to_report_row(entry, &segment, &timekeeper);
             if row.duration_minutes >= 0 {
                 total_minutes += row.duration_minutes;
             }
@@ -165,14 +417,18 @@ fn build_rows(
         }
     }
 
-    let month_start = NaiveDate::parse_from_str(&format!("{}-01", month_key), "%Y-%m-%d")
-        .map_err(|_| ReportError::InvalidMonth(month_key.to_string()))?;
-
     let mut day_groups = Vec::new();
-    let mut current_day = month_start;
-    // Include days up to and including the selected date
-    let end_date = selected_date.min(month_start + Duration::days(30)); // Cap at end of month
-    while current_day <= end_date && current_day.month() == month_start.month() {
+    let mut holiday_minutes = 0;
+    let mut absence_minutes = 0;
+    let mut current_day = range.start;
+    let holidays = crate::holidays::holidays_between(conn, range.start, range.end)?;
+    let absences = crate::absences::absences_by_day(conn, worker_id, range.start, range.end)?;
+    let rules = crate::overtime::load_rules(conn)?;
+    let overtime =
+        crate::overtime::worker_overtime(conn, worker_id, range.start, range.end, &rules)?;
+    let punctuality =
+        crate::timekeeping::punctuality_by_day(conn, worker_id, range.start, range.end)?;
+    while current_day <= range.end {
         let mut rows = grouped.remove(&current_day).unwrap_or_default();
         let mut daily_total_minutes = 0;
         if rows.is_empty() {
@@ -180,23 +436,52 @@ fn build_rows(
                 date: current_day,
                 clock_in: "--:--:--".to_string(),
                 clock_out: "--:--:--".to_string(),
+                clock_in_adjusted: None,
+                clock_out_adjusted: None,
                 duration_minutes: 0,
                 duration_label: format_duration(0),
+                break_minutes: 0,
+                unpaid_break_minutes: 0,
                 is_open: false,
+                correction: None,
+                auto_close: None,
             });
         } else {
             rows.sort_by(|a, b| a.clock_in.cmp(&b.clock_in));
             daily_total_minutes = rows.iter().map(|r| r.duration_minutes).sum();
         }
         let weekday_name = weekday_name_es(current_day.weekday()).to_string();
-        let minutes_needed = get_minutes_needed(current_day.weekday());
-        let daily_balance = daily_total_minutes - minutes_needed;
+        let holiday = holidays.get(&current_day).map(|h| h.name.clone());
+        // Nobody owes hours on a holiday; time worked on one is extra.
+        let minutes_needed = if holiday.is_some() {
+            holiday_minutes += daily_total_minutes;
+            0
+        } else {
+            calendar.minutes_needed(current_day)
+        };
+        let absence = absences.get(&current_day);
+        // A paid absence counts as having worked the scheduled day.
+        let credited = match absence {
+            Some(absence) if absence.paid => minutes_needed,
+            _ => 0,
+        };
+        absence_minutes += credited;
+        let daily_balance = daily_total_minutes + credited - minutes_needed;
+        let split = overtime.get(&current_day).copied().unwrap_or_default();
+        let punctual = punctuality.get(&current_day).copied().unwrap_or_default();
         day_groups.push(DayGroup {
             date: current_day,
             weekday_name,
             rows,
             is_weekend: current_day.weekday() == Weekday::Sun,
+            holiday,
+            absence: absence.map(|a| a.describe()),
+            absence_minutes: credited,
             daily_total_minutes,
+            ordinary_minutes: split.ordinary,
+            overtime_minutes: split.overtime,
+            late_minutes: punctual.late_minutes,
+            early_leave_minutes: punctual.early_leave_minutes,
             minutes_needed,
             daily_balance,
         });
@@ -204,142 +489,246 @@ fn build_rows(
         current_day += Duration::days(1);
     }
 
+    let (hour_bank, hour_bank_adjustments) = match range.hour_bank_month() {
+        Some(month_key) => {
+            let month_delta = day_groups.iter().map(|g| g.daily_balance).sum();
+            (
+                Some(crate::hour_bank::month_balance(
+                    conn,
+                    worker_id,
+                    &month_key,
+                    month_delta,
+                )?),
+                crate::hour_bank::month_adjustments(conn, worker_id, &month_key)?,
+            )
+        }
+        None => (None, Vec::new()),
+    };
+    let all_rows = || day_groups.iter().flat_map(|g| g.rows.iter());
     Ok(WorkerRows {
+        break_minutes: all_rows().map(|r| r.break_minutes).sum(),
+        unpaid_break_minutes: all_rows().map(|r| r.unpaid_break_minutes).sum(),
+        ordinary_minutes: day_groups.iter().map(|g| g.ordinary_minutes).sum(),
+        overtime_minutes: day_groups.iter().map(|g| g.overtime_minutes).sum(),
+        overtime_weeks: crate::overtime::weekly_totals(&overtime, &rules),
+        punctuality: PunctualitySummary::from_days(punctuality.values()),
+        hour_bank,
+        hour_bank_adjustments,
         day_groups,
         total_minutes,
+        holiday_minutes,
+        absence_minutes,
         has_open_sessions,
     })
 }
 
-fn to_report_row(entry: &TimesheetEntry) -> ReportRow {
+/// Shows the punches as scanned; `duration_minutes` is the paid time after
+/// rounding, grace windows and unpaid breaks. A part of a session split at
+/// midnight shows 00:00:00 or 24:00:00 where it was cut.
+fn to_report_row(
+    entry: &TimesheetEntry,
+    segment: &DaySegment,
+    timekeeper: &Timekeeper,
+) -> ReportRow {
     let start_utc = entry.clock_in;
-    let end_utc = entry.clock_out.unwrap_or_else(Utc::now);
-    let mut duration_minutes = (end_utc - start_utc).num_minutes();
-    if duration_minutes < 0 {
-        duration_minutes = 0;
-    }
-    let start_local = start_utc.with_timezone(&Santiago);
-    let end_local = end_utc.with_timezone(&Santiago);
+    let end_utc = entry.clock_out.unwrap_or(timekeeper.now);
+    let duration_minutes = segment.minutes;
+    let start_local = start_utc.with_timezone(&timezone::current());
+    let end_local = end_utc.with_timezone(&timezone::current());
     let is_open = entry.clock_out.is_none();
+    let (effective_in, effective_out) = timekeeper.effective_times(entry);
+    // Only mention the adjusted time when it moved the punch to another minute.
+    let adjusted = |raw: DateTime<Utc>, effective: DateTime<Utc>| {
+        let effective = effective
+            .with_timezone(&timezone::current())
+            .format("%H:%M")
+            .to_string();
+        (raw.with_timezone(&timezone::current())
+            .format("%H:%M")
+            .to_string()
+            != effective)
+            .then_some(effective)
+    };
 
     ReportRow {
-        date: start_local.date_naive(),
-        clock_in: start_local.format("%H:%M:%S").to_string(),
-        clock_out: if is_open {
+        date: segment.date,
+        clock_in: if segment.starts_session {
+            start_local.format("%H:%M:%S").to_string()
+        } else {
+            "00:00:00".to_string()
+        },
+        clock_out: if !segment.ends_session {
+            "24:00:00".to_string()
+        } else if is_open {
             format!("{}*", end_local.format("%H:%M:%S"))
         } else {
             end_local.format("%H:%M:%S").to_string()
         },
+        clock_in_adjusted: if segment.starts_session {
+            adjusted(start_utc, effective_in)
+        } else {
+            None
+        },
+        clock_out_adjusted: if is_open || !segment.ends_session {
+            None
+        } else {
+            adjusted(end_utc, effective_out)
+        },
         duration_minutes,
         duration_label: format_duration(duration_minutes),
+        break_minutes: segment.breaks.total,
+        unpaid_break_minutes: segment.breaks.unpaid,
         is_open,
+        correction: entry
+            .correction_reason
+            .as_deref()
+            .map(|code| ReasonCode::from_code(code).unwrap_or(ReasonCode::Otro)),
+        auto_close: entry
+            .auto_close
+            .as_deref()
+            .and_then(AutoCloseMark::from_code),
     }
 }
 
 fn write_html_report(
     path: &Path,
-    worker_name: &str,
-    month: &str,
-    day_groups: &[DayGroup],
-    total_minutes: i64,
-    has_open_sessions: bool,
+    templates: &ReportTemplates,
+    period: &str,
+    worker: &WorkerReportData,
 ) -> Result<(), ReportError> {
-    let mut html = String::new();
-    writeln!(
-        html,
-        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Timesheet {name} {month}</title>\
-<style>body{{font-family:Arial,sans-serif;padding:20px}}h1{{margin-bottom:0}}table{{border-collapse:collapse;width:100%;margin-top:16px}}th,td{{border:1px solid #555;padding:6px;text-align:center}}th{{background-color:#eee}}table tbody tr.day-even td{{background-color:#f7f7f7}}table tbody tr.day-odd td{{background-color:#ffffff}}table tbody tr.weekend td{{color:#e33d3d}}table tbody tr td:first-child{{font-weight:600}}</style></head><body>",
-        name = worker_name,
-        month = month
-    )
-    .expect("write to string");
-    writeln!(
-        html,
-        "<h1>{}</h1><h2>Month: {}</h2>",
-        escape_html(worker_name),
-        month
-    )
-    .expect("write to string");
-    html.push_str("<table><thead><tr><th>Fecha</th><th>Entrada</th><th>Salida</th><th>Horas (Minutos)</th><th>Total Horas Dia (Minutos)</th><th>Horas Extras</th></tr></thead><tbody>");
+    let html = templates.render_worker(&WorkerReportContext {
+        period: period.to_string(),
+        worker: worker_view(worker),
+    })?;
+    let mut file = File::create(path)?;
+    file.write_all(html.as_bytes())?;
+    Ok(())
+}
 
-    if day_groups.is_empty() {
-        html.push_str("<tr><td colspan=\"6\">No recorded sessions for this month.</td></tr>");
-    } else {
-        for (index, group) in day_groups.iter().enumerate() {
-            let base_class = if index % 2 == 0 {
-                "day-even"
-            } else {
-                "day-odd"
-            };
-            let class = if group.is_weekend {
-                format!("{} weekend", base_class)
+/// The template data for one worker; see [`WorkerReportView`].
+fn worker_view(worker: &WorkerReportData) -> WorkerReportView {
+    let rows = &worker.rows;
+    let days = rows
+        .day_groups
+        .iter()
+        .map(|group| DayView {
+            date: group.date.format("%Y-%m-%d").to_string(),
+            date_label: group.date.format("%m/%d").to_string(),
+            weekday: group.weekday_name.clone(),
+            kind: if group.holiday.is_some() {
+                "holiday"
+            } else if group.absence.is_some() {
+                "absence"
+            } else if group.is_weekend {
+                "sunday"
             } else if group.date.weekday() == Weekday::Sat {
-                format!("{} saturday", base_class)
+                "saturday"
             } else {
-                base_class.to_string()
-            };
-            let rowspan = group.rows.len();
-            for (row_idx, row) in group.rows.iter().enumerate() {
-                html.push_str(&format!("<tr class=\"{}\">", class));
-                if row_idx == 0 {
-                    writeln!(
-                    html,
-                    "<td rowspan=\"{rowspan}\"><strong>{date}</strong><br/><small>{weekday}</small></td>",
-                    date = group.date.format("%m/%d"),
-                    weekday = group.weekday_name,
-                )
-                .expect("write to string");
-                }
-                if row_idx == 0 {
-                    writeln!(
-                    html,
-                    "<td>{}</td><td>{}</td><td>{}</td><td rowspan=\"{rowspan}\">{} ({})</td><td rowspan=\"{rowspan}\">{}</td></tr>",
-                    row.clock_in, row.clock_out, row.duration_label, format_duration(group.daily_total_minutes), group.daily_total_minutes, group.daily_balance
-                )
-                .expect("write to string");
-                } else {
-                    writeln!(
-                        html,
-                        "<td>{}</td><td>{}</td><td>{}</td></tr>",
-                        row.clock_in, row.clock_out, row.duration_label
-                    )
-                    .expect("write to string");
-                }
-            }
-        }
-    }
-    html.push_str("</tbody></table>");
-
-    writeln!(
-        html,
-        "<p><strong>Total:</strong> {} ({} minutes)</p>",
-        format_duration(total_minutes),
-        total_minutes
-    )
-    .expect("write to string");
+                "workday"
+            },
+            holiday: group.holiday.clone(),
+            absence: group.absence.clone(),
+            sessions: group.rows.iter().map(session_view).collect(),
+            total_minutes: group.daily_total_minutes,
+            total_label: format_duration(group.daily_total_minutes),
+            minutes_needed: group.minutes_needed,
+            absence_minutes: group.absence_minutes,
+            balance_minutes: group.daily_balance,
+            ordinary_minutes: group.ordinary_minutes,
+            overtime_minutes: group.overtime_minutes,
+            overtime_label: format_duration(group.overtime_minutes),
+            late_minutes: group.late_minutes,
+            early_leave_minutes: group.early_leave_minutes,
+        })
+        .collect();
+    let minutes = |minutes: i64| (minutes, format_duration(minutes));
+    let (total_minutes, total_label) = minutes(rows.total_minutes);
+    let (ordinary_minutes, ordinary_label) = minutes(rows.ordinary_minutes);
+    let (overtime_minutes, overtime_label) = minutes(rows.overtime_minutes);
+    let (holiday_minutes, holiday_label) = minutes(rows.holiday_minutes);
+    let (absence_minutes, absence_label) = minutes(rows.absence_minutes);
+    let (break_minutes, break_label) = minutes(rows.break_minutes);
+    let (unpaid_break_minutes, unpaid_break_label) = minutes(rows.unpaid_break_minutes);
 
-    if has_open_sessions {
-        html.push_str("<p>* Entries marked with an asterisk do not have a recorded clock out; the current time was used to compute the duration.</p>");
+    WorkerReportView {
+        name: worker.worker_name.clone(),
+        days,
+        totals: TotalsView {
+            total_minutes,
+            total_label,
+            ordinary_minutes,
+            ordinary_label,
+            overtime_minutes,
+            overtime_label,
+            holiday_minutes,
+            holiday_label,
+            absence_minutes,
+            absence_label,
+            break_minutes,
+            break_label,
+            unpaid_break_minutes,
+            unpaid_break_label,
+            late_days: rows.punctuality.late_days,
+            late_minutes: rows.punctuality.late_minutes,
+            early_leave_days: rows.punctuality.early_leave_days,
+            early_leave_minutes: rows.punctuality.early_leave_minutes,
+        },
+        hour_bank: rows.hour_bank.as_ref().map(|bank| HourBankView {
+            closed: bank.closed,
+            opening_minutes: bank.opening,
+            opening: format_balance(bank.opening),
+            delta_minutes: bank.delta,
+            delta: format_balance(bank.delta),
+            adjustments_minutes: bank.adjustments,
+            adjustments: format_balance(bank.adjustments),
+            closing_minutes: bank.closing,
+            closing: format_balance(bank.closing),
+            items: rows
+                .hour_bank_adjustments
+                .iter()
+                .map(|adjustment| AdjustmentView {
+                    date_label: adjustment.date.format("%m/%d").to_string(),
+                    minutes: adjustment.minutes,
+                    amount: format_balance(adjustment.minutes),
+                    reason: adjustment.reason.clone(),
+                })
+                .collect(),
+        }),
+        has_open_sessions: rows.has_open_sessions,
+        has_corrections: has_corrections(&rows.day_groups),
+        has_auto_closed: has_auto_closed(&rows.day_groups),
     }
+}
 
-    html.push_str("</body></html>");
-
-    let mut file = File::create(path)?;
-    file.write_all(html.as_bytes())?;
-    Ok(())
+fn session_view(row: &ReportRow) -> SessionView {
+    SessionView {
+        clock_in: row.clock_in.clone(),
+        clock_out: row.clock_out.clone(),
+        clock_in_adjusted: row.clock_in_adjusted.clone(),
+        clock_out_adjusted: row.clock_out_adjusted.clone(),
+        duration_minutes: row.duration_minutes,
+        duration_label: row.duration_label.clone(),
+        break_minutes: row.break_minutes,
+        break_label: format_duration(row.break_minutes),
+        unpaid_break_minutes: row.unpaid_break_minutes,
+        open: row.is_open,
+        correction: row.correction.map(|reason| reason.label().to_string()),
+        auto_close: row.auto_close.map(|mark| mark.label().to_string()),
+    }
 }
 
 fn write_csv_report(
     path: &Path,
     worker_name: &str,
-    month: &str,
-    day_groups: &[DayGroup],
-    total_minutes: i64,
+    period: &str,
+    rows: &WorkerRows,
 ) -> Result<(), ReportError> {
+    let day_groups = &rows.day_groups;
+    let total_minutes = rows.total_minutes;
     let mut contents = String::new();
-    writeln!(contents, "Worker,{}", worker_name).expect("write to string");
-    writeln!(contents, "Month,{}", month).expect("write to string");
-    contents.push_str("Fecha,Dia,Entrada,Salida,Minutos,HH:MM,Daily Total Minutes,Daily Total HH:MM,Horas Extras\n");
+    writeln!(contents, "Worker,{}", csv_field(worker_name)).expect("write to string");
+    writeln!(contents, "Period,{}", period).expect("write to string");
+    contents.push_str("Fecha,Dia,Entrada,Salida,Entrada Ajustada,Salida Ajustada,Minutos,HH:MM,Minutos Colación,Daily Total Minutes,Daily Total HH:MM,Saldo,Minutos Ordinarios,Minutos Extras,Minutos Atraso,Minutos Salida Anticipada,Corregido,Cierre automático,Feriado,Ausencia\n");
     if day_groups.is_empty() {
         contents.push_str("-, -, -, -, 0, 00:00\n");
     } else {
@@ -370,18 +759,55 @@ fn write_csv_report(
                 } else {
                     "".to_string()
                 };
+                let (ordinary_text, overtime_text) = if idx == 0 {
+                    (
+                        group.ordinary_minutes.to_string(),
+                        group.overtime_minutes.to_string(),
+                    )
+                } else {
+                    ("".to_string(), "".to_string())
+                };
+                let (late_text, early_leave_text) = if idx == 0 {
+                    (
+                        group.late_minutes.to_string(),
+                        group.early_leave_minutes.to_string(),
+                    )
+                } else {
+                    ("".to_string(), "".to_string())
+                };
+                let absence_text = if idx == 0 {
+                    csv_field(group.absence.as_deref().unwrap_or(""))
+                } else {
+                    "".to_string()
+                };
+                let holiday_text = if idx == 0 {
+                    csv_field(group.holiday.as_deref().unwrap_or(""))
+                } else {
+                    "".to_string()
+                };
                 writeln!(
                     contents,
-                    "{},{},{},{},{},{},{},{},{}",
+                    "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
                     date_text,
                     day_text,
                     row.clock_in,
                     row.clock_out,
+                    row.clock_in_adjusted.as_deref().unwrap_or(""),
+                    row.clock_out_adjusted.as_deref().unwrap_or(""),
                     row.duration_minutes,
                     row.duration_label,
+                    row.break_minutes,
                     daily_total_minutes_text,
                     daily_total_label_text,
-                    daily_balance_text
+                    daily_balance_text,
+                    ordinary_text,
+                    overtime_text,
+                    late_text,
+                    early_leave_text,
+                    row.correction.map(|r| r.label()).unwrap_or(""),
+                    row.auto_close.map(|m| m.label()).unwrap_or(""),
+                    holiday_text,
+                    absence_text
                 )
                 .expect("write to string");
             }
@@ -394,6 +820,60 @@ fn write_csv_report(
         format_duration(total_minutes)
     )
     .expect("write to string");
+    writeln!(
+        contents,
+        "Colación,,,{},{}",
+        rows.break_minutes,
+        format_duration(rows.break_minutes)
+    )
+    .expect("write to string");
+    writeln!(
+        contents,
+        "Ordinarias,,,{},{}",
+        rows.ordinary_minutes,
+        format_duration(rows.ordinary_minutes)
+    )
+    .expect("write to string");
+    writeln!(
+        contents,
+        "Horas extras,,,{},{}",
+        rows.overtime_minutes,
+        format_duration(rows.overtime_minutes)
+    )
+    .expect("write to string");
+    if let Some(bank) = &rows.hour_bank {
+        write_hour_bank_csv(&mut contents, bank, &rows.hour_bank_adjustments);
+    }
+    writeln!(
+        contents,
+        "Atrasos,,,{},{},{} días",
+        rows.punctuality.late_minutes,
+        format_duration(rows.punctuality.late_minutes),
+        rows.punctuality.late_days
+    )
+    .expect("write to string");
+    writeln!(
+        contents,
+        "Salidas anticipadas,,,{},{},{} días",
+        rows.punctuality.early_leave_minutes,
+        format_duration(rows.punctuality.early_leave_minutes),
+        rows.punctuality.early_leave_days
+    )
+    .expect("write to string");
+    writeln!(
+        contents,
+        "Feriados,,,{},{}",
+        rows.holiday_minutes,
+        format_duration(rows.holiday_minutes)
+    )
+    .expect("write to string");
+    writeln!(
+        contents,
+        "Ausencias con goce,,,{},{}",
+        rows.absence_minutes,
+        format_duration(rows.absence_minutes)
+    )
+    .expect("write to string");
 
     let mut file = File::create(path)?;
     file.write_all(contents.as_bytes())?;
@@ -402,103 +882,920 @@ fn write_csv_report(
 
 fn write_merged_html_report(
     path: &Path,
-    month: &str,
+    templates: &ReportTemplates,
+    period: &str,
     worker_data: &[WorkerReportData],
 ) -> Result<(), ReportError> {
-    let mut html = String::new();
-    writeln!(
-        html,
-        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>All Workers Timesheet {month}</title>\
-<style>@media print {{ .page-break {{ page-break-before: always; }} }} body{{font-family:Arial,sans-serif;padding:20px}}h1{{margin-bottom:0}}h2{{margin-top:40px;margin-bottom:10px;padding-top:20px;border-top:2px solid #333}}table{{border-collapse:collapse;width:100%;margin-top:16px}}th,td{{border:1px solid #555;padding:6px;text-align:center}}th{{background-color:#eee}}table tbody tr.day-even td{{background-color:#f7f7f7}}table tbody tr.day-odd td{{background-color:#ffffff}}table tbody tr.weekend td{{color:#e33d3d}}table tbody tr td:first-child{{font-weight:600}}</style></head><body>",
-        month = month
-    )
-    .expect("write to string");
-    writeln!(
-        html,
-        "<h1>All Workers Timesheet</h1><h2>Month: {}</h2>",
-        month
-    )
-    .expect("write to string");
+    let html = templates.render_merged(&MergedReportView {
+        period: period.to_string(),
+        workers: worker_data.iter().map(worker_view).collect(),
+    })?;
+    let mut file = File::create(path)?;
+    file.write_all(html.as_bytes())?;
+    Ok(())
+}
 
-    for (worker_idx, worker) in worker_data.iter().enumerate() {
-        if worker_idx > 0 {
-            html.push_str("<div class=\"page-break\"></div>");
-        }
+/// Columns of the PDF timesheet: title, width in points and alignment.
+const PDF_COLUMNS: [(&str, f32, Align); 10] = [
+    ("Fecha", 56.0, Align::Left),
+    ("Entrada", 60.0, Align::Center),
+    ("Salida", 60.0, Align::Center),
+    ("Horas", 50.0, Align::Center),
+    ("Total día", 46.0, Align::Center),
+    ("Saldo", 38.0, Align::Right),
+    ("Extras", 40.0, Align::Center),
+    ("Atraso", 34.0, Align::Right),
+    ("Sal. ant.", 40.0, Align::Right),
+    ("Observaciones", 108.0, Align::Left),
+];
 
-        writeln!(html, "<h2>{}</h2>", escape_html(&worker.worker_name)).expect("write to string");
+const PDF_MARGIN: f32 = 40.0;
+const PDF_ROW_HEIGHT: f32 = 13.0;
+/// Room kept at the bottom of every page for the page number.
+const PDF_FOOTER_HEIGHT: f32 = 40.0;
+const PDF_TITLE: TextStyle = TextStyle::new(Font::Bold, 14.0);
+const PDF_TEXT: TextStyle = TextStyle::new(Font::Regular, 9.0);
+const PDF_BOLD: TextStyle = TextStyle::new(Font::Bold, 9.0);
+const PDF_CELL: TextStyle = TextStyle::new(Font::Regular, 8.0);
+const PDF_HEADER_CELL: TextStyle = TextStyle::new(Font::Bold, 8.0);
+const PDF_SMALL: TextStyle = TextStyle::new(Font::Regular, 7.5);
 
-        html.push_str("<table><thead><tr><th>Fecha</th><th>Entrada</th><th>Salida</th><th>Horas (Minutos)</th><th>Total Horas Dia (Minutos)</th><th>Horas Extras</th></tr></thead><tbody>");
+fn write_pdf_report(
+    path: &Path,
+    worker_name: &str,
+    period: &str,
+    rows: &WorkerRows,
+) -> Result<(), ReportError> {
+    let mut doc = PdfDocument::new(&format!("{} {}", worker_name, period), pdf::LETTER);
+    draw_worker_pdf(&mut doc, worker_name, period, rows);
+    number_pdf_pages(&mut doc);
+    doc.save(path)?;
+    Ok(())
+}
 
-        if worker.day_groups.is_empty() {
-            html.push_str("<tr><td colspan=\"6\">No recorded sessions for this month.</td></tr>");
+/// Every worker's timesheet in one file, each starting on a new page with
+/// its own signature lines.
+fn write_merged_pdf_report(
+    path: &Path,
+    period: &str,
+    workers: &[WorkerReportData],
+) -> Result<(), ReportError> {
+    let mut doc = PdfDocument::new(&format!("All Workers Timesheet {}", period), pdf::LETTER);
+    for worker in workers {
+        draw_worker_pdf(&mut doc, &worker.worker_name, period, &worker.rows);
+    }
+    if workers.is_empty() {
+        doc.page().text(
+            PDF_MARGIN,
+            PDF_MARGIN + 14.0,
+            &format!("Sin trabajadores en el periodo {}", period),
+            PDF_TITLE,
+        );
+    }
+    number_pdf_pages(&mut doc);
+    doc.save(path)?;
+    Ok(())
+}
+
+/// Lays out one worker's timesheet from a new page: the day table, the
+/// totals and the signature lines, repeating the heading on each page.
+fn draw_worker_pdf(doc: &mut PdfDocument, worker_name: &str, period: &str, rows: &WorkerRows) {
+    let bottom = doc.height() - PDF_MARGIN - PDF_FOOTER_HEIGHT;
+    let mut y = pdf_page_heading(doc, worker_name, period);
+    y = pdf_table_header(doc.page(), y);
+
+    if rows.day_groups.is_empty() {
+        doc.page().text(
+            PDF_MARGIN + 4.0,
+            y + 9.0,
+            "Sin sesiones registradas en el periodo.",
+            PDF_CELL,
+        );
+        y += PDF_ROW_HEIGHT;
+    }
+    for (index, group) in rows.day_groups.iter().enumerate() {
+        // Keep a day on one page unless it alone is longer than a page.
+        let height = PDF_ROW_HEIGHT * group.rows.len() as f32;
+        if y + height > bottom {
+            y = pdf_page_heading(doc, worker_name, period);
+            y = pdf_table_header(doc.page(), y);
+        }
+        let color = if group.holiday.is_some() || group.is_weekend {
+            Color::RED
+        } else if group.absence.is_some() {
+            Color::BLUE
         } else {
-            for (index, group) in worker.day_groups.iter().enumerate() {
-                let base_class = if index % 2 == 0 {
-                    "day-even"
+            Color::BLACK
+        };
+        for (row_index, row) in group.rows.iter().enumerate() {
+            if y + PDF_ROW_HEIGHT > bottom {
+                y = pdf_page_heading(doc, worker_name, period);
+                y = pdf_table_header(doc.page(), y);
+            }
+            let page = doc.page();
+            if index % 2 == 1 {
+                page.fill_rect(
+                    PDF_MARGIN,
+                    y,
+                    pdf_table_width(),
+                    PDF_ROW_HEIGHT,
+                    Color::LIGHT_GREY,
+                );
+            }
+            let mut cells: Vec<String> = vec![String::new(); PDF_COLUMNS.len()];
+            if row_index == 0 {
+                cells[0] = format!(
+                    "{} {}",
+                    group.date.format("%d/%m"),
+                    group.weekday_name.chars().take(3).collect::<String>()
+                );
+                cells[4] = format_duration(group.daily_total_minutes);
+                cells[5] = group.daily_balance.to_string();
+                cells[6] = if group.overtime_minutes > 0 {
+                    format_duration(group.overtime_minutes)
                 } else {
-                    "day-odd"
+                    String::new()
                 };
-                let class = if group.is_weekend {
-                    format!("{} weekend", base_class)
-                } else if group.date.weekday() == Weekday::Sat {
-                    format!("{} saturday", base_class)
+                cells[7] = minutes_cell(group.late_minutes);
+                cells[8] = minutes_cell(group.early_leave_minutes);
+            }
+            cells[1] = punch_cell_pdf(&row.clock_in, &row.clock_in_adjusted);
+            cells[2] = punch_cell_pdf(&row.clock_out, &row.clock_out_adjusted);
+            cells[3] = row.duration_label.clone();
+            if row.correction.is_some() {
+                cells[3].push_str(" †");
+            }
+            if row.auto_close.is_some() {
+                cells[3].push_str(" ‡");
+            }
+            cells[9] = observations_pdf(group, row, row_index == 0);
+            pdf_row(page, y, &cells, PDF_CELL.color(color));
+            y += PDF_ROW_HEIGHT;
+        }
+    }
+    doc.page()
+        .line(PDF_MARGIN, y, PDF_MARGIN + pdf_table_width(), y, 0.5);
+    y += 16.0;
+
+    let mut summary = vec![
+        (
+            "Total",
+            format!(
+                "{} ({} minutos)",
+                format_duration(rows.total_minutes),
+                rows.total_minutes
+            ),
+        ),
+        ("Horas ordinarias", format_duration(rows.ordinary_minutes)),
+        ("Horas extras", format_duration(rows.overtime_minutes)),
+    ];
+    if rows.holiday_minutes > 0 {
+        summary.push(("Horas en feriados", format_duration(rows.holiday_minutes)));
+    }
+    if rows.absence_minutes > 0 {
+        summary.push(("Ausencias con goce", format_duration(rows.absence_minutes)));
+    }
+    if rows.break_minutes > 0 {
+        summary.push((
+            "Colación",
+            format!(
+                "{}; {} sin goce descontadas del total",
+                format_duration(rows.break_minutes),
+                format_duration(rows.unpaid_break_minutes)
+            ),
+        ));
+    }
+    summary.push((
+        "Atrasos",
+        format!(
+            "{} días ({} minutos)",
+            rows.punctuality.late_days, rows.punctuality.late_minutes
+        ),
+    ));
+    summary.push((
+        "Salidas anticipadas",
+        format!(
+            "{} días ({} minutos)",
+            rows.punctuality.early_leave_days, rows.punctuality.early_leave_minutes
+        ),
+    ));
+    if let Some(bank) = &rows.hour_bank {
+        summary.push((
+            if bank.closed {
+                "Banco de horas"
+            } else {
+                "Banco de horas (provisional)"
+            },
+            format!(
+                "inicial {} · mes {} · ajustes {} · final {}",
+                format_balance(bank.opening),
+                format_balance(bank.delta),
+                format_balance(bank.adjustments),
+                format_balance(bank.closing)
+            ),
+        ));
+    }
+    let mut notes = Vec::new();
+    if rows.has_open_sessions {
+        notes.push("* Sin salida registrada; se usó la hora actual para calcular la duración.");
+    }
+    if has_corrections(&rows.day_groups) {
+        notes.push("† Marca corregida manualmente; el motivo aparece en observaciones.");
+    }
+    if has_auto_closed(&rows.day_groups) {
+        notes.push("‡ Cerrada automáticamente; las que requieren revisión cuentan cero horas.");
+    }
+
+    let needed = 14.0 * summary.len() as f32 + 10.0 * notes.len() as f32 + 110.0;
+    if y + needed > bottom {
+        y = pdf_page_heading(doc, worker_name, period);
+    }
+    let page = doc.page();
+    for (label, value) in &summary {
+        page.text(PDF_MARGIN, y, &format!("{}:", label), PDF_BOLD);
+        page.text(PDF_MARGIN + 150.0, y, value, PDF_TEXT);
+        y += 14.0;
+    }
+    for note in &notes {
+        page.text(PDF_MARGIN, y, note, PDF_SMALL.color(Color::GREY));
+        y += 10.0;
+    }
+
+    y += 12.0;
+    page.text(
+        PDF_MARGIN,
+        y,
+        "Declaro que las marcas y totales de este registro son correctos.",
+        PDF_TEXT,
+    );
+    y += 60.0;
+    let line_width = 200.0;
+    let right = pdf_table_width() + PDF_MARGIN - line_width;
+    for (x, caption, name) in [
+        (PDF_MARGIN, "Firma trabajador", worker_name),
+        (right, "Firma empleador", ""),
+    ] {
+        page.line(x, y, x + line_width, y, 0.75);
+        page.text(
+            x + line_width / 2.0,
+            y + 12.0,
+            caption,
+            PDF_TEXT.align(Align::Center),
+        );
+        page.text(
+            x + line_width / 2.0,
+            y + 24.0,
+            name,
+            PDF_SMALL.align(Align::Center),
+        );
+    }
+}
+
+fn pdf_table_width() -> f32 {
+    PDF_COLUMNS.iter().map(|(_, width, _)| width).sum()
+}
+
+/// Starts a page with the worker and period and returns where the content
+/// starts.
+fn pdf_page_heading(doc: &mut PdfDocument, worker_name: &str, period: &str) -> f32 {
+    let right = PDF_MARGIN + pdf_table_width();
+    let page = doc.add_page();
+    page.text(PDF_MARGIN, PDF_MARGIN + 14.0, worker_name, PDF_TITLE);
+    page.text(
+        right,
+        PDF_MARGIN + 14.0,
+        "Registro de asistencia",
+        PDF_BOLD.align(Align::Right),
+    );
+    page.text(
+        PDF_MARGIN,
+        PDF_MARGIN + 30.0,
+        &format!("Periodo: {}", period),
+        PDF_TEXT,
+    );
+    PDF_MARGIN + 44.0
+}
+
+fn pdf_table_header(page: &mut Page, y: f32) -> f32 {
+    let titles: Vec<String> = PDF_COLUMNS
+        .iter()
+        .map(|(title, _, _)| title.to_string())
+        .collect();
+    page.fill_rect(
+        PDF_MARGIN,
+        y,
+        pdf_table_width(),
+        PDF_ROW_HEIGHT + 2.0,
+        Color::LIGHT_GREY,
+    );
+    pdf_row(page, y + 1.0, &titles, PDF_HEADER_CELL);
+    page.line(
+        PDF_MARGIN,
+        y + PDF_ROW_HEIGHT + 2.0,
+        PDF_MARGIN + pdf_table_width(),
+        y + PDF_ROW_HEIGHT + 2.0,
+        0.5,
+    );
+    y + PDF_ROW_HEIGHT + 2.0
+}
+
+/// One line of cells, each cut to its column.
+fn pdf_row(page: &mut Page, y: f32, cells: &[String], style: TextStyle) {
+    let mut x = PDF_MARGIN;
+    for ((_, width, align), cell) in PDF_COLUMNS.iter().zip(cells) {
+        let text = pdf::fit_text(cell, width - 4.0, style.font, style.size);
+        let anchor = match align {
+            Align::Left => x + 2.0,
+            Align::Center => x + width / 2.0,
+            Align::Right => x + width - 2.0,
+        };
+        page.text(anchor, y + 9.0, &text, style.align(*align));
+        x += width;
+    }
+}
+
+/// "08:58:12 (09:00)" when rounding or a grace window moved the punch.
+fn punch_cell_pdf(raw: &str, adjusted: &Option<String>) -> String {
+    match adjusted {
+        Some(adjusted) => format!("{} ({})", &raw[..raw.len().min(5)], adjusted),
+        None => raw.to_string(),
+    }
+}
+
+fn observations_pdf(group: &DayGroup, row: &ReportRow, first_row: bool) -> String {
+    let mut notes = Vec::new();
+    if first_row {
+        if let Some(holiday) = &group.holiday {
+            notes.push(format!("Feriado: {}", holiday));
+        }
+        if let Some(absence) = &group.absence {
+            notes.push(absence.clone());
+        }
+    }
+    if row.break_minutes > 0 {
+        notes.push(format!(
+            "Colación {}{}",
+            format_duration(row.break_minutes),
+            if row.unpaid_break_minutes == 0 {
+                " (pagada)"
+            } else {
+                ""
+            }
+        ));
+    }
+    if let Some(reason) = row.correction {
+        notes.push(reason.label().to_string());
+    }
+    if let Some(mark) = row.auto_close {
+        notes.push(mark.label().to_string());
+    }
+    notes.join("; ")
+}
+
+/// "Página n de m" and the generation time at the foot of every page.
+fn number_pdf_pages(doc: &mut PdfDocument) {
+    let total = doc.page_count();
+    let (width, height) = (doc.width(), doc.height());
+    let generated = format!(
+        "Generado el {}",
+        timezone::to_local(Utc::now()).format("%Y-%m-%d %H:%M")
+    );
+    for (number, page) in doc.pages_mut() {
+        let y = height - PDF_MARGIN + 10.0;
+        page.text(PDF_MARGIN, y, &generated, PDF_SMALL.color(Color::GREY));
+        page.text(
+            width - PDF_MARGIN,
+            y,
+            &format!("Página {} de {}", number, total),
+            PDF_SMALL.align(Align::Right),
+        );
+    }
+}
+
+/// Columns of a worker sheet: title and width in characters.
+const XLSX_COLUMNS: [(&str, f64); 13] = [
+    ("Fecha", 11.0),
+    ("Día", 10.0),
+    ("Entrada", 9.0),
+    ("Salida", 9.0),
+    ("Sesiones", 9.0),
+    ("Horas", 9.0),
+    ("Requeridas", 11.0),
+    ("Ausencia con goce", 11.0),
+    ("Saldo", 9.0),
+    ("Horas extras", 11.0),
+    ("Atraso (min)", 11.0),
+    ("Salida anticipada (min)", 13.0),
+    ("Observaciones", 40.0),
+];
+
+/// Worker sheets start with the name, the period and the column titles.
+const XLSX_FIRST_ROW: u32 = 3;
+
+/// The period's workbook for accounting: a "Resumen" sheet whose figures
+/// point at each worker's totals, then one sheet per worker with a row per
+/// day. Hours are decimal numbers and every total is a formula, so the
+/// file stays editable.
+fn write_xlsx_workbook(
+    path: &Path,
+    period: &str,
+    workers: &[WorkerReportData],
+) -> Result<(), ReportError> {
+    let title = Format::new().set_bold().set_font_size(14);
+    let header = Format::new()
+        .set_bold()
+        .set_text_wrap()
+        .set_background_color(XlsxColor::RGB(0xEEEEEE))
+        .set_border_bottom(FormatBorder::Thin);
+    let date = Format::new().set_num_format("dd/mm/yyyy");
+    let time = Format::new().set_num_format("hh:mm");
+    let hours = Format::new().set_num_format("0.00");
+    let balance = Format::new().set_num_format("0.00;[Red]-0.00");
+    let count = Format::new().set_num_format("0");
+    let total_label = Format::new().set_bold().set_border_top(FormatBorder::Thin);
+    let total_hours = hours.clone().set_bold().set_border_top(FormatBorder::Thin);
+    let total_balance = balance
+        .clone()
+        .set_bold()
+        .set_border_top(FormatBorder::Thin);
+    let total_count = count.clone().set_bold().set_border_top(FormatBorder::Thin);
+
+    let mut used_names = vec!["resumen".to_string()];
+    let sheet_names: Vec<String> = workers
+        .iter()
+        .map(|worker| xlsx_sheet_name(&worker.worker_name, &mut used_names))
+        .collect();
+    let mut workbook = Workbook::new();
+
+    let summary = workbook.add_worksheet().set_name("Resumen")?;
+    summary.write_string_with_format(0, 0, "Resumen de horas", &title)?;
+    summary.write_string(1, 0, format!("Periodo: {}", period))?;
+    // Title, worker-sheet column it copies, cell and total formats.
+    let summary_columns: [(&str, u16, &Format, &Format); 6] = [
+        ("Horas", 5, &hours, &total_hours),
+        ("Requeridas", 6, &hours, &total_hours),
+        ("Saldo", 8, &balance, &total_balance),
+        ("Horas extras", 9, &hours, &total_hours),
+        ("Atraso (min)", 10, &count, &total_count),
+        ("Salida anticipada (min)", 11, &count, &total_count),
+    ];
+    summary.write_string_with_format(XLSX_FIRST_ROW - 1, 0, "Trabajador", &header)?;
+    summary.set_column_width(0, 28)?;
+    for (index, (label, _, _, _)) in summary_columns.iter().enumerate() {
+        let col = index as u16 + 1;
+        summary.write_string_with_format(XLSX_FIRST_ROW - 1, col, *label, &header)?;
+        summary.set_column_width(col, 12)?;
+    }
+    for (index, (worker, sheet_name)) in workers.iter().zip(&sheet_names).enumerate() {
+        let row = XLSX_FIRST_ROW + index as u32;
+        let totals = xlsx_totals(&worker.rows);
+        let total_row = XLSX_FIRST_ROW + worker.rows.day_groups.len() as u32;
+        summary.write_string(row, 0, &worker.worker_name)?;
+        for (index, (_, source_col, format, _)) in summary_columns.iter().enumerate() {
+            let formula = Formula::new(format!(
+                "={}!{}",
+                quote_sheet_name(sheet_name),
+                row_col_to_cell(total_row, *source_col)
+            ))
+            .set_result(totals[*source_col as usize - 4].to_string());
+            summary.write_formula_with_format(row, index as u16 + 1, formula, format)?;
+        }
+    }
+    let summary_total_row = XLSX_FIRST_ROW + workers.len() as u32;
+    summary.write_string_with_format(summary_total_row, 0, "Total", &total_label)?;
+    for (index, (_, _, _, format)) in summary_columns.iter().enumerate() {
+        let col = index as u16 + 1;
+        summary.write_formula_with_format(
+            summary_total_row,
+            col,
+            xlsx_sum(XLSX_FIRST_ROW, summary_total_row, col),
+            format,
+        )?;
+    }
+    summary.set_freeze_panes(XLSX_FIRST_ROW, 1)?;
+
+    for (worker, sheet_name) in workers.iter().zip(&sheet_names) {
+        let rows = &worker.rows;
+        let sheet = workbook.add_worksheet().set_name(sheet_name)?;
+        sheet.write_string_with_format(0, 0, &worker.worker_name, &title)?;
+        sheet.write_string(1, 0, format!("Periodo: {}", period))?;
+        for (col, (label, width)) in XLSX_COLUMNS.iter().enumerate() {
+            sheet.write_string_with_format(XLSX_FIRST_ROW - 1, col as u16, *label, &header)?;
+            sheet.set_column_width(col as u16, *width)?;
+        }
+
+        for (index, group) in rows.day_groups.iter().enumerate() {
+            let row = XLSX_FIRST_ROW + index as u32;
+            let excel_date = ExcelDateTime::from_ymd(
+                group.date.year() as u16,
+                group.date.month() as u8,
+                group.date.day() as u8,
+            )?;
+            sheet.write_datetime_with_format(row, 0, &excel_date, &date)?;
+            sheet.write_string(row, 1, &group.weekday_name)?;
+            let worked: Vec<&ReportRow> = group
+                .rows
+                .iter()
+                .filter(|r| r.clock_in != "--:--:--")
+                .collect();
+            if let Some(first) = worked.first().and_then(|r| day_fraction(&r.clock_in)) {
+                sheet.write_number_with_format(row, 2, first, &time)?;
+            }
+            if let Some(last) = worked.last().and_then(|r| day_fraction(&r.clock_out)) {
+                sheet.write_number_with_format(row, 3, last, &time)?;
+            }
+            sheet.write_number_with_format(row, 4, worked.len() as f64, &count)?;
+            sheet.write_number_with_format(
+                row,
+                5,
+                minutes_to_hours(group.daily_total_minutes),
+                &hours,
+            )?;
+            sheet.write_number_with_format(
+                row,
+                6,
+                minutes_to_hours(group.minutes_needed),
+                &hours,
+            )?;
+            sheet.write_number_with_format(
+                row,
+                7,
+                minutes_to_hours(group.absence_minutes),
+                &hours,
+            )?;
+            let cell = |col| row_col_to_cell(row, col);
+            sheet.write_formula_with_format(
+                row,
+                8,
+                Formula::new(format!("={}+{}-{}", cell(5), cell(7), cell(6)))
+                    .set_result(minutes_to_hours(group.daily_balance).to_string()),
+                &balance,
+            )?;
+            sheet.write_number_with_format(
+                row,
+                9,
+                minutes_to_hours(group.overtime_minutes),
+                &hours,
+            )?;
+            sheet.write_number_with_format(row, 10, group.late_minutes as f64, &count)?;
+            sheet.write_number_with_format(row, 11, group.early_leave_minutes as f64, &count)?;
+            let notes: Vec<String> = group
+                .holiday
+                .iter()
+                .map(|name| format!("Feriado: {}", name))
+                .chain(group.absence.iter().cloned())
+                .chain(group.rows.iter().filter_map(|r| {
+                    r.correction
+                        .map(|reason| reason.label().to_string())
+                        .or_else(|| r.auto_close.map(|mark| mark.label().to_string()))
+                }))
+                .collect();
+            if !notes.is_empty() {
+                sheet.write_string(row, 12, notes.join("; "))?;
+            }
+        }
+
+        let total_row = XLSX_FIRST_ROW + rows.day_groups.len() as u32;
+        let totals = xlsx_totals(rows);
+        sheet.write_string_with_format(total_row, 0, "Total", &total_label)?;
+        for col in 4..=11u16 {
+            let format = match col {
+                8 => &total_balance,
+                4 | 10 | 11 => &total_count,
+                _ => &total_hours,
+            };
+            sheet.write_formula_with_format(
+                total_row,
+                col,
+                xlsx_sum(XLSX_FIRST_ROW, total_row, col)
+                    .set_result(totals[col as usize - 4].to_string()),
+                format,
+            )?;
+        }
+
+        if let Some(bank) = &rows.hour_bank {
+            let first = total_row + 2;
+            sheet.write_string_with_format(
+                first,
+                0,
+                if bank.closed {
+                    "Banco de horas"
                 } else {
-                    base_class.to_string()
-                };
-                let rowspan = group.rows.len();
-                for (row_idx, row) in group.rows.iter().enumerate() {
-                    html.push_str(&format!("<tr class=\"{}\">", class));
-                    if row_idx == 0 {
-                        writeln!(
-                            html,
-                            "<td rowspan=\"{rowspan}\"><strong>{date}</strong><br/><small>{weekday}</small></td>",
-                            date = group.date.format("%m/%d"),
-                            weekday = group.weekday_name,
-                        )
-                        .expect("write to string");
-                    }
-                    if row_idx == 0 {
-                        writeln!(
-                            html,
-                            "<td>{}</td><td>{}</td><td>{}</td><td rowspan=\"{rowspan}\">{} ({})</td><td rowspan=\"{rowspan}\">{}</td></tr>",
-                            row.clock_in, row.clock_out, row.duration_label, format_duration(group.daily_total_minutes), group.daily_total_minutes, group.daily_balance
-                        )
-                        .expect("write to string");
-                    } else {
-                        writeln!(
-                            html,
-                            "<td>{}</td><td>{}</td><td>{}</td></tr>",
-                            row.clock_in, row.clock_out, row.duration_label
-                        )
-                        .expect("write to string");
-                    }
-                }
+                    "Banco de horas (provisional)"
+                },
+                &title,
+            )?;
+            for (offset, (label, minutes)) in [
+                ("Saldo inicial", bank.opening),
+                ("Saldo del período", bank.delta),
+                ("Ajustes", bank.adjustments),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                let row = first + 1 + offset as u32;
+                sheet.write_string(row, 0, label)?;
+                sheet.write_number_with_format(row, 5, minutes_to_hours(minutes), &balance)?;
             }
+            sheet.write_string_with_format(first + 4, 0, "Saldo final", &total_label)?;
+            sheet.write_formula_with_format(
+                first + 4,
+                5,
+                xlsx_sum(first + 1, first + 4, 5)
+                    .set_result(minutes_to_hours(bank.closing).to_string()),
+                &total_balance,
+            )?;
         }
-        html.push_str("</tbody></table>");
+        sheet.set_freeze_panes(XLSX_FIRST_ROW, 1)?;
+    }
+
+    workbook.save(path)?;
+    Ok(())
+}
+
+/// A worker sheet's totals for columns E to L, used as the cached results
+/// of the total formulas so viewers that do not recalculate still show them.
+fn xlsx_totals(rows: &WorkerRows) -> [f64; 8] {
+    let groups = &rows.day_groups;
+    let sum = |f: fn(&DayGroup) -> i64| groups.iter().map(f).sum::<i64>();
+    let sessions = groups
+        .iter()
+        .flat_map(|g| &g.rows)
+        .filter(|r| r.clock_in != "--:--:--")
+        .count();
+    [
+        sessions as f64,
+        minutes_to_hours(sum(|g| g.daily_total_minutes)),
+        minutes_to_hours(sum(|g| g.minutes_needed)),
+        minutes_to_hours(sum(|g| g.absence_minutes)),
+        minutes_to_hours(sum(|g| g.daily_balance)),
+        minutes_to_hours(sum(|g| g.overtime_minutes)),
+        sum(|g| g.late_minutes) as f64,
+        sum(|g| g.early_leave_minutes) as f64,
+    ]
+}
+
+/// `=SUM(...)` over rows `first` up to, not including, `end` of `col`.
+fn xlsx_sum(first: u32, end: u32, col: u16) -> Formula {
+    if end <= first {
+        return Formula::new("=0");
+    }
+    Formula::new(format!(
+        "=SUM({}:{})",
+        row_col_to_cell(first, col),
+        row_col_to_cell(end - 1, col)
+    ))
+}
 
+/// A sheet name Excel accepts: at most 31 characters, none of `[]:*?/\`,
+/// and different from the names already in `used`.
+fn xlsx_sheet_name(worker_name: &str, used: &mut Vec<String>) -> String {
+    let cleaned: String = worker_name
+        .chars()
+        .map(|c| if "[]:*?/\\".contains(c) { ' ' } else { c })
+        .collect();
+    let cleaned = cleaned.trim().trim_matches('\'').trim();
+    let base: String = if cleaned.is_empty() {
+        "Trabajador".to_string()
+    } else {
+        cleaned.chars().take(31).collect()
+    };
+    let mut name = base.clone();
+    let mut copy = 2;
+    while used.contains(&name.to_lowercase()) {
+        let suffix = format!(" ({})", copy);
+        let keep = 31 - suffix.chars().count();
+        name = format!(
+            "{}{}",
+            base.chars().take(keep).collect::<String>().trim_end(),
+            suffix
+        );
+        copy += 1;
+    }
+    used.push(name.to_lowercase());
+    name
+}
+
+/// "HH:MM:SS" (possibly followed by a marker) as a fraction of a day.
+fn day_fraction(time: &str) -> Option<f64> {
+    let mut parts = time.get(..8)?.split(':').map(|p| p.parse::<u32>().ok());
+    let (h, m, s) = (parts.next()??, parts.next()??, parts.next()??);
+    (h <= 24 && m < 60 && s < 60).then(|| f64::from(h * 3600 + m * 60 + s) / 86_400.0)
+}
+
+fn minutes_to_hours(minutes: i64) -> f64 {
+    minutes as f64 / 60.0
+}
+
+/// Writes the period's ordinary and overtime hours per worker, broken down
+/// by week with the weekly limit that applied.
+fn write_overtime_summary_html(
+    path: &Path,
+    period: &str,
+    worker_data: &[WorkerReportData],
+) -> Result<(), ReportError> {
+    let mut html = String::new();
+    writeln!(
+        html,
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Horas extras {period}</title>\
+<style>body{{font-family:Arial,sans-serif;padding:20px}}h1{{margin-bottom:0}}table{{border-collapse:collapse;width:100%;margin-top:16px}}th,td{{border:1px solid #555;padding:6px;text-align:center}}th{{background-color:#eee}}tr.total td{{font-weight:600;background-color:#f7f7f7}}</style></head><body>",
+        period = period
+    )
+    .expect("write to string");
+    writeln!(html, "<h1>Horas extras</h1><h2>Period: {}</h2>", period).expect("write to string");
+    html.push_str("<table><thead><tr><th>Trabajador</th><th>Semana</th><th>Límite semanal</th><th>Horas ordinarias</th><th>Horas extras</th></tr></thead><tbody>");
+    for worker in worker_data {
+        let weeks = &worker.rows.overtime_weeks;
+        for (index, week) in weeks.iter().enumerate() {
+            html.push_str("<tr>");
+            if index == 0 {
+                writeln!(
+                    html,
+                    "<td rowspan=\"{}\">{}</td>",
+                    weeks.len() + 1,
+                    escape_html(&worker.worker_name)
+                )
+                .expect("write to string");
+            }
+            writeln!(
+                html,
+                "<td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                week_label(week),
+                format_duration(week.limit_minutes),
+                format_duration(week.ordinary),
+                format_duration(week.overtime)
+            )
+            .expect("write to string");
+        }
+        let name_cell = if weeks.is_empty() {
+            format!("<td>{}</td>", escape_html(&worker.worker_name))
+        } else {
+            String::new()
+        };
         writeln!(
             html,
-            "<p><strong>Total:</strong> {} ({} minutes)</p>",
-            format_duration(worker.total_minutes),
-            worker.total_minutes
+            "<tr class=\"total\">{}<td colspan=\"2\">Total del mes</td><td>{} ({} minutes)</td><td>{} ({} minutes)</td></tr>",
+            name_cell,
+            format_duration(worker.rows.ordinary_minutes),
+            worker.rows.ordinary_minutes,
+            format_duration(worker.rows.overtime_minutes),
+            worker.rows.overtime_minutes
         )
         .expect("write to string");
+    }
+    html.push_str("</tbody></table>");
+    html.push_str("<p>Las horas que exceden el tope diario o el límite semanal vigente se cuentan como extras. Las semanas que cruzan el inicio del mes consideran también los días del mes anterior.</p>");
+    html.push_str("</body></html>");
 
-        if worker.has_open_sessions {
-            html.push_str("<p>* Entries marked with an asterisk do not have a recorded clock out; the current time was used to compute the duration.</p>");
+    let mut file = File::create(path)?;
+    file.write_all(html.as_bytes())?;
+    Ok(())
+}
+
+fn write_overtime_summary_csv(
+    path: &Path,
+    period: &str,
+    worker_data: &[WorkerReportData],
+) -> Result<(), ReportError> {
+    let mut contents = String::new();
+    writeln!(contents, "Period,{}", period).expect("write to string");
+    contents.push_str(
+        "Trabajador,Semana,Limite Semanal Minutos,Minutos Ordinarios,Minutos Extras,Extras HH:MM\n",
+    );
+    for worker in worker_data {
+        let name = csv_field(&worker.worker_name);
+        for week in &worker.rows.overtime_weeks {
+            writeln!(
+                contents,
+                "{},{},{},{},{},{}",
+                name,
+                week.week_start,
+                week.limit_minutes,
+                week.ordinary,
+                week.overtime,
+                format_duration(week.overtime)
+            )
+            .expect("write to string");
         }
+        writeln!(
+            contents,
+            "{},Total,,{},{},{}",
+            name,
+            worker.rows.ordinary_minutes,
+            worker.rows.overtime_minutes,
+            format_duration(worker.rows.overtime_minutes)
+        )
+        .expect("write to string");
     }
 
+    let mut file = File::create(path)?;
+    file.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+fn week_label(week: &WeekOvertime) -> String {
+    format!(
+        "{} al {}",
+        week.week_start.format("%d/%m"),
+        (week.week_start + Duration::days(6)).format("%d/%m")
+    )
+}
+
+/// Writes a printable feriado legal statement for one worker: every
+/// monthly accrual and vacation taken, with the running balance, as of
+/// `as_of`. Returns the path of the file.
+pub fn export_vacation_statement(
+    conn: &Connection,
+    worker_id: i64,
+    as_of: NaiveDate,
+    output_dir: &Path,
+) -> Result<PathBuf, ReportError> {
+    use crate::vacations::format_days;
+
+    let worker = db::get_worker(conn, worker_id)?.ok_or_else(|| {
+        ReportError::Database(db::DbError::Validation(format!(
+            "no existe el trabajador {}",
+            worker_id
+        )))
+    })?;
+    let balance = crate::vacations::vacation_balance(conn, &worker, as_of)?.ok_or_else(|| {
+        ReportError::Database(db::DbError::Validation(format!(
+            "{} no tiene fecha de ingreso",
+            worker.name
+        )))
+    })?;
+
+    let mut html = String::new();
+    writeln!(
+        html,
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Feriado legal {name}</title>\
+<style>body{{font-family:Arial,sans-serif;padding:20px}}h1{{margin-bottom:0}}table{{border-collapse:collapse;width:100%;margin-top:16px}}th,td{{border:1px solid #555;padding:6px;text-align:center}}th{{background-color:#eee}}td.left{{text-align:left}}</style></head><body>",
+        name = escape_html(&worker.name)
+    )
+    .expect("write to string");
+    writeln!(
+        html,
+        "<h1>{}</h1><h2>Estado de feriado legal al {}</h2>",
+        escape_html(&worker.name),
+        as_of.format("%d/%m/%Y")
+    )
+    .expect("write to string");
+    writeln!(
+        html,
+        "<p>Fecha de ingreso: {} &middot; Años de servicio: {} &middot; Días por año: {} (progresivos: {})</p>",
+        balance.hire_date.format("%d/%m/%Y"),
+        balance.years_of_service,
+        format_days(balance.annual_days),
+        balance.progressive_days
+    )
+    .expect("write to string");
+    html.push_str("<table><thead><tr><th>Fecha</th><th>Movimiento</th><th>Días</th><th>Saldo</th></tr></thead><tbody>");
+    for movement in &balance.movements {
+        writeln!(
+            html,
+            "<tr><td>{}</td><td class=\"left\">{}</td><td>{}</td><td>{}</td></tr>",
+            movement.date.format("%d/%m/%Y"),
+            escape_html(&movement.description),
+            format_days(movement.days),
+            format_days(movement.balance)
+        )
+        .expect("write to string");
+    }
+    html.push_str("</tbody></table>");
+    writeln!(
+        html,
+        "<p><strong>Acumulados:</strong> {} días &middot; <strong>Tomados:</strong> {} días &middot; <strong>Disponibles:</strong> {} días</p>",
+        format_days(balance.accrued),
+        format_days(balance.taken),
+        format_days(balance.available)
+    )
+    .expect("write to string");
+    if balance.scheduled > 0.0 {
+        writeln!(
+            html,
+            "<p>Vacaciones ya programadas después de esta fecha: {} días.</p>",
+            format_days(balance.scheduled)
+        )
+        .expect("write to string");
+    }
+    html.push_str(
+        "<p>Los días se cuentan como hábiles: sábados, domingos y feriados no se descuentan.</p>",
+    );
     html.push_str("</body></html>");
 
-    let mut file = File::create(path)?;
+    fs::create_dir_all(output_dir)?;
+    let path = output_dir.join(format!(
+        "feriado_legal_{}_{}.html",
+        sanitize_filename(&worker.name),
+        as_of.format("%Y-%m-%d")
+    ));
+    let mut file = File::create(&path)?;
     file.write_all(html.as_bytes())?;
-    Ok(())
+    Ok(path)
 }
 
 fn send_worker_html_reports_email(
-    month_key: &str,
+    period: &str,
     worker_html_paths: &[PathBuf],
 ) -> Result<(), ReportError> {
     if worker_html_paths.is_empty() {
@@ -517,8 +1814,8 @@ fn send_worker_html_reports_email(
         })?;
 
     let text_body = format!(
-        "Adjuntamos los reportes HTML por trabajador para el mes {}.",
-        month_key
+        "Adjuntamos los reportes HTML por trabajador para el periodo {}.",
+        period
     );
     let html_attachment_type: header::ContentType = "text/html; charset=utf-8"
         .parse()
@@ -551,7 +1848,7 @@ fn send_worker_html_reports_email(
         .to(REPORT_EMAIL_TO
             .parse()
             .map_err(|e| ReportError::Email(format!("invalid destination address: {}", e)))?)
-        .subject(format!("Reportes HTML trabajadores {}", month_key))
+        .subject(format!("Reportes HTML trabajadores {}", period))
         .multipart(multipart)
         .map_err(|e| ReportError::Email(format!("failed to build email: {}", e)))?;
 
@@ -569,13 +1866,61 @@ fn send_worker_html_reports_email(
     Ok(())
 }
 
+fn write_hour_bank_csv(contents: &mut String, bank: &MonthBalance, adjustments: &[Adjustment]) {
+    for (label, minutes) in [
+        ("Banco saldo inicial", bank.opening),
+        ("Banco saldo del mes", bank.delta),
+        ("Banco ajustes", bank.adjustments),
+        ("Banco saldo final", bank.closing),
+    ] {
+        writeln!(
+            contents,
+            "{},,,{},{}",
+            label,
+            minutes,
+            format_balance(minutes)
+        )
+        .expect("write to string");
+    }
+    for adjustment in adjustments {
+        writeln!(
+            contents,
+            "Ajuste {},,,{},{},{}",
+            adjustment.date.format("%m/%d"),
+            adjustment.minutes,
+            format_balance(adjustment.minutes),
+            csv_field(&adjustment.reason)
+        )
+        .expect("write to string");
+    }
+}
+
+/// Blank when on time, so the columns only draw attention to real cases.
+fn minutes_cell(minutes: i64) -> String {
+    if minutes > 0 {
+        minutes.to_string()
+    } else {
+        String::new()
+    }
+}
+
+fn has_corrections(day_groups: &[DayGroup]) -> bool {
+    day_groups
+        .iter()
+        .any(|group| group.rows.iter().any(|row| row.correction.is_some()))
+}
+
+fn has_auto_closed(day_groups: &[DayGroup]) -> bool {
+    day_groups
+        .iter()
+        .any(|group| group.rows.iter().any(|row| row.auto_close.is_some()))
+}
+
 fn sanitize_filename(name: &str) -> String {
     let mut result = String::with_capacity(name.len());
     for ch in name.chars() {
         if ch.is_ascii_alphanumeric() {
             result.push(ch);
-        } else if ch.is_ascii_whitespace() || ch == '-' {
-            result.push('_');
         } else {
             result.push('_');
         }
@@ -593,7 +1938,7 @@ fn format_duration(minutes: i64) -> String {
     format!("{:02}:{:02}", hours, mins)
 }
 
-fn escape_html(value: &str) -> String {
+pub(crate) fn escape_html(value: &str) -> String {
     let mut escaped = String::with_capacity(value.len());
     for ch in value.chars() {
         match ch {
@@ -608,18 +1953,6 @@ fn escape_html(value: &str) -> String {
     escaped
 }
 
-fn get_minutes_needed(weekday: Weekday) -> i64 {
-    match weekday {
-        Weekday::Mon => 510,
-        Weekday::Tue => 510,
-        Weekday::Wed => 450,
-        Weekday::Thu => 450,
-        Weekday::Fri => 450,
-        Weekday::Sat => 150,
-        Weekday::Sun => 0,
-    }
-}
-
 fn weekday_name_es(weekday: Weekday) -> &'static str {
     match weekday {
         Weekday::Mon => "lunes",
@@ -631,3 +1964,232 @@ fn weekday_name_es(weekday: Weekday) -> &'static str {
         Weekday::Sun => "domingo",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::absences::{Absence, AbsenceKind, add_absence};
+    use crate::utils::local_time_on;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn test_db() -> (Connection, i64, AuditContext) {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&mut conn, Path::new("backups")).unwrap();
+        let ctx = AuditContext::admin("test");
+        let worker_id = db::add_worker(&mut conn, "Prueba", "1000", &ctx).unwrap();
+        (conn, worker_id, ctx)
+    }
+
+    /// Three sessions on every day of March 2024, enough rows to need
+    /// several pages.
+    fn busy_march(conn: &mut Connection, worker_id: i64, ctx: &AuditContext) -> WorkerRows {
+        for day in 1..=31 {
+            for time in ["08:00", "10:00", "10:15", "13:00", "14:00", "17:00"] {
+                let at = local_time_on(date(2024, 3, day), time).unwrap();
+                db::toggle_clock(conn, worker_id, None, at, false, ctx).unwrap();
+            }
+        }
+        build_rows(conn, worker_id, ReportRange::month(date(2024, 3, 1))).unwrap()
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("timesheet-{}-{}", std::process::id(), name))
+    }
+
+    fn count(haystack: &[u8], needle: &str) -> usize {
+        haystack
+            .windows(needle.len())
+            .filter(|window| *window == needle.as_bytes())
+            .count()
+    }
+
+    #[test]
+    fn absence_days_skip_days_off_inside_the_leave() {
+        let _guard = crate::timezone::TEST_ZONE_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        timezone::set_current(timezone::DEFAULT_TIME_ZONE);
+        let (mut conn, worker_id, ctx) = test_db();
+        // Friday to Monday; the default schedule has no Sunday shift.
+        let absence = Absence {
+            id: 0,
+            worker_id,
+            kind: AbsenceKind::LicenciaMedica,
+            start_date: date(2024, 3, 8),
+            end_date: date(2024, 3, 11),
+            paid: true,
+            note: String::new(),
+        };
+        add_absence(&mut conn, &absence, &ctx).unwrap();
+
+        let range = ReportRange::month(date(2024, 3, 1));
+        let rows = build_rows(&conn, worker_id, range).unwrap();
+        let worker = db::get_worker(&conn, worker_id).unwrap().unwrap();
+        let value = payroll_value(
+            PayrollField::AbsenceDays,
+            &worker,
+            range,
+            &rows,
+            HoursFormat::default(),
+        );
+        assert_eq!(value, "3");
+    }
+
+    #[test]
+    fn pdf_pages_are_numbered_and_each_worker_signs() {
+        let _guard = crate::timezone::TEST_ZONE_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        timezone::set_current(timezone::DEFAULT_TIME_ZONE);
+        let (mut conn, worker_id, ctx) = test_db();
+        let rows = busy_march(&mut conn, worker_id, &ctx);
+
+        let mut doc = PdfDocument::new("Prueba", pdf::LETTER);
+        draw_worker_pdf(&mut doc, "Prueba", "2024-03", &rows);
+        let pages = doc.page_count();
+        assert!(pages > 1);
+        number_pdf_pages(&mut doc);
+        let bytes = doc.to_bytes();
+        for number in 1..=pages {
+            let footer = format!("(P\\341gina {} de {})", number, pages);
+            assert_eq!(count(&bytes, &footer), 1, "{}", footer);
+        }
+        assert_eq!(count(&bytes, "(Firma trabajador)"), 1);
+        assert_eq!(count(&bytes, "(Firma empleador)"), 1);
+
+        let april = WorkerReportData {
+            worker_name: "Abril".to_string(),
+            rows: build_rows(&conn, worker_id, ReportRange::month(date(2024, 4, 1))).unwrap(),
+        };
+        let busy = WorkerReportData {
+            worker_name: "Prueba".to_string(),
+            rows,
+        };
+        let path = temp_path("merged.pdf");
+        write_merged_pdf_report(&path, "2024-03", &[busy, april]).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let total = pages + 1;
+        assert_eq!(
+            count(&bytes, &format!("(P\\341gina {} de {})", total, total)),
+            1
+        );
+        assert_eq!(count(&bytes, "(Firma trabajador)"), 2);
+    }
+
+    #[test]
+    fn sheet_names_are_valid_and_unique() {
+        let mut used = vec!["resumen".to_string()];
+        assert_eq!(xlsx_sheet_name("Resumen", &mut used), "Resumen (2)");
+        assert_eq!(
+            xlsx_sheet_name("Ana [turno]: a/b", &mut used),
+            "Ana  turno   a b"
+        );
+        assert_eq!(xlsx_sheet_name("'?'", &mut used), "Trabajador");
+        assert_eq!(xlsx_sheet_name("*", &mut used), "Trabajador (2)");
+
+        let long = "María José de los Ángeles Fernández";
+        let first = xlsx_sheet_name(long, &mut used);
+        let second = xlsx_sheet_name(long, &mut used);
+        assert_eq!(first, "María José de los Ángeles Ferná");
+        assert_eq!(second, "María José de los Ángeles F (2)");
+        assert!(second.chars().count() <= 31);
+        assert_eq!(used.len(), 7);
+    }
+
+    #[test]
+    fn xlsx_totals_are_sums_of_the_day_rows() {
+        assert!(xlsx_sum(3, 34, 5) == Formula::new("=SUM(F4:F34)"));
+        assert!(xlsx_sum(3, 3, 5) == Formula::new("=0"));
+
+        let _guard = crate::timezone::TEST_ZONE_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        timezone::set_current(timezone::DEFAULT_TIME_ZONE);
+        let (mut conn, worker_id, ctx) = test_db();
+        let rows = busy_march(&mut conn, worker_id, &ctx);
+        let totals = xlsx_totals(&rows);
+        assert_eq!(totals[0], 93.0);
+        // 2 h + 2 h 45 min + 3 h a day.
+        assert_eq!(totals[1], 31.0 * 7.75);
+        assert_eq!(totals[1], minutes_to_hours(rows.total_minutes));
+
+        let path = temp_path("workbook.xlsx");
+        let workers = [WorkerReportData {
+            worker_name: "Prueba".to_string(),
+            rows,
+        }];
+        write_xlsx_workbook(&path, "2024-03", &workers).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(bytes.starts_with(b"PK"));
+    }
+
+    /// Splits a CSV line, honouring quoted fields.
+    fn csv_fields(line: &str) -> Vec<String> {
+        let mut fields = vec![String::new()];
+        let mut quoted = false;
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    fields.last_mut().unwrap().push('"');
+                }
+                '"' => quoted = !quoted,
+                ',' if !quoted => fields.push(String::new()),
+                c => fields.last_mut().unwrap().push(c),
+            }
+        }
+        fields
+    }
+
+    #[test]
+    fn csv_free_text_is_quoted() {
+        let _guard = crate::timezone::TEST_ZONE_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        timezone::set_current(timezone::DEFAULT_TIME_ZONE);
+        let (mut conn, worker_id, ctx) = test_db();
+        let absence = Absence {
+            id: 0,
+            worker_id,
+            kind: AbsenceKind::DiaAdministrativo,
+            start_date: date(2024, 3, 4),
+            end_date: date(2024, 3, 4),
+            paid: false,
+            note: "trámite, notaría".to_string(),
+        };
+        add_absence(&mut conn, &absence, &ctx).unwrap();
+        let holiday = crate::holidays::Holiday {
+            date: date(2024, 3, 5),
+            name: "Fiesta \"San José\", local".to_string(),
+            source: "manual".to_string(),
+        };
+        crate::holidays::save_holidays(&mut conn, &[holiday], &ctx).unwrap();
+
+        let rows = build_rows(&conn, worker_id, ReportRange::month(date(2024, 3, 1))).unwrap();
+        let path = temp_path("report.csv");
+        write_csv_report(&path, "Pérez, Ana", "2024-03", &rows).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(csv_fields(lines[0]), ["Worker", "Pérez, Ana"]);
+        let columns = csv_fields(lines[2]).len();
+        let day = |prefix: &str| {
+            let line = lines.iter().find(|line| line.starts_with(prefix)).unwrap();
+            let fields = csv_fields(line);
+            assert_eq!(fields.len(), columns, "{}", line);
+            fields
+        };
+        assert_eq!(day("03/04,")[19], "Día administrativo (sin goce)");
+        assert_eq!(day("03/05,")[18], "Fiesta \"San José\", local");
+        assert!(contents.contains(",\"Fiesta \"\"San José\"\", local\","));
+    }
+}
@@ -0,0 +1,816 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::audit::{self, AuditContext};
+use crate::db::{DbError, Result};
+use crate::utils::local_time_on;
+
+/// Working hours for one weekday of a template. An `end` at or before
+/// `start` means the shift ends on the following day.
+#[derive(Clone, Debug, Serialize)]
+pub struct ScheduleDay {
+    #[serde(serialize_with = "serialize_weekday")]
+    pub weekday: Weekday,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub break_minutes: i64,
+}
+
+impl ScheduleDay {
+    /// Minutes the worker owes on this day: the shift length minus the break.
+    pub fn required_minutes(&self) -> i64 {
+        let mut span = (self.end - self.start).num_minutes();
+        if span <= 0 {
+            span += 24 * 60;
+        }
+        (span - self.break_minutes).max(0)
+    }
+
+    /// The shift's start and end as instants, for a shift starting on `date`.
+    pub fn bounds_on(&self, date: NaiveDate) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let start = local_time_on(date, &self.start.format("%H:%M").to_string())?;
+        let end_date = if self.end <= self.start {
+            date.succ_opt()?
+        } else {
+            date
+        };
+        let end = local_time_on(end_date, &self.end.format("%H:%M").to_string())?;
+        Some((start, end))
+    }
+}
+
+fn serialize_weekday<S: serde::Serializer>(
+    weekday: &Weekday,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_u32(weekday.num_days_from_monday())
+}
+
+/// The days of a template from `valid_from` until its next version.
+#[derive(Clone, Debug, Serialize)]
+pub struct ScheduleVersion {
+    /// `None` for the template's first version, which also covers every
+    /// earlier date.
+    pub valid_from: Option<NaiveDate>,
+    pub days: Vec<ScheduleDay>,
+}
+
+impl ScheduleVersion {
+    fn covers(&self, date: NaiveDate) -> bool {
+        self.valid_from.is_none_or(|from| from <= date)
+    }
+}
+
+/// A named weekly template. Days without an entry are days off.
+///
+/// Editing a template adds a version from a given date instead of changing
+/// past days, so required minutes and balances of earlier periods stay as
+/// they were computed.
+#[derive(Clone, Debug, Serialize)]
+pub struct Schedule {
+    pub id: i64,
+    pub name: String,
+    /// Used for workers with no assignment covering a date.
+    pub is_default: bool,
+    /// Oldest first; never empty.
+    pub versions: Vec<ScheduleVersion>,
+}
+
+impl Schedule {
+    /// The latest version, the one shown in the editor.
+    pub fn current(&self) -> &ScheduleVersion {
+        self.versions.last().expect("schedules have a version")
+    }
+
+    /// The version in force on `date`.
+    pub fn version_on(&self, date: NaiveDate) -> &ScheduleVersion {
+        self.versions
+            .iter()
+            .rev()
+            .find(|version| version.covers(date))
+            .unwrap_or(&self.versions[0])
+    }
+
+    /// The latest version's day for `weekday`.
+    pub fn day(&self, weekday: Weekday) -> Option<&ScheduleDay> {
+        self.current().days.iter().find(|d| d.weekday == weekday)
+    }
+
+    pub fn day_on(&self, date: NaiveDate) -> Option<&ScheduleDay> {
+        self.version_on(date)
+            .days
+            .iter()
+            .find(|d| d.weekday == date.weekday())
+    }
+}
+
+/// A template in force for a worker from `effective_from` through
+/// `effective_to` (inclusive); `None` means until further notice.
+#[derive(Clone, Debug, Serialize)]
+pub struct Assignment {
+    pub id: i64,
+    pub worker_id: i64,
+    pub schedule_id: i64,
+    pub effective_from: NaiveDate,
+    pub effective_to: Option<NaiveDate>,
+}
+
+impl Assignment {
+    pub fn covers(&self, date: NaiveDate) -> bool {
+        self.effective_from <= date && self.effective_to.is_none_or(|to| date <= to)
+    }
+}
+
+/// Everything needed to answer "what was this worker's schedule on a date",
+/// loaded once per worker so reports do not query per day.
+#[derive(Clone, Debug)]
+pub struct WorkerCalendar {
+    assignments: Vec<(Assignment, Schedule)>,
+    default: Option<Schedule>,
+}
+
+impl WorkerCalendar {
+    /// The template in force on `date`, falling back to the default one.
+    pub fn schedule_on(&self, date: NaiveDate) -> Option<&Schedule> {
+        self.assignments
+            .iter()
+            .find(|(assignment, _)| assignment.covers(date))
+            .map(|(_, schedule)| schedule)
+            .or(self.default.as_ref())
+    }
+
+    pub fn day_on(&self, date: NaiveDate) -> Option<&ScheduleDay> {
+        self.schedule_on(date)
+            .and_then(|schedule| schedule.day_on(date))
+    }
+
+    /// The scheduled shift a session clocked in at `clock_in` belongs to: of
+    /// the shifts starting that day or the day before, the first one still
+    /// running at the clock-in.
+    pub fn shift_for(
+        &self,
+        clock_in: DateTime<Utc>,
+    ) -> Option<(&ScheduleDay, DateTime<Utc>, DateTime<Utc>)> {
+        let date = crate::timezone::to_local(clock_in).date_naive();
+        [date.pred_opt(), Some(date)]
+            .into_iter()
+            .flatten()
+            .filter_map(|day| {
+                let shift = self.day_on(day)?;
+                let (start, end) = shift.bounds_on(day)?;
+                Some((shift, start, end))
+            })
+            .filter(|(_, _, end)| *end > clock_in)
+            .min_by_key(|(_, _, end)| *end)
+    }
+
+    pub fn minutes_needed(&self, date: NaiveDate) -> i64 {
+        self.day_on(date)
+            .map(ScheduleDay::required_minutes)
+            .unwrap_or(0)
+    }
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").ok()
+}
+
+fn load_days(conn: &Connection, version_id: i64) -> Result<Vec<ScheduleDay>> {
+    let mut stmt = conn.prepare(
+        "SELECT weekday, start_time, end_time, break_minutes FROM schedule_days
+         WHERE version_id = ? ORDER BY weekday",
+    )?;
+    let rows = stmt.query_map(rusqlite::params![version_id], |row| {
+        Ok((
+            row.get::<_, u8>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, i64>(3)?,
+        ))
+    })?;
+    let mut days = Vec::new();
+    for row in rows {
+        let (weekday, start, end, break_minutes) = row?;
+        // Rows that cannot be read are treated as days off rather than
+        // failing every report that touches the template.
+        if let (Ok(weekday), Some(start), Some(end)) = (
+            Weekday::try_from(weekday),
+            parse_time(&start),
+            parse_time(&end),
+        ) {
+            days.push(ScheduleDay {
+                weekday,
+                start,
+                end,
+                break_minutes,
+            });
+        }
+    }
+    Ok(days)
+}
+
+fn load_versions(conn: &Connection, schedule_id: i64) -> Result<Vec<ScheduleVersion>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, valid_from FROM schedule_versions WHERE schedule_id = ?
+         ORDER BY valid_from IS NOT NULL, valid_from",
+    )?;
+    let rows = stmt
+        .query_map(rusqlite::params![schedule_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let mut versions = Vec::new();
+    for (id, valid_from) in rows {
+        // A version with an unreadable date is skipped like a bad day row.
+        let valid_from = match valid_from {
+            Some(value) => match parse_date(&value) {
+                Some(date) => Some(date),
+                None => continue,
+            },
+            None => None,
+        };
+        versions.push(ScheduleVersion {
+            valid_from,
+            days: load_days(conn, id)?,
+        });
+    }
+    if versions.is_empty() {
+        versions.push(ScheduleVersion {
+            valid_from: None,
+            days: Vec::new(),
+        });
+    }
+    Ok(versions)
+}
+
+fn schedule_from_row(
+    conn: &Connection,
+    id: i64,
+    name: String,
+    is_default: bool,
+) -> Result<Schedule> {
+    Ok(Schedule {
+        id,
+        name,
+        is_default,
+        versions: load_versions(conn, id)?,
+    })
+}
+
+pub fn get_schedule(conn: &Connection, id: i64) -> Result<Option<Schedule>> {
+    let row = conn
+        .query_row(
+            "SELECT id, name, is_default FROM schedules WHERE id = ?",
+            rusqlite::params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+    row.map(|(id, name, is_default)| schedule_from_row(conn, id, name, is_default))
+        .transpose()
+}
+
+/// All templates, ordered by name.
+pub fn list_schedules(conn: &Connection) -> Result<Vec<Schedule>> {
+    let mut stmt = conn.prepare("SELECT id, name, is_default FROM schedules ORDER BY name")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<rusqlite::Result<Vec<(i64, String, bool)>>>()?;
+    rows.into_iter()
+        .map(|(id, name, is_default)| schedule_from_row(conn, id, name, is_default))
+        .collect()
+}
+
+/// Creates a template (`id = None`) or renames an existing one and puts
+/// `days` in force from `valid_from`. Returns the template id.
+///
+/// A new template's days cover every date. For an existing one, versions
+/// starting on or after `valid_from` are replaced and earlier days keep
+/// their hours. `valid_from` must fall after the last month closed in the
+/// banco de horas, whose balances were computed with the old days.
+pub fn save_schedule(
+    conn: &mut Connection,
+    id: Option<i64>,
+    name: &str,
+    days: &[ScheduleDay],
+    valid_from: NaiveDate,
+    ctx: &AuditContext,
+) -> Result<i64> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(DbError::Validation(
+            "el horario necesita un nombre".to_string(),
+        ));
+    }
+    if let Some(day) = days.iter().find(|d| d.break_minutes < 0) {
+        return Err(DbError::Validation(format!(
+            "colación negativa para el día {}",
+            day.weekday
+        )));
+    }
+    let tx = conn.transaction()?;
+    let before = match id {
+        Some(id) => Some(
+            get_schedule(&tx, id)?
+                .ok_or_else(|| DbError::Validation(format!("no existe el horario {}", id)))?,
+        ),
+        None => None,
+    };
+    let id = match &before {
+        Some(before) => {
+            tx.execute(
+                "UPDATE schedules SET name = ? WHERE id = ?",
+                rusqlite::params![name, before.id],
+            )?;
+            let previous = before
+                .versions
+                .iter()
+                .rev()
+                .find(|version| version.valid_from.is_none_or(|from| from < valid_from))
+                .unwrap_or(&before.versions[0]);
+            let replaced = before
+                .versions
+                .iter()
+                .any(|version| version.valid_from.is_some_and(|from| from >= valid_from));
+            let changed = !same_days(&previous.days, days);
+            if replaced || changed {
+                if let Some(month) = crate::hour_bank::last_closed_month(&tx)?
+                    && valid_from.format("%Y-%m").to_string() <= month
+                {
+                    return Err(DbError::Validation(format!(
+                        "el mes {} ya está cerrado; los cambios deben regir desde una fecha posterior",
+                        month
+                    )));
+                }
+                tx.execute(
+                    "DELETE FROM schedule_days WHERE version_id IN
+                     (SELECT id FROM schedule_versions WHERE schedule_id = ? AND valid_from >= ?)",
+                    rusqlite::params![before.id, valid_from.to_string()],
+                )?;
+                tx.execute(
+                    "DELETE FROM schedule_versions WHERE schedule_id = ? AND valid_from >= ?",
+                    rusqlite::params![before.id, valid_from.to_string()],
+                )?;
+            }
+            if changed {
+                insert_version(&tx, before.id, Some(valid_from), days)?;
+            }
+            before.id
+        }
+        None => {
+            tx.execute(
+                "INSERT INTO schedules (name) VALUES (?)",
+                rusqlite::params![name],
+            )?;
+            let id = tx.last_insert_rowid();
+            insert_version(&tx, id, None, days)?;
+            id
+        }
+    };
+    let after = get_schedule(&tx, id)?;
+    audit::record(
+        &tx,
+        ctx,
+        "schedules",
+        Some(id),
+        if before.is_some() { "update" } else { "create" },
+        before.as_ref().and_then(audit::snapshot),
+        after.as_ref().and_then(audit::snapshot),
+    )?;
+    tx.commit()?;
+    Ok(id)
+}
+
+fn same_days(a: &[ScheduleDay], b: &[ScheduleDay]) -> bool {
+    a.len() == b.len()
+        && a.iter().all(|x| {
+            b.iter().any(|y| {
+                x.weekday == y.weekday
+                    && x.start == y.start
+                    && x.end == y.end
+                    && x.break_minutes == y.break_minutes
+            })
+        })
+}
+
+fn insert_version(
+    tx: &rusqlite::Transaction,
+    schedule_id: i64,
+    valid_from: Option<NaiveDate>,
+    days: &[ScheduleDay],
+) -> Result<()> {
+    tx.execute(
+        "INSERT INTO schedule_versions (schedule_id, valid_from) VALUES (?, ?)",
+        rusqlite::params![schedule_id, valid_from.map(|d| d.to_string())],
+    )?;
+    let version_id = tx.last_insert_rowid();
+    for day in days {
+        tx.execute(
+            "INSERT INTO schedule_days (version_id, weekday, start_time, end_time, break_minutes)
+             VALUES (?, ?, ?, ?, ?)",
+            rusqlite::params![
+                version_id,
+                day.weekday.num_days_from_monday(),
+                day.start.format("%H:%M").to_string(),
+                day.end.format("%H:%M").to_string(),
+                day.break_minutes
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Makes `id` the template used for workers without an assignment.
+pub fn set_default_schedule(conn: &mut Connection, id: i64, ctx: &AuditContext) -> Result<()> {
+    let tx = conn.transaction()?;
+    let before = get_schedule(&tx, id)?
+        .ok_or_else(|| DbError::Validation(format!("no existe el horario {}", id)))?;
+    tx.execute(
+        "UPDATE schedules SET is_default = (id = ?)",
+        rusqlite::params![id],
+    )?;
+    audit::record(
+        &tx,
+        ctx,
+        "schedules",
+        Some(id),
+        "set_default",
+        audit::snapshot(&before),
+        None,
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+const ASSIGNMENT_COLUMNS: &str = "id, worker_id, schedule_id, effective_from, effective_to";
+
+fn assignment_from_row(row: &rusqlite::Row) -> rusqlite::Result<Option<Assignment>> {
+    let from: String = row.get(3)?;
+    let to: Option<String> = row.get(4)?;
+    let Some(effective_from) = parse_date(&from) else {
+        return Ok(None);
+    };
+    let effective_to = match to {
+        Some(to) => match parse_date(&to) {
+            Some(to) => Some(to),
+            None => return Ok(None),
+        },
+        None => None,
+    };
+    Ok(Some(Assignment {
+        id: row.get(0)?,
+        worker_id: row.get(1)?,
+        schedule_id: row.get(2)?,
+        effective_from,
+        effective_to,
+    }))
+}
+
+/// The worker's assignments, most recent first.
+pub fn worker_assignments(conn: &Connection, worker_id: i64) -> Result<Vec<Assignment>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM schedule_assignments WHERE worker_id = ? ORDER BY effective_from DESC",
+        ASSIGNMENT_COLUMNS
+    ))?;
+    let rows = stmt.query_map(rusqlite::params![worker_id], assignment_from_row)?;
+    let mut assignments = Vec::new();
+    for row in rows {
+        if let Some(assignment) = row? {
+            assignments.push(assignment);
+        }
+    }
+    Ok(assignments)
+}
+
+/// Puts `schedule_id` in force for the worker from `from` through `to`.
+///
+/// An open-ended assignment that started earlier is ended the day before
+/// `from`, which is the usual "new contract from date X" case. When `to` is
+/// set, the earlier assignment resumes the day after it, as for a temporary
+/// shift change. Any other overlap is rejected.
+pub fn assign_schedule(
+    conn: &mut Connection,
+    worker_id: i64,
+    schedule_id: i64,
+    from: NaiveDate,
+    to: Option<NaiveDate>,
+    ctx: &AuditContext,
+) -> Result<i64> {
+    if to.is_some_and(|to| to < from) {
+        return Err(DbError::Validation(
+            "la fecha de término es anterior a la de inicio".to_string(),
+        ));
+    }
+    let tx = conn.transaction()?;
+    if get_schedule(&tx, schedule_id)?.is_none() {
+        return Err(DbError::Validation(format!(
+            "no existe el horario {}",
+            schedule_id
+        )));
+    }
+    for existing in worker_assignments(&tx, worker_id)? {
+        if existing.effective_to.is_none() && existing.effective_from < from {
+            let ended = Assignment {
+                effective_to: Some(from - Duration::days(1)),
+                ..existing.clone()
+            };
+            tx.execute(
+                "UPDATE schedule_assignments SET effective_to = ? WHERE id = ?",
+                rusqlite::params![ended.effective_to.map(|d| d.to_string()), existing.id],
+            )?;
+            audit::record(
+                &tx,
+                ctx,
+                "schedule_assignments",
+                Some(existing.id),
+                "end",
+                audit::snapshot(&existing),
+                audit::snapshot(&ended),
+            )?;
+            if let Some(resume) = to.and_then(|to| to.succ_opt()) {
+                insert_assignment(&tx, worker_id, existing.schedule_id, resume, None, ctx)?;
+            }
+            continue;
+        }
+        let existing_end = existing.effective_to.unwrap_or(NaiveDate::MAX);
+        let new_end = to.unwrap_or(NaiveDate::MAX);
+        if existing.effective_from <= new_end && from <= existing_end {
+            return Err(DbError::Validation(format!(
+                "se superpone con la asignación desde {}",
+                existing.effective_from
+            )));
+        }
+    }
+    let id = insert_assignment(&tx, worker_id, schedule_id, from, to, ctx)?;
+    tx.commit()?;
+    Ok(id)
+}
+
+fn insert_assignment(
+    tx: &rusqlite::Transaction,
+    worker_id: i64,
+    schedule_id: i64,
+    from: NaiveDate,
+    to: Option<NaiveDate>,
+    ctx: &AuditContext,
+) -> Result<i64> {
+    tx.execute(
+        "INSERT INTO schedule_assignments (worker_id, schedule_id, effective_from, effective_to)
+         VALUES (?, ?, ?, ?)",
+        rusqlite::params![
+            worker_id,
+            schedule_id,
+            from.to_string(),
+            to.map(|d| d.to_string())
+        ],
+    )?;
+    let id = tx.last_insert_rowid();
+    let after = Assignment {
+        id,
+        worker_id,
+        schedule_id,
+        effective_from: from,
+        effective_to: to,
+    };
+    audit::record(
+        tx,
+        ctx,
+        "schedule_assignments",
+        Some(id),
+        "create",
+        None,
+        audit::snapshot(&after),
+    )?;
+    Ok(id)
+}
+
+pub fn remove_assignment(conn: &mut Connection, id: i64, ctx: &AuditContext) -> Result<()> {
+    let tx = conn.transaction()?;
+    let before = tx
+        .query_row(
+            &format!(
+                "SELECT {} FROM schedule_assignments WHERE id = ?",
+                ASSIGNMENT_COLUMNS
+            ),
+            rusqlite::params![id],
+            assignment_from_row,
+        )
+        .optional()?
+        .flatten();
+    tx.execute(
+        "DELETE FROM schedule_assignments WHERE id = ?",
+        rusqlite::params![id],
+    )?;
+    audit::record(
+        &tx,
+        ctx,
+        "schedule_assignments",
+        Some(id),
+        "delete",
+        before.as_ref().and_then(audit::snapshot),
+        None,
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn load_worker_calendar(conn: &Connection, worker_id: i64) -> Result<WorkerCalendar> {
+    let mut assignments = Vec::new();
+    for assignment in worker_assignments(conn, worker_id)? {
+        if let Some(schedule) = get_schedule(conn, assignment.schedule_id)? {
+            assignments.push((assignment, schedule));
+        }
+    }
+    let default_id: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM schedules WHERE is_default = 1 ORDER BY id LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let default = match default_id {
+        Some(id) => get_schedule(conn, id)?,
+        None => None,
+    };
+    Ok(WorkerCalendar {
+        assignments,
+        default,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn test_db() -> (Connection, i64, AuditContext) {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&mut conn, Path::new("backups")).unwrap();
+        let ctx = AuditContext::admin("test");
+        let worker_id = crate::db::add_worker(&mut conn, "Prueba", "1000", &ctx).unwrap();
+        (conn, worker_id, ctx)
+    }
+
+    /// Monday to Friday from `start` to `end` without a break.
+    fn weekdays(start: &str, end: &str) -> Vec<ScheduleDay> {
+        [
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+        ]
+        .into_iter()
+        .map(|weekday| ScheduleDay {
+            weekday,
+            start: parse_time(start).unwrap(),
+            end: parse_time(end).unwrap(),
+            break_minutes: 0,
+        })
+        .collect()
+    }
+
+    fn minutes_on(conn: &Connection, worker_id: i64, day: NaiveDate) -> i64 {
+        load_worker_calendar(conn, worker_id)
+            .unwrap()
+            .minutes_needed(day)
+    }
+
+    #[test]
+    fn editing_a_template_keeps_earlier_days() {
+        let (mut conn, worker_id, ctx) = test_db();
+        let id = save_schedule(
+            &mut conn,
+            None,
+            "Oficina",
+            &weekdays("09:00", "18:00"),
+            date(2026, 1, 1),
+            &ctx,
+        )
+        .unwrap();
+        assign_schedule(&mut conn, worker_id, id, date(2026, 1, 1), None, &ctx).unwrap();
+
+        save_schedule(
+            &mut conn,
+            Some(id),
+            "Oficina",
+            &weekdays("09:00", "17:00"),
+            date(2026, 3, 2),
+            &ctx,
+        )
+        .unwrap();
+        // Fridays, Feb 27 and Mar 6.
+        assert_eq!(minutes_on(&conn, worker_id, date(2026, 2, 27)), 9 * 60);
+        assert_eq!(minutes_on(&conn, worker_id, date(2026, 3, 6)), 8 * 60);
+
+        // Saving again from an earlier date replaces the later version.
+        save_schedule(
+            &mut conn,
+            Some(id),
+            "Oficina",
+            &weekdays("08:00", "16:00"),
+            date(2026, 2, 2),
+            &ctx,
+        )
+        .unwrap();
+        let schedule = get_schedule(&conn, id).unwrap().unwrap();
+        assert_eq!(schedule.versions.len(), 2);
+        assert_eq!(minutes_on(&conn, worker_id, date(2026, 1, 30)), 9 * 60);
+        assert_eq!(minutes_on(&conn, worker_id, date(2026, 3, 6)), 8 * 60);
+        assert_eq!(
+            schedule.day(Weekday::Mon).unwrap().start,
+            parse_time("08:00").unwrap()
+        );
+    }
+
+    #[test]
+    fn edits_cannot_reach_into_a_closed_month() {
+        let (mut conn, worker_id, ctx) = test_db();
+        let tx = conn.transaction().unwrap();
+        crate::hour_bank::record_close(&tx, worker_id, "2026-02", 0, &ctx).unwrap();
+        tx.commit().unwrap();
+
+        let days = weekdays("09:00", "17:00");
+        let result = save_schedule(
+            &mut conn,
+            Some(1),
+            "Jornada general",
+            &days,
+            date(2026, 2, 15),
+            &ctx,
+        );
+        assert!(matches!(result, Err(DbError::Validation(_))));
+        assert_eq!(minutes_on(&conn, worker_id, date(2026, 2, 16)), 510);
+
+        save_schedule(
+            &mut conn,
+            Some(1),
+            "Jornada general",
+            &days,
+            date(2026, 3, 1),
+            &ctx,
+        )
+        .unwrap();
+        assert_eq!(minutes_on(&conn, worker_id, date(2026, 2, 16)), 510);
+        assert_eq!(minutes_on(&conn, worker_id, date(2026, 3, 2)), 480);
+    }
+
+    #[test]
+    fn bounded_assignment_resumes_the_earlier_one() {
+        let (mut conn, worker_id, ctx) = test_db();
+        let office = save_schedule(
+            &mut conn,
+            None,
+            "Oficina",
+            &weekdays("09:00", "18:00"),
+            date(2026, 1, 1),
+            &ctx,
+        )
+        .unwrap();
+        let short = save_schedule(
+            &mut conn,
+            None,
+            "Media jornada",
+            &weekdays("09:00", "13:00"),
+            date(2026, 1, 1),
+            &ctx,
+        )
+        .unwrap();
+        assign_schedule(&mut conn, worker_id, office, date(2026, 1, 1), None, &ctx).unwrap();
+        assign_schedule(
+            &mut conn,
+            worker_id,
+            short,
+            date(2026, 3, 2),
+            Some(date(2026, 3, 13)),
+            &ctx,
+        )
+        .unwrap();
+
+        assert_eq!(minutes_on(&conn, worker_id, date(2026, 2, 27)), 9 * 60);
+        assert_eq!(minutes_on(&conn, worker_id, date(2026, 3, 13)), 4 * 60);
+        assert_eq!(minutes_on(&conn, worker_id, date(2026, 3, 16)), 9 * 60);
+        let ranges: Vec<_> = worker_assignments(&conn, worker_id)
+            .unwrap()
+            .into_iter()
+            .map(|a| (a.schedule_id, a.effective_from, a.effective_to))
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (office, date(2026, 3, 14), None),
+                (short, date(2026, 3, 2), Some(date(2026, 3, 13))),
+                (office, date(2026, 1, 1), Some(date(2026, 3, 1))),
+            ]
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::audit::{self, AuditContext};
+use crate::db::Result;
+
+/// Reads one value from the `settings` table.
+pub fn get(conn: &Connection, key: &str) -> Result<Option<String>> {
+    Ok(conn
+        .query_row(
+            "SELECT value FROM settings WHERE key = ?",
+            rusqlite::params![key],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Stores one value, replacing any previous one, and records the change.
+/// Writing the value that is already stored is a no-op.
+pub fn set(conn: &mut Connection, key: &str, value: &str, ctx: &AuditContext) -> Result<()> {
+    set_many(conn, &[(key, value)], ctx)
+}
+
+/// Stores the values of one save together: either every key changes or,
+/// if one write fails, none does.
+pub fn set_many(conn: &mut Connection, values: &[(&str, &str)], ctx: &AuditContext) -> Result<()> {
+    let tx = conn.transaction()?;
+    for (key, value) in values {
+        write(&tx, key, value, ctx)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+fn write(conn: &Connection, key: &str, value: &str, ctx: &AuditContext) -> Result<()> {
+    let before = get(conn, key)?;
+    if before.as_deref() == Some(value) {
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?, ?)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        rusqlite::params![key, value],
+    )?;
+    audit::record(
+        conn,
+        ctx,
+        "settings",
+        None,
+        "set",
+        before.map(|v| serde_json::json!({ key: v })),
+        Some(serde_json::json!({ key: value })),
+    )?;
+    Ok(())
+}
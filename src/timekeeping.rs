@@ -0,0 +1,662 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use rusqlite::Connection;
+
+use crate::audit::AuditContext;
+use crate::breaks::{BreakMinutes, SessionBreaks};
+use crate::db::{DbError, Result, TimesheetEntry};
+use crate::schedules::{WorkerCalendar, load_worker_calendar};
+use crate::timezone;
+use crate::utils::local_day_bounds_utc;
+
+const MODE_KEY: &str = "rounding.mode";
+const INCREMENT_KEY: &str = "rounding.increment";
+const START_GRACE_KEY: &str = "rounding.start_grace";
+const END_GRACE_KEY: &str = "rounding.end_grace";
+const ATTRIBUTION_KEY: &str = "attribution.mode";
+
+/// Which way punches move to the rounding increment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundingMode {
+    Nearest,
+    Up,
+    Down,
+}
+
+impl RoundingMode {
+    /// Order of the options in the Settings picker.
+    pub const ALL: [RoundingMode; 3] =
+        [RoundingMode::Nearest, RoundingMode::Up, RoundingMode::Down];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RoundingMode::Nearest => "Al más cercano",
+            RoundingMode::Up => "Hacia arriba",
+            RoundingMode::Down => "Hacia abajo",
+        }
+    }
+
+    fn code(self) -> &'static str {
+        match self {
+            RoundingMode::Nearest => "nearest",
+            RoundingMode::Up => "up",
+            RoundingMode::Down => "down",
+        }
+    }
+
+    fn from_code(code: &str) -> Option<RoundingMode> {
+        RoundingMode::ALL.into_iter().find(|m| m.code() == code)
+    }
+
+    pub fn index(self) -> usize {
+        RoundingMode::ALL
+            .iter()
+            .position(|m| *m == self)
+            .unwrap_or(0)
+    }
+}
+
+/// Which day a session that crosses midnight counts on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Attribution {
+    /// The whole session counts on the day it started.
+    #[default]
+    ShiftStart,
+    /// The minutes before midnight count on one day and the rest on the
+    /// next.
+    SplitAtMidnight,
+}
+
+impl Attribution {
+    /// Order of the options in the Settings picker.
+    pub const ALL: [Attribution; 2] = [Attribution::ShiftStart, Attribution::SplitAtMidnight];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Attribution::ShiftStart => "Día de inicio del turno",
+            Attribution::SplitAtMidnight => "Dividir a medianoche",
+        }
+    }
+
+    fn code(self) -> &'static str {
+        match self {
+            Attribution::ShiftStart => "shift_start",
+            Attribution::SplitAtMidnight => "split",
+        }
+    }
+
+    fn from_code(code: &str) -> Option<Attribution> {
+        Attribution::ALL.into_iter().find(|a| a.code() == code)
+    }
+
+    pub fn index(self) -> usize {
+        Attribution::ALL
+            .iter()
+            .position(|a| *a == self)
+            .unwrap_or(0)
+    }
+}
+
+pub fn load_attribution(conn: &Connection) -> Result<Attribution> {
+    Ok(crate::settings::get(conn, ATTRIBUTION_KEY)?
+        .as_deref()
+        .and_then(Attribution::from_code)
+        .unwrap_or_default())
+}
+
+pub fn save_attribution(
+    conn: &mut Connection,
+    attribution: Attribution,
+    ctx: &AuditContext,
+) -> Result<()> {
+    crate::settings::set(conn, ATTRIBUTION_KEY, attribution.code(), ctx)
+}
+
+/// Increments, in minutes, offered for rounding.
+pub const INCREMENTS: [i64; 4] = [1, 5, 10, 15];
+
+/// How recorded punches turn into the times that are paid. The stored
+/// punches never change; reports and totals apply the policy on read.
+///
+/// A clock-in up to `start_grace_minutes` after the scheduled start counts
+/// as the scheduled start, and a clock-out up to `end_grace_minutes` before
+/// the scheduled end counts as the scheduled end. Other punches are rounded
+/// to `increment_minutes`. The default rounds down to the minute with no
+/// grace, which leaves punches as recorded: totals truncate the whole span
+/// to minutes, as they did before rounding existed, rather than flooring
+/// each punch, which could add a minute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RoundingPolicy {
+    pub mode: RoundingMode,
+    pub increment_minutes: i64,
+    pub start_grace_minutes: i64,
+    pub end_grace_minutes: i64,
+}
+
+impl Default for RoundingPolicy {
+    fn default() -> Self {
+        RoundingPolicy {
+            mode: RoundingMode::Down,
+            increment_minutes: 1,
+            start_grace_minutes: 0,
+            end_grace_minutes: 0,
+        }
+    }
+}
+
+impl RoundingPolicy {
+    pub fn round(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let increment = self.increment_minutes.max(1) * 60;
+        if self.mode == RoundingMode::Down && increment == 60 {
+            return at;
+        }
+        let seconds = at.timestamp();
+        let remainder = seconds.rem_euclid(increment);
+        let down = seconds - remainder;
+        let rounded = match self.mode {
+            RoundingMode::Down => down,
+            RoundingMode::Up if remainder == 0 && at.timestamp_subsec_nanos() == 0 => down,
+            RoundingMode::Up => down + increment,
+            RoundingMode::Nearest if remainder * 2 >= increment => down + increment,
+            RoundingMode::Nearest => down,
+        };
+        Utc.timestamp_opt(rounded, 0).single().unwrap_or(at)
+    }
+
+    fn clock_in(&self, at: DateTime<Utc>, scheduled_start: Option<DateTime<Utc>>) -> DateTime<Utc> {
+        if let Some(start) = scheduled_start
+            && at > start
+            && at - start <= Duration::minutes(self.start_grace_minutes)
+        {
+            return start;
+        }
+        self.round(at)
+    }
+
+    fn clock_out(&self, at: DateTime<Utc>, scheduled_end: Option<DateTime<Utc>>) -> DateTime<Utc> {
+        if let Some(end) = scheduled_end
+            && at < end
+            && end - at <= Duration::minutes(self.end_grace_minutes)
+        {
+            return end;
+        }
+        self.round(at)
+    }
+}
+
+pub fn load_policy(conn: &Connection) -> Result<RoundingPolicy> {
+    let defaults = RoundingPolicy::default();
+    let number = |key, default: i64| -> Result<i64> {
+        Ok(crate::settings::get(conn, key)?
+            .and_then(|value| value.trim().parse().ok())
+            .filter(|value: &i64| *value >= 0)
+            .unwrap_or(default))
+    };
+    Ok(RoundingPolicy {
+        mode: crate::settings::get(conn, MODE_KEY)?
+            .as_deref()
+            .and_then(RoundingMode::from_code)
+            .unwrap_or(defaults.mode),
+        increment_minutes: number(INCREMENT_KEY, defaults.increment_minutes)?,
+        start_grace_minutes: number(START_GRACE_KEY, defaults.start_grace_minutes)?,
+        end_grace_minutes: number(END_GRACE_KEY, defaults.end_grace_minutes)?,
+    })
+}
+
+pub fn save_policy(
+    conn: &mut Connection,
+    policy: RoundingPolicy,
+    ctx: &AuditContext,
+) -> Result<()> {
+    if !INCREMENTS.contains(&policy.increment_minutes) {
+        return Err(DbError::Validation(format!(
+            "incremento de redondeo no permitido: {} minutos",
+            policy.increment_minutes
+        )));
+    }
+    if policy.start_grace_minutes < 0 || policy.end_grace_minutes < 0 {
+        return Err(DbError::Validation(
+            "la tolerancia no puede ser negativa".to_string(),
+        ));
+    }
+    crate::settings::set_many(
+        conn,
+        &[
+            (MODE_KEY, policy.mode.code()),
+            (INCREMENT_KEY, &policy.increment_minutes.to_string()),
+            (START_GRACE_KEY, &policy.start_grace_minutes.to_string()),
+            (END_GRACE_KEY, &policy.end_grace_minutes.to_string()),
+        ],
+        ctx,
+    )
+}
+
+/// The part of a session attributed to one local day.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DaySegment {
+    pub date: NaiveDate,
+    /// Effective times of the part, within the day under
+    /// `Attribution::SplitAtMidnight`.
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Whether the part holds the session's clock-in or clock-out.
+    pub starts_session: bool,
+    pub ends_session: bool,
+    /// Paid minutes of the part; the parts of a session add up to
+    /// `Timekeeper::worked_minutes`.
+    pub minutes: i64,
+    pub breaks: BreakMinutes,
+}
+
+/// Turns one worker's raw sessions into paid time: the rounding policy,
+/// the worker's schedule for the grace windows, the breaks inside the
+/// sessions, and which day each part of a session counts on.
+pub struct Timekeeper {
+    pub policy: RoundingPolicy,
+    pub attribution: Attribution,
+    pub calendar: WorkerCalendar,
+    pub breaks: SessionBreaks,
+    /// End of sessions that are still open.
+    pub now: DateTime<Utc>,
+}
+
+impl Timekeeper {
+    pub fn load(
+        conn: &Connection,
+        worker_id: i64,
+        entries: &[TimesheetEntry],
+    ) -> Result<Timekeeper> {
+        Ok(Timekeeper {
+            policy: load_policy(conn)?,
+            attribution: load_attribution(conn)?,
+            calendar: load_worker_calendar(conn, worker_id)?,
+            breaks: SessionBreaks::load(conn, entries)?,
+            now: Utc::now(),
+        })
+    }
+
+    /// The clock-in and clock-out that are paid. An open session runs until
+    /// `now`, which is not rounded.
+    pub fn effective_times(&self, entry: &TimesheetEntry) -> (DateTime<Utc>, DateTime<Utc>) {
+        let bounds = self
+            .calendar
+            .shift_for(entry.clock_in)
+            .map(|(_, start, end)| (start, end));
+        let clock_in = self.policy.clock_in(entry.clock_in, bounds.map(|b| b.0));
+        let clock_out = match entry.clock_out {
+            Some(out) => self.policy.clock_out(out, bounds.map(|b| b.1)),
+            None => self.now,
+        };
+        (clock_in, clock_out.max(clock_in))
+    }
+
+    pub fn break_minutes(&self, entry: &TimesheetEntry) -> BreakMinutes {
+        self.breaks.minutes(entry, self.now)
+    }
+
+    /// Paid minutes of the session: the effective span minus unpaid breaks.
+    pub fn worked_minutes(&self, entry: &TimesheetEntry) -> i64 {
+        let (clock_in, clock_out) = self.effective_times(entry);
+        ((clock_out - clock_in).num_minutes() - self.break_minutes(entry).unpaid).max(0)
+    }
+
+    /// The session's paid minutes by local day, following the attribution
+    /// rule.
+    pub fn segments(&self, entry: &TimesheetEntry) -> Vec<DaySegment> {
+        let (clock_in, clock_out) = self.effective_times(entry);
+        let worked = self.worked_minutes(entry);
+        if self.attribution == Attribution::ShiftStart {
+            return vec![DaySegment {
+                date: entry
+                    .clock_in
+                    .with_timezone(&timezone::current())
+                    .date_naive(),
+                from: clock_in,
+                to: clock_out,
+                starts_session: true,
+                ends_session: true,
+                minutes: worked,
+                breaks: self.break_minutes(entry),
+            }];
+        }
+        let mut segments = Vec::new();
+        let mut date = clock_in.with_timezone(&timezone::current()).date_naive();
+        let mut assigned = 0;
+        loop {
+            let (day_start, day_end) = local_day_bounds_utc(date);
+            let from = clock_in.max(day_start);
+            let to = clock_out.min(day_end);
+            let ends_session = clock_out <= day_end;
+            let breaks = self.breaks.minutes_between(entry, from, to, self.now);
+            // The last part takes the remainder so rounding to whole minutes
+            // never changes the session total.
+            let minutes = if ends_session {
+                worked - assigned
+            } else {
+                ((to - from).num_minutes() - breaks.unpaid).max(0)
+            };
+            assigned += minutes;
+            segments.push(DaySegment {
+                date,
+                from,
+                to,
+                starts_session: segments.is_empty(),
+                ends_session,
+                minutes,
+                breaks,
+            });
+            match date.succ_opt() {
+                Some(next) if !ends_session => date = next,
+                _ => break,
+            }
+        }
+        segments
+    }
+
+    /// Paid minutes per local day over `entries`.
+    pub fn minutes_by_day(&self, entries: &[TimesheetEntry]) -> BTreeMap<NaiveDate, i64> {
+        let mut by_day = BTreeMap::new();
+        for entry in entries {
+            for segment in self.segments(entry) {
+                *by_day.entry(segment.date).or_insert(0) += segment.minutes;
+            }
+        }
+        by_day
+    }
+}
+
+/// Paid hours a worker is credited with on local days `from` through `to`,
+/// for the live totals.
+pub fn worked_hours_between(
+    conn: &Connection,
+    worker_id: i64,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<f64> {
+    let entries = crate::db::get_timesheet_entries_overlapping(conn, worker_id, from, to)?;
+    let timekeeper = Timekeeper::load(conn, worker_id, &entries)?;
+    let minutes: i64 = timekeeper
+        .minutes_by_day(&entries)
+        .range(from..=to)
+        .map(|(_, minutes)| minutes)
+        .sum();
+    Ok(minutes as f64 / 60.0)
+}
+
+/// How far a day's punches strayed from the schedule, beyond the grace
+/// windows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Punctuality {
+    /// Minutes between the scheduled start and the first clock-in.
+    pub late_minutes: i64,
+    /// Minutes between the last clock-out and the scheduled end.
+    pub early_leave_minutes: i64,
+}
+
+impl Timekeeper {
+    /// Compares the first clock-in and last clock-out of a day's sessions
+    /// with the scheduled shift the first clock-in belongs to. Punches
+    /// inside the grace windows are on time, and a day with a session still
+    /// open has no early leave yet.
+    pub fn punctuality(&self, day_entries: &[&TimesheetEntry]) -> Punctuality {
+        let Some(first_in) = day_entries.iter().map(|e| e.clock_in).min() else {
+            return Punctuality::default();
+        };
+        let Some((_, start, end)) = self.calendar.shift_for(first_in) else {
+            return Punctuality::default();
+        };
+        let late = (first_in - start).num_minutes();
+        let late_minutes = if late > self.policy.start_grace_minutes {
+            late
+        } else {
+            0
+        };
+        let early_leave_minutes = match day_entries
+            .iter()
+            .map(|e| e.clock_out)
+            .collect::<Option<Vec<_>>>()
+            .and_then(|outs| outs.into_iter().max())
+        {
+            Some(last_out) => {
+                let early = (end - last_out).num_minutes();
+                if early > self.policy.end_grace_minutes {
+                    early
+                } else {
+                    0
+                }
+            }
+            None => 0,
+        };
+        Punctuality {
+            late_minutes,
+            early_leave_minutes,
+        }
+    }
+}
+
+/// Late arrivals and early departures of a worker on each day from `from`
+/// through `to` that has sessions, keyed by the day the sessions' shift
+/// starts, so a night shift reached after midnight counts on the day
+/// before. Holidays and days on leave are skipped.
+pub fn punctuality_by_day(
+    conn: &Connection,
+    worker_id: i64,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<BTreeMap<NaiveDate, Punctuality>> {
+    let entries = crate::db::get_timesheet_entries_between(conn, worker_id, from, to)?;
+    let timekeeper = Timekeeper::load(conn, worker_id, &entries)?;
+    let holidays = crate::holidays::holidays_between(conn, from, to)?;
+    let absences = crate::absences::absences_by_day(conn, worker_id, from, to)?;
+    let mut by_day: BTreeMap<NaiveDate, Vec<&TimesheetEntry>> = BTreeMap::new();
+    for entry in &entries {
+        let date = timekeeper
+            .calendar
+            .shift_for(entry.clock_in)
+            .map_or(entry.clock_in, |(_, start, _)| start)
+            .with_timezone(&timezone::current())
+            .date_naive();
+        if !holidays.contains_key(&date) && !absences.contains_key(&date) {
+            by_day.entry(date).or_default().push(entry);
+        }
+    }
+    Ok(by_day
+        .into_iter()
+        .map(|(date, day_entries)| (date, timekeeper.punctuality(&day_entries)))
+        .collect())
+}
+
+/// Days late and days left early, with their minutes, over a period.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PunctualitySummary {
+    pub late_days: i64,
+    pub late_minutes: i64,
+    pub early_leave_days: i64,
+    pub early_leave_minutes: i64,
+}
+
+impl PunctualitySummary {
+    pub fn from_days<'a>(days: impl IntoIterator<Item = &'a Punctuality>) -> PunctualitySummary {
+        let mut summary = PunctualitySummary::default();
+        for day in days {
+            if day.late_minutes > 0 {
+                summary.late_days += 1;
+                summary.late_minutes += day.late_minutes;
+            }
+            if day.early_leave_minutes > 0 {
+                summary.early_leave_days += 1;
+                summary.early_leave_minutes += day.early_leave_minutes;
+            }
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn at(hms: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(&format!("2026-03-10T{}Z", hms))
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn policy(mode: RoundingMode, increment_minutes: i64) -> RoundingPolicy {
+        RoundingPolicy {
+            mode,
+            increment_minutes,
+            ..RoundingPolicy::default()
+        }
+    }
+
+    #[test]
+    fn rounding_at_increment_boundaries() {
+        let cases = [
+            (RoundingMode::Down, "09:00:00", "09:00:00"),
+            (RoundingMode::Down, "09:14:59", "09:00:00"),
+            (RoundingMode::Down, "09:15:00", "09:15:00"),
+            (RoundingMode::Up, "09:00:00", "09:00:00"),
+            (RoundingMode::Up, "09:00:01", "09:15:00"),
+            (RoundingMode::Up, "09:00:00.500", "09:15:00"),
+            (RoundingMode::Up, "09:15:00", "09:15:00"),
+            (RoundingMode::Nearest, "09:07:29", "09:00:00"),
+            (RoundingMode::Nearest, "09:07:30", "09:15:00"),
+            (RoundingMode::Nearest, "09:15:00", "09:15:00"),
+            (RoundingMode::Nearest, "23:52:30", "00:00:00"),
+        ];
+        for (mode, punch, expected) in cases {
+            let mut expected = at(expected);
+            if punch.starts_with("23:") {
+                expected += Duration::days(1);
+            }
+            assert_eq!(
+                policy(mode, 15).round(at(punch)),
+                expected,
+                "{:?} {}",
+                mode,
+                punch
+            );
+        }
+    }
+
+    #[test]
+    fn one_minute_increments() {
+        assert_eq!(
+            policy(RoundingMode::Up, 1).round(at("09:00:01")),
+            at("09:01:00")
+        );
+        assert_eq!(
+            policy(RoundingMode::Nearest, 1).round(at("09:00:29")),
+            at("09:00:00")
+        );
+        assert_eq!(
+            policy(RoundingMode::Nearest, 1).round(at("09:00:30")),
+            at("09:01:00")
+        );
+        // The default keeps the punch; the span is truncated instead.
+        assert_eq!(
+            RoundingPolicy::default().round(at("09:00:59")),
+            at("09:00:59")
+        );
+    }
+
+    #[test]
+    fn default_policy_truncates_the_span_like_before() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&mut conn, Path::new("backups")).unwrap();
+        let ctx = AuditContext::admin("test");
+        let worker_id = crate::db::add_worker(&mut conn, "Prueba", "1000", &ctx).unwrap();
+        let (clock_in, clock_out) = (at("12:00:50"), at("20:00:10"));
+        crate::db::toggle_clock(&mut conn, worker_id, None, clock_in, false, &ctx).unwrap();
+        let crate::db::ClockEvent::ClockedOut { entry_id, .. } =
+            crate::db::toggle_clock(&mut conn, worker_id, None, clock_out, false, &ctx).unwrap()
+        else {
+            panic!("expected a clock-out");
+        };
+        let entry = crate::db::get_entry(&conn, entry_id).unwrap().unwrap();
+        let timekeeper = Timekeeper::load(&conn, worker_id, std::slice::from_ref(&entry)).unwrap();
+
+        assert_eq!(timekeeper.policy, RoundingPolicy::default());
+        assert_eq!(
+            timekeeper.worked_minutes(&entry),
+            (clock_out - clock_in).num_minutes()
+        );
+        assert_eq!(timekeeper.worked_minutes(&entry), 479);
+    }
+
+    #[test]
+    fn night_shift_punches_after_midnight_belong_to_the_evening_shift() {
+        use crate::schedules::{ScheduleDay, save_schedule, set_default_schedule};
+        use crate::utils::local_time_on;
+
+        let _guard = crate::timezone::TEST_ZONE_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        timezone::set_current(timezone::DEFAULT_TIME_ZONE);
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&mut conn, Path::new("backups")).unwrap();
+        let ctx = AuditContext::admin("test");
+        let worker_id = crate::db::add_worker(&mut conn, "Prueba", "1000", &ctx).unwrap();
+        let time = |value| chrono::NaiveTime::parse_from_str(value, "%H:%M").unwrap();
+        let days: Vec<ScheduleDay> = (0..7)
+            .map(|day| ScheduleDay {
+                weekday: chrono::Weekday::try_from(day).unwrap(),
+                start: time("22:00"),
+                end: time("06:00"),
+                break_minutes: 0,
+            })
+            .collect();
+        let id = save_schedule(&mut conn, None, "Noche", &days, NaiveDate::MIN, &ctx).unwrap();
+        set_default_schedule(&mut conn, id, &ctx).unwrap();
+        let grace = RoundingPolicy {
+            start_grace_minutes: 5,
+            end_grace_minutes: 5,
+            ..RoundingPolicy::default()
+        };
+        save_policy(&mut conn, grace, &ctx).unwrap();
+
+        let day = |d| NaiveDate::from_ymd_opt(2026, 3, d).unwrap();
+        let local = |d, value| local_time_on(day(d), value).unwrap();
+        for (clock_in, clock_out) in [
+            // On time, leaving two minutes early.
+            (local(10, "22:03"), local(11, "05:58")),
+            // Arriving after midnight for the shift that began at 22:00.
+            (local(12, "00:10"), local(12, "06:00")),
+        ] {
+            crate::db::toggle_clock(&mut conn, worker_id, None, clock_in, false, &ctx).unwrap();
+            crate::db::toggle_clock(&mut conn, worker_id, None, clock_out, false, &ctx).unwrap();
+        }
+
+        let entries =
+            crate::db::get_timesheet_entries_between(&conn, worker_id, day(10), day(12)).unwrap();
+        assert_eq!(entries.len(), 2);
+        let timekeeper = Timekeeper::load(&conn, worker_id, &entries).unwrap();
+        assert_eq!(
+            timekeeper.effective_times(&entries[0]),
+            (local(10, "22:00"), local(11, "06:00"))
+        );
+        assert_eq!(
+            timekeeper.effective_times(&entries[1]),
+            (local(12, "00:10"), local(12, "06:00"))
+        );
+
+        let by_day = punctuality_by_day(&conn, worker_id, day(10), day(12)).unwrap();
+        assert_eq!(
+            by_day.into_iter().collect::<Vec<_>>(),
+            vec![
+                (day(10), Punctuality::default()),
+                (
+                    day(11),
+                    Punctuality {
+                        late_minutes: 130,
+                        early_leave_minutes: 0,
+                    }
+                ),
+            ]
+        );
+    }
+}
@@ -1,10 +1,12 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
+/// Starts the periodic timers. Slint stops a timer when it is dropped, so the
+/// caller must keep the returned handles alive for as long as the UI runs.
 pub fn setup_timers(
     conn: Rc<RefCell<rusqlite::Connection>>,
     ui_handle: slint::Weak<crate::ui::MainWindow>,
-) {
+) -> Vec<slint::Timer> {
     // Set up timer to refresh ongoing hours every 10 seconds
     let conn_clone_worker_timer = conn.clone();
     let ui_handle_worker_timer = ui_handle.clone();
@@ -19,4 +21,26 @@ pub fn setup_timers(
             );
         },
     );
+
+    // Close forgotten sessions according to the end-of-day policy
+    let conn_clone_auto_close = conn.clone();
+    let ui_handle_auto_close = ui_handle.clone();
+    let auto_close_timer = slint::Timer::default();
+    auto_close_timer.start(
+        slint::TimerMode::Repeated,
+        std::time::Duration::from_secs(60),
+        move || {
+            let result = crate::auto_close::run(&mut conn_clone_auto_close.borrow_mut());
+            match result {
+                Ok(0) => {}
+                Ok(_) => crate::worker_display::refresh_workers(
+                    &conn_clone_auto_close,
+                    &ui_handle_auto_close,
+                ),
+                Err(e) => crate::config::log_event(&format!("Error auto-closing sessions: {}", e)),
+            }
+        },
+    );
+
+    vec![worker_timer, auto_close_timer]
 }
@@ -0,0 +1,200 @@
+use std::sync::RwLock;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use rusqlite::Connection;
+
+use crate::audit::AuditContext;
+use crate::db::{DbError, Result};
+
+const TIME_ZONE_KEY: &str = "time_zone";
+
+/// Used until another zone is saved in Settings.
+pub const DEFAULT_TIME_ZONE: Tz = chrono_tz::America::Santiago;
+
+/// The zone that decides which local day a punch falls on. Loaded from the
+/// settings at startup and replaced when an admin saves another one.
+static TIME_ZONE: RwLock<Tz> = RwLock::new(DEFAULT_TIME_ZONE);
+
+/// Tests that depend on the process-wide zone run one at a time.
+#[cfg(test)]
+pub(crate) static TEST_ZONE_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+pub fn current() -> Tz {
+    *TIME_ZONE.read().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn set_current(tz: Tz) {
+    *TIME_ZONE.write().unwrap_or_else(|e| e.into_inner()) = tz;
+}
+
+/// `at` on the local wall clock.
+pub fn to_local(at: DateTime<Utc>) -> DateTime<Tz> {
+    at.with_timezone(&current())
+}
+
+/// Parses an IANA name such as "America/Santiago".
+pub fn parse(name: &str) -> Option<Tz> {
+    name.trim().parse().ok()
+}
+
+/// "Santiago (America/Santiago)" for the Time tab.
+pub fn display_name(tz: Tz) -> String {
+    let name = tz.name();
+    let city = name.rsplit('/').next().unwrap_or(name).replace('_', " ");
+    if city == name {
+        name.to_string()
+    } else {
+        format!("{} ({})", city, name)
+    }
+}
+
+pub fn load(conn: &Connection) -> Result<Tz> {
+    Ok(crate::settings::get(conn, TIME_ZONE_KEY)?
+        .and_then(|name| parse(&name))
+        .unwrap_or(DEFAULT_TIME_ZONE))
+}
+
+/// Makes the saved zone current. Call once the database is open.
+pub fn init(conn: &Connection) -> Result<Tz> {
+    let tz = load(conn)?;
+    set_current(tz);
+    Ok(tz)
+}
+
+pub fn save(conn: &mut Connection, name: &str, ctx: &AuditContext) -> Result<Tz> {
+    let tz = parse(name).ok_or_else(|| {
+        DbError::Validation(format!(
+            "zona horaria desconocida: {} (use un nombre IANA, p. ej. America/Santiago)",
+            name.trim()
+        ))
+    })?;
+    crate::settings::set(conn, TIME_ZONE_KEY, tz.name(), ctx)?;
+    set_current(tz);
+    Ok(tz)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    use super::*;
+    use crate::timekeeping::{Attribution, save_attribution};
+    use crate::utils::day_bounds_utc_in;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn day_hours(tz: Tz, day: NaiveDate) -> i64 {
+        let (start, end) = day_bounds_utc_in(tz, day);
+        (end - start).num_hours()
+    }
+
+    #[test]
+    fn day_bounds_follow_daylight_saving_changes() {
+        let santiago = chrono_tz::America::Santiago;
+        // Clocks go back at midnight: 23:00–24:00 happens twice on Apr 4.
+        assert_eq!(day_hours(santiago, date(2026, 4, 4)), 25);
+        // Clocks jump from 24:00 to 01:00, so Sep 6 starts at 01:00.
+        assert_eq!(day_hours(santiago, date(2026, 9, 6)), 23);
+        assert_eq!(
+            day_bounds_utc_in(santiago, date(2026, 9, 6)).0,
+            Utc.with_ymd_and_hms(2026, 9, 6, 4, 0, 0).unwrap()
+        );
+        assert_eq!(day_hours(santiago, date(2026, 6, 15)), 24);
+
+        let new_york = chrono_tz::America::New_York;
+        assert_eq!(day_hours(new_york, date(2026, 3, 8)), 23);
+        assert_eq!(day_hours(new_york, date(2026, 11, 1)), 25);
+        assert_eq!(day_hours(new_york, date(2026, 7, 1)), 24);
+    }
+
+    fn test_db() -> (Connection, i64, AuditContext) {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&mut conn, Path::new("backups")).unwrap();
+        let ctx = AuditContext::admin("test");
+        let worker_id = crate::db::add_worker(&mut conn, "Prueba", "1000", &ctx).unwrap();
+        (conn, worker_id, ctx)
+    }
+
+    fn punch(conn: &mut Connection, worker_id: i64, ctx: &AuditContext, at: DateTime<Utc>) {
+        crate::db::toggle_clock(conn, worker_id, None, at, false, ctx).unwrap();
+    }
+
+    fn daily_hours(conn: &Connection, worker_id: i64, day: &str) -> f64 {
+        crate::db::get_daily_hours(conn, worker_id, day).unwrap()
+    }
+
+    #[test]
+    fn day_totals_across_daylight_saving_changes() {
+        let _guard = TEST_ZONE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let (mut conn, worker_id, ctx) = test_db();
+        save(&mut conn, "America/Santiago", &ctx).unwrap();
+
+        // 22:00 on the 25-hour Apr 4 until 05:00 the next morning: eight
+        // real hours, three of them before the second midnight.
+        punch(
+            &mut conn,
+            worker_id,
+            &ctx,
+            Utc.with_ymd_and_hms(2026, 4, 5, 1, 0, 0).unwrap(),
+        );
+        punch(
+            &mut conn,
+            worker_id,
+            &ctx,
+            Utc.with_ymd_and_hms(2026, 4, 5, 9, 0, 0).unwrap(),
+        );
+        // 22:00 on Sep 5 until 05:00 on Sep 6, whose midnight is skipped:
+        // six real hours.
+        punch(
+            &mut conn,
+            worker_id,
+            &ctx,
+            Utc.with_ymd_and_hms(2026, 9, 6, 2, 0, 0).unwrap(),
+        );
+        punch(
+            &mut conn,
+            worker_id,
+            &ctx,
+            Utc.with_ymd_and_hms(2026, 9, 6, 8, 0, 0).unwrap(),
+        );
+
+        assert_eq!(daily_hours(&conn, worker_id, "2026-04-04"), 8.0);
+        assert_eq!(daily_hours(&conn, worker_id, "2026-04-05"), 0.0);
+        assert_eq!(daily_hours(&conn, worker_id, "2026-09-05"), 6.0);
+        assert_eq!(daily_hours(&conn, worker_id, "2026-09-06"), 0.0);
+
+        save_attribution(&mut conn, Attribution::SplitAtMidnight, &ctx).unwrap();
+        assert_eq!(daily_hours(&conn, worker_id, "2026-04-04"), 3.0);
+        assert_eq!(daily_hours(&conn, worker_id, "2026-04-05"), 5.0);
+        assert_eq!(daily_hours(&conn, worker_id, "2026-09-05"), 2.0);
+        assert_eq!(daily_hours(&conn, worker_id, "2026-09-06"), 4.0);
+        assert_eq!(
+            crate::db::get_monthly_hours(&conn, worker_id, "2026-04").unwrap(),
+            8.0
+        );
+
+        // 00:30 EDT to 03:30 EST on Nov 1: three hours on the wall clock,
+        // four worked.
+        save(&mut conn, "America/New_York", &ctx).unwrap();
+        punch(
+            &mut conn,
+            worker_id,
+            &ctx,
+            Utc.with_ymd_and_hms(2026, 11, 1, 4, 30, 0).unwrap(),
+        );
+        punch(
+            &mut conn,
+            worker_id,
+            &ctx,
+            Utc.with_ymd_and_hms(2026, 11, 1, 8, 30, 0).unwrap(),
+        );
+        assert_eq!(daily_hours(&conn, worker_id, "2026-11-01"), 4.0);
+
+        set_current(DEFAULT_TIME_ZONE);
+    }
+}
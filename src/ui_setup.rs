@@ -1,26 +1,163 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use crate::utils::{local_ip_address, santiago_today_naive};
+use crate::timezone;
+use crate::utils::{local_ip_address, local_today_naive};
 use crate::worker_display::refresh_workers;
-use chrono_tz::America::Santiago;
 
 pub fn initialize_ui_and_data(
     ui: &crate::ui::MainWindow,
     conn: &Rc<RefCell<rusqlite::Connection>>,
     ui_handle: &slint::Weak<crate::ui::MainWindow>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let today = santiago_today_naive();
+    let today = local_today_naive();
     ui.set_selected_date(today.format("%Y-%m-%d").to_string().into());
 
     // Initialize current time display
-    let santiago_time = chrono::Utc::now().with_timezone(&Santiago);
-    ui.set_current_time_display(santiago_time.format("%H:%M:%S").to_string().into());
+    let now = timezone::to_local(chrono::Utc::now());
+    ui.set_current_time_display(now.format("%H:%M:%S").to_string().into());
+    let time_zone = timezone::current();
+    ui.set_time_zone_label(timezone::display_name(time_zone).into());
+    ui.set_time_zone_name(time_zone.name().into());
     let ip_display = local_ip_address().unwrap_or_else(|| "No disponible".to_string());
     ui.set_current_ip_display(ip_display.into());
+    let data_dir = crate::config::data_dir();
+    ui.set_database_path_display(
+        format!(
+            "{} ({})",
+            data_dir.database_path().display(),
+            data_dir.source()
+        )
+        .into(),
+    );
+    ui.set_templates_directory(
+        crate::report_templates::templates_dir()
+            .display()
+            .to_string()
+            .into(),
+    );
+
+    let reason_labels: Vec<slint::SharedString> = crate::corrections::ReasonCode::ALL
+        .iter()
+        .map(|reason| reason.label().into())
+        .collect();
+    ui.set_correction_reason_labels(Rc::new(slint::VecModel::from(reason_labels)).into());
+
+    let absence_labels: Vec<slint::SharedString> = crate::absences::AbsenceKind::ALL
+        .iter()
+        .map(|kind| kind.label().into())
+        .collect();
+    let absence_paid: Vec<bool> = crate::absences::AbsenceKind::ALL
+        .iter()
+        .map(|kind| kind.paid_by_default())
+        .collect();
+    ui.set_absence_kind_labels(Rc::new(slint::VecModel::from(absence_labels)).into());
+    ui.set_absence_kind_paid(Rc::new(slint::VecModel::from(absence_paid)).into());
+
+    let policy_labels: Vec<slint::SharedString> = crate::auto_close::AutoClosePolicy::LABELS
+        .iter()
+        .map(|label| (*label).into())
+        .collect();
+    ui.set_auto_close_policy_labels(Rc::new(slint::VecModel::from(policy_labels)).into());
+    {
+        let conn_ref = conn.borrow();
+        match (
+            crate::auto_close::load_policy(&conn_ref),
+            crate::auto_close::load_cutoff(&conn_ref),
+            crate::auto_close::load_limits(&conn_ref),
+        ) {
+            (Ok(policy), Ok(cutoff), Ok(limits)) => {
+                ui.set_auto_close_policy_index(policy.index() as i32);
+                ui.set_auto_close_cutoff(cutoff.format("%H:%M").to_string().into());
+                ui.set_auto_close_max_shift(limits.max_shift_hours.to_string().into());
+                ui.set_auto_close_grace(limits.grace_minutes.to_string().into());
+            }
+            (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+                crate::config::log_event(&format!("Error loading auto-close policy: {}", e));
+            }
+        }
+    }
+
+    let rounding_labels: Vec<slint::SharedString> = crate::timekeeping::RoundingMode::ALL
+        .iter()
+        .map(|mode| mode.label().into())
+        .collect();
+    ui.set_rounding_mode_labels(Rc::new(slint::VecModel::from(rounding_labels)).into());
+    let increment_labels: Vec<slint::SharedString> = crate::timekeeping::INCREMENTS
+        .iter()
+        .map(|minutes| format!("{} min", minutes).into())
+        .collect();
+    ui.set_rounding_increment_labels(Rc::new(slint::VecModel::from(increment_labels)).into());
+    match crate::timekeeping::load_policy(&conn.borrow()) {
+        Ok(policy) => crate::worker_display::show_rounding_policy(ui, &policy),
+        Err(e) => crate::config::log_event(&format!("Error loading rounding policy: {}", e)),
+    }
+
+    let attribution_labels: Vec<slint::SharedString> = crate::timekeeping::Attribution::ALL
+        .iter()
+        .map(|attribution| attribution.label().into())
+        .collect();
+    ui.set_attribution_labels(Rc::new(slint::VecModel::from(attribution_labels)).into());
+    match crate::timekeeping::load_attribution(&conn.borrow()) {
+        Ok(attribution) => ui.set_attribution_index(attribution.index() as i32),
+        Err(e) => crate::config::log_event(&format!("Error loading attribution: {}", e)),
+    }
+
+    let report_period_labels: Vec<slint::SharedString> = crate::periods::PeriodKind::ALL
+        .iter()
+        .map(|kind| kind.label().into())
+        .collect();
+    ui.set_report_period_labels(Rc::new(slint::VecModel::from(report_period_labels)).into());
+    crate::worker_display::refresh_report_period(conn, ui_handle);
+
+    let separator_labels: Vec<slint::SharedString> = crate::payroll_export::Separator::ALL
+        .iter()
+        .map(|separator| separator.label().into())
+        .collect();
+    ui.set_export_separator_labels(Rc::new(slint::VecModel::from(separator_labels)).into());
+    let hours_labels: Vec<slint::SharedString> = crate::payroll_export::HoursFormat::ALL
+        .iter()
+        .map(|format| format.label().into())
+        .collect();
+    ui.set_export_hours_labels(Rc::new(slint::VecModel::from(hours_labels)).into());
+    let encoding_labels: Vec<slint::SharedString> = crate::payroll_export::Encoding::ALL
+        .iter()
+        .map(|encoding| encoding.label().into())
+        .collect();
+    ui.set_export_encoding_labels(Rc::new(slint::VecModel::from(encoding_labels)).into());
+    ui.set_export_field_codes(
+        crate::payroll_export::PayrollField::ALL
+            .map(|f| f.code())
+            .join(", ")
+            .into(),
+    );
+
+    match crate::breaks::load_settings(&conn.borrow()) {
+        Ok(settings) => {
+            ui.set_break_scan_window(
+                crate::breaks::format_scan_window(settings.scan_window).into(),
+            );
+            ui.set_break_paid(settings.paid);
+        }
+        Err(e) => crate::config::log_event(&format!("Error loading break settings: {}", e)),
+    }
+    match crate::overtime::load_rules(&conn.borrow()) {
+        Ok(rules) => crate::worker_display::show_overtime_rules(ui, &rules),
+        Err(e) => crate::config::log_event(&format!("Error loading overtime rules: {}", e)),
+    }
+
+    if let Err(e) = crate::auto_close::run(&mut conn.borrow_mut()) {
+        crate::config::log_event(&format!("Error auto-closing sessions: {}", e));
+    }
+    if let Err(e) = crate::db::scan_invalid_rows(&conn.borrow()) {
+        crate::config::log_event(&format!("Error scanning for invalid rows: {}", e));
+    }
 
     // Load initial data using refresh function
     refresh_workers(conn, ui_handle);
+    crate::worker_display::refresh_schedules(conn, ui_handle);
+    crate::worker_display::refresh_holidays(conn, ui_handle);
+    crate::worker_display::refresh_export_profiles(conn, ui_handle);
 
     Ok(())
 }
@@ -1,5 +1,5 @@
 use chrono::{DateTime, Duration, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Utc};
-use chrono_tz::America::Santiago;
+use chrono_tz::Tz;
 use std::net::UdpSocket;
 
 pub fn format_hours(decimal_hours: f64) -> String {
@@ -8,8 +8,22 @@ pub fn format_hours(decimal_hours: f64) -> String {
     format!("{:02}:{:02}", hours, minutes)
 }
 
-pub fn santiago_today_naive() -> NaiveDate {
-    Utc::now().with_timezone(&Santiago).date_naive()
+pub fn local_today_naive() -> NaiveDate {
+    crate::timezone::to_local(Utc::now()).date_naive()
+}
+
+/// Interprets an "HH:MM" (or "HH:MM:SS") wall-clock time on `date` in the
+/// configured time zone and returns the matching UTC instant.
+pub fn local_time_on(date: NaiveDate, time: &str) -> Option<DateTime<Utc>> {
+    let time = time.trim();
+    let time = chrono::NaiveTime::parse_from_str(time, "%H:%M:%S")
+        .or_else(|_| chrono::NaiveTime::parse_from_str(time, "%H:%M"))
+        .ok()?;
+    match crate::timezone::current().from_local_datetime(&NaiveDateTime::new(date, time)) {
+        LocalResult::Single(dt) => Some(dt.with_timezone(&Utc)),
+        LocalResult::Ambiguous(dt, _) => Some(dt.with_timezone(&Utc)),
+        LocalResult::None => None,
+    }
 }
 
 pub fn local_ip_address() -> Option<String> {
@@ -29,30 +43,38 @@ pub fn local_ip_address() -> Option<String> {
     None
 }
 
-pub fn santiago_day_bounds_utc(date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
-    let start_local = santiago_start_of_day(date);
+/// Start of the local day `date` and of the next one, in UTC. Days are 23
+/// or 25 hours long when daylight saving time starts or ends.
+pub fn local_day_bounds_utc(date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    day_bounds_utc_in(crate::timezone::current(), date)
+}
+
+pub fn day_bounds_utc_in(tz: Tz, date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start_local = start_of_day(tz, date);
     let next_day = date.succ_opt().unwrap_or_else(|| date + Duration::days(1));
-    let end_local = santiago_start_of_day(next_day);
+    let end_local = start_of_day(tz, next_day);
     (
         start_local.with_timezone(&Utc),
         end_local.with_timezone(&Utc),
     )
 }
 
-fn santiago_start_of_day(date: NaiveDate) -> DateTime<chrono_tz::Tz> {
+/// Local midnight, or the first instant of the day where midnight is
+/// skipped by a daylight saving change.
+fn start_of_day(tz: Tz, date: NaiveDate) -> DateTime<Tz> {
     let naive = NaiveDateTime::new(
         date,
         chrono::NaiveTime::from_hms_opt(0, 0, 0).expect("valid time"),
     );
-    match Santiago.from_local_datetime(&naive) {
+    match tz.from_local_datetime(&naive) {
         LocalResult::Single(dt) => dt,
         LocalResult::Ambiguous(dt, _) => dt,
         LocalResult::None => {
             let shifted = naive + Duration::hours(1);
-            match Santiago.from_local_datetime(&shifted) {
+            match tz.from_local_datetime(&shifted) {
                 LocalResult::Single(dt) => dt,
                 LocalResult::Ambiguous(dt, _) => dt,
-                LocalResult::None => Santiago.from_utc_datetime(&shifted),
+                LocalResult::None => tz.from_utc_datetime(&shifted),
             }
         }
     }
@@ -62,19 +84,27 @@ fn santiago_start_of_day(date: NaiveDate) -> DateTime<chrono_tz::Tz> {
 pub fn get_last_clock_out(
     conn: &rusqlite::Connection,
     worker_id: i64,
-) -> rusqlite::Result<Option<String>> {
+) -> crate::db::Result<Option<String>> {
     let mut stmt = conn.prepare(
-        "SELECT clock_out FROM timesheets WHERE worker_id = ? AND clock_out IS NOT NULL ORDER BY id DESC LIMIT 1"
+        "SELECT id, clock_out FROM timesheets WHERE worker_id = ? AND clock_out IS NOT NULL ORDER BY id DESC"
     )?;
     let mut rows = stmt.query([worker_id])?;
-    if let Some(row) = rows.next()? {
-        let time: String = row.get(0)?;
-        let dt = chrono::DateTime::parse_from_rfc3339(&time)
-            .expect("Invalid time")
-            .with_timezone(&chrono::Utc)
-            .with_timezone(&Santiago);
-        Ok(Some(dt.format("%H:%M:%S").to_string()))
-    } else {
-        Ok(None)
+    while let Some(row) = rows.next()? {
+        let id: i64 = row.get(0)?;
+        let time: String = row.get(1)?;
+        match chrono::DateTime::parse_from_rfc3339(&time) {
+            Ok(dt) => {
+                let dt = crate::timezone::to_local(dt.with_timezone(&chrono::Utc));
+                return Ok(Some(dt.format("%H:%M:%S").to_string()));
+            }
+            Err(_) => {
+                // Reported by db::scan_invalid_rows; fall back to the previous punch.
+                crate::config::log_event(&format!(
+                    "Skipping invalid clock_out in timesheets id {}: '{}'",
+                    id, time
+                ));
+            }
+        }
     }
+    Ok(None)
 }
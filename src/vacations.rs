@@ -0,0 +1,266 @@
+use chrono::{Datelike, Months, NaiveDate, Weekday};
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::absences::AbsenceKind;
+use crate::db::{Result, Worker};
+
+/// Working days of feriado legal earned per year of service.
+pub const ANNUAL_DAYS: f64 = 15.0;
+
+/// At most this many years with previous employers count towards the
+/// feriado progresivo.
+const MAX_PRIOR_YEARS: i64 = 10;
+
+/// Extra vacation days per year under the feriado progresivo: one day for
+/// every three years worked beyond the first ten, where the years beyond
+/// ten have to be with the current employer. Up to ten years with previous
+/// employers count towards the first ten.
+pub fn progressive_days(prior_service_years: i64, years_with_employer: i64) -> i64 {
+    let counted = prior_service_years.clamp(0, MAX_PRIOR_YEARS) + years_with_employer.max(0);
+    let beyond_ten = (counted - 10).min(years_with_employer);
+    (beyond_ten / 3).max(0)
+}
+
+/// Whether a vacation day taken on `date` uses up balance. Saturdays,
+/// Sundays and public holidays never do.
+fn is_vacation_working_day(date: NaiveDate, is_holiday: impl Fn(NaiveDate) -> bool) -> bool {
+    !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !is_holiday(date)
+}
+
+/// One line of a worker's vacation statement.
+#[derive(Clone, Debug, Serialize)]
+pub struct VacationMovement {
+    pub date: NaiveDate,
+    pub description: String,
+    /// Days earned (positive) or taken (negative).
+    pub days: f64,
+    /// Balance right after this movement.
+    pub balance: f64,
+}
+
+/// A worker's feriado legal as of a date.
+#[derive(Clone, Debug, Serialize)]
+pub struct VacationBalance {
+    pub worker_id: i64,
+    pub hire_date: NaiveDate,
+    pub as_of: NaiveDate,
+    /// Completed years with the current employer.
+    pub years_of_service: i64,
+    /// Days per year currently earned, including the progressive ones.
+    pub annual_days: f64,
+    pub progressive_days: i64,
+    pub accrued: f64,
+    pub taken: f64,
+    /// Vacation days already recorded after `as_of`.
+    pub scheduled: f64,
+    pub available: f64,
+    pub movements: Vec<VacationMovement>,
+}
+
+/// Builds the balance from the hire date and the recorded vacation
+/// absences. Each completed month of service earns a twelfth of the
+/// yearly days in force during that month. Returns `None` when the worker
+/// has no hire date.
+pub fn vacation_balance(
+    conn: &Connection,
+    worker: &Worker,
+    as_of: NaiveDate,
+) -> Result<Option<VacationBalance>> {
+    let Some(hire_date) = worker.hire_date else {
+        return Ok(None);
+    };
+    let mut movements = Vec::new();
+
+    let mut months = 1;
+    while let Some(date) = hire_date.checked_add_months(Months::new(months))
+        && date <= as_of
+    {
+        let years = i64::from((months - 1) / 12);
+        let yearly = ANNUAL_DAYS + progressive_days(worker.prior_service_years, years) as f64;
+        movements.push(VacationMovement {
+            date,
+            description: "Acumulación mensual".to_string(),
+            days: yearly / 12.0,
+            balance: 0.0,
+        });
+        months += 1;
+    }
+
+    let absences = crate::absences::worker_absences(conn, worker.id)?;
+    let vacations: Vec<_> = absences
+        .into_iter()
+        .filter(|absence| absence.kind == AbsenceKind::Vacaciones)
+        .collect();
+    let mut scheduled = 0.0;
+    if let (Some(first), Some(last)) = (
+        vacations.iter().map(|a| a.start_date).min(),
+        vacations.iter().map(|a| a.end_date).max(),
+    ) {
+        let holidays = crate::holidays::holidays_between(conn, first, last)?;
+        let is_holiday = |date: NaiveDate| holidays.contains_key(&date);
+        for absence in &vacations {
+            let mut taken = 0.0;
+            let mut ahead = 0.0;
+            let mut day = absence.start_date;
+            while day <= absence.end_date {
+                if is_vacation_working_day(day, is_holiday) {
+                    if day <= as_of {
+                        taken += 1.0;
+                    } else {
+                        ahead += 1.0;
+                    }
+                }
+                let Some(next) = day.succ_opt() else {
+                    break;
+                };
+                day = next;
+            }
+            scheduled += ahead;
+            if taken > 0.0 {
+                movements.push(VacationMovement {
+                    date: absence.start_date,
+                    description: format!(
+                        "Vacaciones del {} al {}",
+                        absence.start_date,
+                        absence.end_date.min(as_of)
+                    ),
+                    days: -taken,
+                    balance: 0.0,
+                });
+            }
+        }
+    }
+
+    movements.sort_by_key(|movement| movement.date);
+    let mut balance = 0.0;
+    let (mut accrued, mut taken) = (0.0, 0.0);
+    for movement in &mut movements {
+        balance += movement.days;
+        movement.balance = balance;
+        if movement.days >= 0.0 {
+            accrued += movement.days;
+        } else {
+            taken -= movement.days;
+        }
+    }
+
+    let years_of_service = i64::from((months - 1) / 12);
+    let progressive = progressive_days(worker.prior_service_years, years_of_service);
+    Ok(Some(VacationBalance {
+        worker_id: worker.id,
+        hire_date,
+        as_of,
+        years_of_service,
+        annual_days: ANNUAL_DAYS + progressive as f64,
+        progressive_days: progressive,
+        accrued,
+        taken,
+        scheduled,
+        available: balance,
+        movements,
+    }))
+}
+
+/// Formats a day count the way Chilean payroll documents do, e.g. "12,50".
+pub fn format_days(days: f64) -> String {
+    format!("{:.2}", days).replace('.', ",")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::absences::{Absence, add_absence};
+    use crate::audit::AuditContext;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn worker(conn: &mut Connection, hire_date: NaiveDate, prior_service_years: i64) -> Worker {
+        let ctx = AuditContext::admin("test");
+        let id = crate::db::add_worker(conn, "Prueba", "1000", &ctx).unwrap();
+        crate::db::set_worker_employment(conn, id, Some(hire_date), prior_service_years, &ctx)
+            .unwrap();
+        crate::db::get_worker(conn, id).unwrap().unwrap()
+    }
+
+    fn test_db() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::migrate(&mut conn, Path::new("backups")).unwrap();
+        conn
+    }
+
+    #[test]
+    fn progressive_days_start_after_ten_years() {
+        assert_eq!(progressive_days(0, 10), 0);
+        assert_eq!(progressive_days(0, 12), 0);
+        assert_eq!(progressive_days(0, 13), 1);
+        assert_eq!(progressive_days(0, 15), 1);
+        assert_eq!(progressive_days(0, 16), 2);
+        assert_eq!(progressive_days(0, 19), 3);
+    }
+
+    #[test]
+    fn progressive_days_count_prior_service_up_to_ten_years() {
+        // The years beyond ten must be with the current employer.
+        assert_eq!(progressive_days(10, 2), 0);
+        assert_eq!(progressive_days(10, 3), 1);
+        assert_eq!(progressive_days(5, 8), 1);
+        assert_eq!(progressive_days(8, 2), 0);
+        assert_eq!(progressive_days(25, 3), 1);
+        assert_eq!(progressive_days(25, 6), 2);
+    }
+
+    #[test]
+    fn balance_accrues_the_progressive_day_from_its_year() {
+        let mut conn = test_db();
+        let worker = worker(&mut conn, date(2023, 1, 1), 10);
+        let balance = vacation_balance(&conn, &worker, date(2026, 4, 1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(balance.years_of_service, 3);
+        assert_eq!(balance.progressive_days, 1);
+        assert_eq!(balance.annual_days, 16.0);
+        // 36 months at 15 days a year and three at 16.
+        assert!((balance.accrued - 49.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weekends_and_holidays_do_not_use_up_vacation() {
+        let mut conn = test_db();
+        let worker = worker(&mut conn, date(2025, 1, 15), 0);
+        // Monday Mar 30 to Friday Apr 10, 2026: ten weekdays, one of them
+        // Good Friday, plus a weekend whose Saturday is also a holiday.
+        add_absence(
+            &mut conn,
+            &Absence {
+                id: 0,
+                worker_id: worker.id,
+                kind: AbsenceKind::Vacaciones,
+                start_date: date(2026, 3, 30),
+                end_date: date(2026, 4, 10),
+                paid: true,
+                note: String::new(),
+            },
+            &AuditContext::admin("test"),
+        )
+        .unwrap();
+
+        let balance = vacation_balance(&conn, &worker, date(2026, 4, 15))
+            .unwrap()
+            .unwrap();
+        assert_eq!(balance.taken, 9.0);
+        assert!((balance.accrued - 18.75).abs() < 1e-9);
+        assert!((balance.available - 9.75).abs() < 1e-9);
+
+        // Halfway through, the rest of the request is still ahead.
+        let balance = vacation_balance(&conn, &worker, date(2026, 4, 3))
+            .unwrap()
+            .unwrap();
+        assert_eq!(balance.taken, 4.0);
+        assert_eq!(balance.scheduled, 5.0);
+    }
+}
@@ -1,12 +1,16 @@
-use chrono::Weekday;
-use chrono_tz::America::Santiago;
-use slint::SharedString;
+use chrono::{Datelike, Weekday};
+use slint::{Model, SharedString};
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use crate::hour_bank::format_balance;
+use crate::timezone;
 use crate::types::{DataWorker, TimesheetDisplay};
-use crate::ui::{ReportItem, WorkerInfo, WorkerWithTimes};
-use crate::utils::{format_hours, santiago_today_naive};
+use crate::ui::{
+    AbsenceItem, AssignmentItem, AuditItem, BadgeItem, CorrectionSession, HolidayItem,
+    HourBankItem, ReportItem, ScheduleDayItem, WorkerInfo, WorkerWithTimes,
+};
+use crate::utils::{format_hours, local_today_naive};
 
 pub fn refresh_workers(
     conn: &Rc<RefCell<rusqlite::Connection>>,
@@ -16,7 +20,8 @@ pub fn refresh_workers(
         let conn_ref = conn.borrow();
         match crate::db::get_workers(&conn_ref) {
             Ok(workers) => {
-                let today = santiago_today_naive().format("%Y-%m-%d").to_string();
+                let today_date = local_today_naive();
+                let today = today_date.format("%Y-%m-%d").to_string();
 
                 // Sort workers: in progress first (by last check-in desc), then not in progress (by last check-out desc)
                 let mut worker_sort_list: Vec<(
@@ -83,19 +88,27 @@ pub fn refresh_workers(
                                         .map(|(index, entry)| {
                                             let clock_in_time = entry
                                                 .clock_in
-                                                .with_timezone(&Santiago)
+                                                .with_timezone(&timezone::current())
                                                 .format("%H:%M:%S")
                                                 .to_string();
 
+                                            let on_break = entry.clock_out.is_none()
+                                                && crate::breaks::open_break(&conn_ref, entry.id)
+                                                    .is_ok_and(|b| b.is_some());
                                             let (clock_out_time, color) =
                                                 if let Some(out_time) = entry.clock_out {
                                                     (
                                                         out_time
-                                                            .with_timezone(&Santiago)
+                                                            .with_timezone(&timezone::current())
                                                             .format("%H:%M:%S")
                                                             .to_string(),
                                                         slint::Color::from_argb_u8(0, 0, 0, 0),
                                                     ) // Transparent for completed
+                                                } else if on_break {
+                                                    (
+                                                        "En colación".to_string(),
+                                                        slint::Color::from_rgb_u8(255, 215, 0),
+                                                    ) // Yellow while on a break
                                                 } else {
                                                     (
                                                         "En Progreso".to_string(),
@@ -165,7 +178,7 @@ pub fn refresh_workers(
                                         .map(|(index, entry)| {
                                             let clock_in_time = entry
                                                 .clock_in
-                                                .with_timezone(&Santiago)
+                                                .with_timezone(&timezone::current())
                                                 .format("%H:%M:%S")
                                                 .to_string();
 
@@ -173,7 +186,7 @@ pub fn refresh_workers(
                                                 if let Some(out_time) = entry.clock_out {
                                                     (
                                                         out_time
-                                                            .with_timezone(&Santiago)
+                                                            .with_timezone(&timezone::current())
                                                             .format("%H:%M:%S")
                                                             .to_string(),
                                                         slint::Color::from_argb_u8(0, 0, 0, 0),
@@ -233,6 +246,7 @@ pub fn refresh_workers(
                 let in_progress_worker_items: Vec<WorkerWithTimes> = in_progress_workers_data
                     .into_iter()
                     .flat_map(|w| {
+                        let schedule = schedule_label(&conn_ref, w.worker.id, today_date);
                         w.times.into_iter().map(move |t| WorkerWithTimes {
                             name: SharedString::from(if t.show_name {
                                 w.worker.name.clone()
@@ -241,8 +255,11 @@ pub fn refresh_workers(
                             }),
                             checked_in_time: SharedString::from(t.checked_in_time),
                             checked_out_time: SharedString::from(t.checked_out_time),
+                            schedule: SharedString::from(schedule.clone()),
                             color: t.color,
-                            barcode: SharedString::from(w.worker.barcode.clone()),
+                            barcode: SharedString::from(
+                                w.worker.barcode.clone().unwrap_or_default(),
+                            ),
                             show_name: t.show_name,
                         })
                     })
@@ -252,6 +269,7 @@ pub fn refresh_workers(
                     not_in_progress_workers_data
                         .into_iter()
                         .flat_map(|w| {
+                            let schedule = schedule_label(&conn_ref, w.worker.id, today_date);
                             w.times.into_iter().map(move |t| WorkerWithTimes {
                                 name: SharedString::from(if t.show_name {
                                     w.worker.name.clone()
@@ -260,8 +278,11 @@ pub fn refresh_workers(
                                 }),
                                 checked_in_time: SharedString::from(t.checked_in_time),
                                 checked_out_time: SharedString::from(t.checked_out_time),
+                                schedule: SharedString::from(schedule.clone()),
                                 color: t.color,
-                                barcode: SharedString::from(w.worker.barcode.clone()),
+                                barcode: SharedString::from(
+                                    w.worker.barcode.clone().unwrap_or_default(),
+                                ),
                                 show_name: t.show_name,
                             })
                         })
@@ -282,52 +303,110 @@ pub fn refresh_workers(
                 ui.set_worker_names(Rc::new(slint::VecModel::from(names)).into());
 
                 // 🔧 NEW: also refresh management_workers for the Workers tab
-                let management_worker_items: Vec<WorkerInfo> = sorted_workers
+                let mut management_workers = sorted_workers.clone();
+                if ui.get_show_inactive_workers() {
+                    let inactive = crate::db::get_all_workers(&conn_ref)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter(|w| !w.active);
+                    management_workers.extend(inactive);
+                }
+                let management_worker_items: Vec<WorkerInfo> = management_workers
                     .iter()
                     .map(|w| WorkerInfo {
+                        id: w.id as i32,
                         name: SharedString::from(w.name.clone()),
-                        barcode: SharedString::from(w.barcode.clone()),
+                        barcode: SharedString::from(w.barcode.clone().unwrap_or_default()),
+                        active: w.active,
+                        hire_date: w
+                            .hire_date
+                            .map(|d| d.to_string())
+                            .unwrap_or_default()
+                            .into(),
+                        prior_service_years: w.prior_service_years.to_string().into(),
+                        vacation: vacation_label(&conn_ref, w, today_date).into(),
+                        rut: w.rut.clone().unwrap_or_default().into(),
                     })
                     .collect();
                 ui.set_management_workers(
                     Rc::new(slint::VecModel::from(management_worker_items)).into(),
                 );
 
+                // Correction picker keeps a stable, name-sorted order so the
+                // selected index survives the periodic refresh.
+                let mut correction_workers: Vec<&crate::db::Worker> =
+                    sorted_workers.iter().collect();
+                correction_workers.sort_by(|a, b| a.name.cmp(&b.name));
+                let correction_names: Vec<SharedString> = correction_workers
+                    .iter()
+                    .map(|w| SharedString::from(w.name.clone()))
+                    .collect();
+                let correction_ids: Vec<i32> =
+                    correction_workers.iter().map(|w| w.id as i32).collect();
+                ui.set_correction_worker_names(
+                    Rc::new(slint::VecModel::from(correction_names)).into(),
+                );
+                ui.set_correction_worker_ids(Rc::new(slint::VecModel::from(correction_ids)).into());
+
                 // Update reports
                 let mut report_items = Vec::new();
                 let selected_date_str = ui.get_selected_date().to_string();
                 let selected_naive =
                     chrono::NaiveDate::parse_from_str(&selected_date_str, "%Y-%m-%d")
-                        .unwrap_or(santiago_today_naive());
+                        .unwrap_or(local_today_naive());
                 let today = selected_naive.format("%Y-%m-%d").to_string();
                 let month = selected_naive.format("%Y-%m").to_string();
                 // Week start (Monday), end (Sunday)
                 let week = selected_naive.week(Weekday::Mon);
                 let week_start = week.first_day();
                 let week_end = week.last_day();
-                let week_start_str = week_start.format("%Y-%m-%d").to_string();
-                let week_end_str = week_end.format("%Y-%m-%d").to_string();
+                let month_start = selected_naive.with_day(1).unwrap_or(selected_naive);
 
                 for worker in &sorted_workers {
                     let daily =
                         crate::db::get_daily_hours(&conn_ref, worker.id, &today).unwrap_or(0.0);
-                    let weekly = crate::db::get_weekly_hours(
+                    let weekly =
+                        crate::db::get_weekly_hours(&conn_ref, worker.id, week_start, week_end)
+                            .unwrap_or(0.0);
+                    let monthly =
+                        crate::db::get_monthly_hours(&conn_ref, worker.id, &month).unwrap_or(0.0);
+                    let punctuality = crate::timekeeping::punctuality_by_day(
                         &conn_ref,
                         worker.id,
-                        &week_start_str,
-                        &week_end_str,
+                        month_start,
+                        selected_naive,
                     )
-                    .unwrap_or(0.0);
-                    let monthly =
-                        crate::db::get_monthly_hours(&conn_ref, worker.id, &month).unwrap_or(0.0);
+                    .map(|days| crate::timekeeping::PunctualitySummary::from_days(days.values()))
+                    .unwrap_or_default();
                     report_items.push(ReportItem {
                         name: SharedString::from(worker.name.clone()),
                         daily_hours: SharedString::from(format_hours(daily)),
                         weekly_hours: SharedString::from(format_hours(weekly)),
                         monthly_hours: SharedString::from(format_hours(monthly)),
+                        late_count: SharedString::from(format_day_count(
+                            punctuality.late_days,
+                            punctuality.late_minutes,
+                        )),
+                        early_leave_count: SharedString::from(format_day_count(
+                            punctuality.early_leave_days,
+                            punctuality.early_leave_minutes,
+                        )),
                     });
                 }
                 ui.set_reports(Rc::new(slint::VecModel::from(report_items)).into());
+
+                let invalid_rows: Vec<SharedString> = crate::db::invalid_rows_seen()
+                    .iter()
+                    .map(|row| SharedString::from(row.to_string()))
+                    .collect();
+                ui.set_invalid_rows(Rc::new(slint::VecModel::from(invalid_rows)).into());
+
+                let audit_items: Vec<AuditItem> = crate::audit::recent_entries(&conn_ref, 100)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(audit_item)
+                    .collect();
+                ui.set_audit_entries(Rc::new(slint::VecModel::from(audit_items)).into());
             }
             Err(e) => {
                 ui.set_error_dialog_message(
@@ -339,3 +418,613 @@ pub fn refresh_workers(
         }
     }
 }
+
+/// Reloads the sessions of the worker picked in the correction panel for the
+/// selected date. Kept out of `refresh_workers` so the periodic refresh does
+/// not wipe times the operator is still typing.
+pub fn refresh_correction_sessions(
+    conn: &Rc<RefCell<rusqlite::Connection>>,
+    ui_handle: &slint::Weak<crate::ui::MainWindow>,
+) {
+    let Some(ui) = ui_handle.upgrade() else {
+        return;
+    };
+    let Some(worker_id) = selected_correction_worker(&ui) else {
+        ui.set_correction_sessions(Rc::new(slint::VecModel::<CorrectionSession>::default()).into());
+        return;
+    };
+    let date = ui.get_selected_date().to_string();
+    match crate::db::get_daily_timesheet_entries(&conn.borrow(), worker_id, &date) {
+        Ok(entries) => {
+            let sessions: Vec<CorrectionSession> = entries
+                .iter()
+                .map(|entry| {
+                    let clock_out = entry
+                        .clock_out
+                        .map(|out| {
+                            out.with_timezone(&timezone::current())
+                                .format("%H:%M:%S")
+                                .to_string()
+                        })
+                        .unwrap_or_default();
+                    let mut detail = match entry.clock_out {
+                        Some(out) => format_hours(
+                            out.signed_duration_since(entry.clock_in).num_seconds() as f64 / 3600.0,
+                        ),
+                        None => "En Progreso".to_string(),
+                    };
+                    if let Some(reason) = entry
+                        .correction_reason
+                        .as_deref()
+                        .and_then(crate::corrections::ReasonCode::from_code)
+                    {
+                        detail = format!("{} · {}", detail, reason.label());
+                    }
+                    if let Some(mark) = entry
+                        .auto_close
+                        .as_deref()
+                        .and_then(crate::auto_close::AutoCloseMark::from_code)
+                    {
+                        detail = format!("{} · {}", detail, mark.label());
+                    }
+                    CorrectionSession {
+                        id: entry.id as i32,
+                        clock_in: SharedString::from(
+                            entry
+                                .clock_in
+                                .with_timezone(&timezone::current())
+                                .format("%H:%M:%S")
+                                .to_string(),
+                        ),
+                        clock_out: SharedString::from(clock_out),
+                        detail: SharedString::from(detail),
+                    }
+                })
+                .collect();
+            ui.set_correction_sessions(Rc::new(slint::VecModel::from(sessions)).into());
+        }
+        Err(e) => {
+            ui.set_error_dialog_message(format!("Error al cargar marcas: {}", e).into());
+            ui.set_show_error_dialog(true);
+            ui.set_trigger_error_dialog_show(true);
+        }
+    }
+}
+
+/// Fills the banco de horas panel for the month of the selected date.
+pub fn refresh_hour_bank(
+    conn: &Rc<RefCell<rusqlite::Connection>>,
+    ui_handle: &slint::Weak<crate::ui::MainWindow>,
+) {
+    let Some(ui) = ui_handle.upgrade() else {
+        return;
+    };
+    let selected = chrono::NaiveDate::parse_from_str(&ui.get_selected_date(), "%Y-%m-%d")
+        .unwrap_or(local_today_naive());
+    match crate::reports::hour_bank_balances(&conn.borrow(), selected) {
+        Ok(balances) => {
+            let items: Vec<HourBankItem> = balances
+                .into_iter()
+                .map(|(worker, balance)| HourBankItem {
+                    name: SharedString::from(worker.name),
+                    opening: SharedString::from(format_balance(balance.opening)),
+                    delta: SharedString::from(format_balance(balance.delta)),
+                    adjustments: SharedString::from(format_balance(balance.adjustments)),
+                    closing: SharedString::from(format_balance(balance.closing)),
+                    status: SharedString::from(if balance.closed {
+                        "Cerrado"
+                    } else {
+                        "Provisional"
+                    }),
+                })
+                .collect();
+            ui.set_hour_bank_items(Rc::new(slint::VecModel::from(items)).into());
+        }
+        Err(e) => {
+            ui.set_error_dialog_message(format!("Error al cargar banco de horas: {}", e).into());
+            ui.set_show_error_dialog(true);
+            ui.set_trigger_error_dialog_show(true);
+        }
+    }
+}
+
+/// Sets the Reports tab range to the saved period around the selected
+/// date, ending at the selected date when that comes first so a period
+/// still in progress is reported up to that day.
+pub fn refresh_report_period(
+    conn: &Rc<RefCell<rusqlite::Connection>>,
+    ui_handle: &slint::Weak<crate::ui::MainWindow>,
+) {
+    let Some(ui) = ui_handle.upgrade() else {
+        return;
+    };
+    let selected = chrono::NaiveDate::parse_from_str(&ui.get_selected_date(), "%Y-%m-%d")
+        .unwrap_or(local_today_naive());
+    let definition = match crate::periods::load_definition(&conn.borrow()) {
+        Ok(definition) => definition,
+        Err(e) => {
+            crate::config::log_event(&format!("Error loading report period: {}", e));
+            crate::periods::PeriodDefinition::default()
+        }
+    };
+    let range = definition.range_containing(selected);
+    ui.set_report_period_index(definition.kind.index() as i32);
+    ui.set_report_closing_day(definition.closing_day.to_string().into());
+    ui.set_report_start_date(range.start.format("%Y-%m-%d").to_string().into());
+    ui.set_report_end_date(
+        range
+            .end
+            .min(selected)
+            .format("%Y-%m-%d")
+            .to_string()
+            .into(),
+    );
+}
+
+/// Today's shift for the Time tab, e.g. "09:00–17:30", or "Libre" on a
+/// day off, or "Feriado" on a public holiday, or the kind of leave the
+/// worker is on.
+fn schedule_label(conn: &rusqlite::Connection, worker_id: i64, date: chrono::NaiveDate) -> String {
+    if let Ok(Some(_)) = crate::holidays::get_holiday(conn, date) {
+        return "Feriado".to_string();
+    }
+    if let Ok(absences) = crate::absences::absences_by_day(conn, worker_id, date, date)
+        && let Some(absence) = absences.get(&date)
+    {
+        return absence.kind.label().to_string();
+    }
+    let calendar = match crate::schedules::load_worker_calendar(conn, worker_id) {
+        Ok(calendar) => calendar,
+        Err(_) => return String::new(),
+    };
+    match calendar.day_on(date) {
+        Some(day) => format!("{}–{}", day.start.format("%H:%M"), day.end.format("%H:%M")),
+        None => "Libre".to_string(),
+    }
+}
+
+/// Available feriado legal days for the Workers list, e.g.
+/// "Vacaciones: 12,50 días", or nothing without a hire date.
+fn vacation_label(
+    conn: &rusqlite::Connection,
+    worker: &crate::db::Worker,
+    date: chrono::NaiveDate,
+) -> String {
+    match crate::vacations::vacation_balance(conn, worker, date) {
+        Ok(Some(balance)) => format!(
+            "Vacaciones: {} días",
+            crate::vacations::format_days(balance.available)
+        ),
+        _ => String::new(),
+    }
+}
+
+/// Fills the feriado legal summary under the worker being edited.
+pub fn refresh_worker_vacation(
+    conn: &Rc<RefCell<rusqlite::Connection>>,
+    ui_handle: &slint::Weak<crate::ui::MainWindow>,
+    worker_id: i64,
+) {
+    use crate::vacations::format_days;
+
+    let Some(ui) = ui_handle.upgrade() else {
+        return;
+    };
+    let conn = conn.borrow();
+    let result = crate::db::get_worker(&conn, worker_id).and_then(|worker| match worker {
+        Some(worker) => crate::vacations::vacation_balance(&conn, &worker, local_today_naive()),
+        None => Ok(None),
+    });
+    match result {
+        Ok(Some(balance)) => {
+            let mut summary = format!(
+                "{} años de servicio, {} días por año (progresivos: {}). Acumulados {}, tomados {}, disponibles {}.",
+                balance.years_of_service,
+                format_days(balance.annual_days),
+                balance.progressive_days,
+                format_days(balance.accrued),
+                format_days(balance.taken),
+                format_days(balance.available)
+            );
+            if balance.scheduled > 0.0 {
+                summary.push_str(&format!(
+                    " Programados: {}.",
+                    format_days(balance.scheduled)
+                ));
+            }
+            ui.set_vacation_summary(summary.into());
+        }
+        Ok(None) => ui.set_vacation_summary(
+            "Ingrese la fecha de ingreso para calcular el feriado legal.".into(),
+        ),
+        Err(e) => {
+            ui.set_error_dialog_message(format!("Error al calcular vacaciones: {}", e).into());
+            ui.set_show_error_dialog(true);
+            ui.set_trigger_error_dialog_show(true);
+        }
+    }
+}
+
+/// Loads the badge history shown under the worker being edited.
+pub fn refresh_worker_badges(
+    conn: &Rc<RefCell<rusqlite::Connection>>,
+    ui_handle: &slint::Weak<crate::ui::MainWindow>,
+    worker_id: i64,
+) {
+    let Some(ui) = ui_handle.upgrade() else {
+        return;
+    };
+    match crate::badges::get_worker_badges(&conn.borrow(), worker_id) {
+        Ok(badges) => {
+            let items: Vec<BadgeItem> = badges
+                .into_iter()
+                .map(|badge| BadgeItem {
+                    id: badge.id as i32,
+                    active: badge.is_active(),
+                    barcode: SharedString::from(badge.barcode),
+                    valid_from: SharedString::from(local_timestamp(&badge.valid_from)),
+                    valid_to: SharedString::from(
+                        badge
+                            .valid_to
+                            .as_deref()
+                            .map(local_timestamp)
+                            .unwrap_or_else(|| "vigente".to_string()),
+                    ),
+                    note: SharedString::from(badge.revoked_reason.unwrap_or_default()),
+                })
+                .collect();
+            ui.set_worker_badges(Rc::new(slint::VecModel::from(items)).into());
+        }
+        Err(e) => {
+            ui.set_error_dialog_message(format!("Error al cargar tarjetas: {}", e).into());
+            ui.set_show_error_dialog(true);
+            ui.set_trigger_error_dialog_show(true);
+        }
+    }
+}
+
+const WEEKDAY_LABELS: [&str; 7] = [
+    "Lunes",
+    "Martes",
+    "Miércoles",
+    "Jueves",
+    "Viernes",
+    "Sábado",
+    "Domingo",
+];
+
+/// Reloads the template picker in Settings and the editor below it. When no
+/// template is selected yet, the default one is shown.
+pub fn refresh_schedules(
+    conn: &Rc<RefCell<rusqlite::Connection>>,
+    ui_handle: &slint::Weak<crate::ui::MainWindow>,
+) {
+    let Some(ui) = ui_handle.upgrade() else {
+        return;
+    };
+    let schedules = match crate::schedules::list_schedules(&conn.borrow()) {
+        Ok(schedules) => schedules,
+        Err(e) => {
+            ui.set_error_dialog_message(format!("Error al cargar horarios: {}", e).into());
+            ui.set_show_error_dialog(true);
+            ui.set_trigger_error_dialog_show(true);
+            return;
+        }
+    };
+    let names: Vec<SharedString> = schedules.iter().map(|s| s.name.as_str().into()).collect();
+    let ids: Vec<i32> = schedules.iter().map(|s| s.id as i32).collect();
+    let index = usize::try_from(ui.get_schedule_index())
+        .ok()
+        .filter(|index| *index < schedules.len())
+        .or_else(|| schedules.iter().position(|s| s.is_default));
+    ui.set_schedule_names(Rc::new(slint::VecModel::from(names)).into());
+    ui.set_schedule_ids(Rc::new(slint::VecModel::from(ids)).into());
+    ui.set_schedule_index(index.map(|i| i as i32).unwrap_or(-1));
+    show_schedule(&ui, index.and_then(|i| schedules.get(i)));
+}
+
+/// Fills the template editor with the latest version of `schedule`, or
+/// with an empty week for a new template. Changes take effect from today
+/// unless another date is entered.
+pub fn show_schedule(ui: &crate::ui::MainWindow, schedule: Option<&crate::schedules::Schedule>) {
+    let days: Vec<ScheduleDayItem> = WEEKDAY_LABELS
+        .iter()
+        .enumerate()
+        .map(|(i, label)| {
+            let day = Weekday::try_from(i as u8)
+                .ok()
+                .and_then(|weekday| schedule.and_then(|s| s.day(weekday)));
+            ScheduleDayItem {
+                label: (*label).into(),
+                start: day
+                    .map(|d| d.start.format("%H:%M").to_string())
+                    .unwrap_or_default()
+                    .into(),
+                end: day
+                    .map(|d| d.end.format("%H:%M").to_string())
+                    .unwrap_or_default()
+                    .into(),
+                break_minutes: day
+                    .map(|d| d.break_minutes.to_string())
+                    .unwrap_or_default()
+                    .into(),
+            }
+        })
+        .collect();
+    ui.set_schedule_name(schedule.map(|s| s.name.as_str()).unwrap_or("").into());
+    ui.set_schedule_is_default(schedule.is_some_and(|s| s.is_default));
+    ui.set_schedule_days(Rc::new(slint::VecModel::from(days)).into());
+    ui.set_schedule_valid_from(
+        crate::utils::local_today_naive()
+            .format("%Y-%m-%d")
+            .to_string()
+            .into(),
+    );
+}
+
+pub fn show_rounding_policy(
+    ui: &crate::ui::MainWindow,
+    policy: &crate::timekeeping::RoundingPolicy,
+) {
+    ui.set_rounding_mode_index(policy.mode.index() as i32);
+    ui.set_rounding_increment_index(
+        crate::timekeeping::INCREMENTS
+            .iter()
+            .position(|i| *i == policy.increment_minutes)
+            .unwrap_or(0) as i32,
+    );
+    ui.set_rounding_start_grace(policy.start_grace_minutes.to_string().into());
+    ui.set_rounding_end_grace(policy.end_grace_minutes.to_string().into());
+}
+
+pub fn show_overtime_rules(ui: &crate::ui::MainWindow, rules: &crate::overtime::OvertimeRules) {
+    ui.set_overtime_weekly_limits(
+        crate::overtime::format_weekly_limits(&rules.weekly_limits).into(),
+    );
+    ui.set_overtime_daily_cap(
+        format!(
+            "{:02}:{:02}",
+            rules.daily_cap_minutes / 60,
+            rules.daily_cap_minutes % 60
+        )
+        .into(),
+    );
+}
+
+/// Template id behind the Settings picker, if one is chosen.
+pub fn selected_schedule(ui: &crate::ui::MainWindow) -> Option<i64> {
+    let index = usize::try_from(ui.get_schedule_index()).ok()?;
+    ui.get_schedule_ids().row_data(index).map(|id| id as i64)
+}
+
+/// Reloads the payroll export profiles into the Settings editor and the
+/// Reports picker, keeping the selected ones when they still exist.
+pub fn refresh_export_profiles(
+    conn: &Rc<RefCell<rusqlite::Connection>>,
+    ui_handle: &slint::Weak<crate::ui::MainWindow>,
+) {
+    let Some(ui) = ui_handle.upgrade() else {
+        return;
+    };
+    let profiles = match crate::payroll_export::list_profiles(&conn.borrow()) {
+        Ok(profiles) => profiles,
+        Err(e) => {
+            ui.set_error_dialog_message(
+                format!("Error al cargar perfiles de exportación: {}", e).into(),
+            );
+            ui.set_show_error_dialog(true);
+            ui.set_trigger_error_dialog_show(true);
+            return;
+        }
+    };
+    let names: Vec<SharedString> = profiles.iter().map(|p| p.name.as_str().into()).collect();
+    let ids: Vec<i32> = profiles.iter().map(|p| p.id as i32).collect();
+    let index = usize::try_from(ui.get_export_profile_index())
+        .ok()
+        .filter(|index| *index < profiles.len())
+        .or((!profiles.is_empty()).then_some(0));
+    if ui.get_payroll_profile_index() as usize >= profiles.len() {
+        ui.set_payroll_profile_index(0);
+    }
+    ui.set_export_profile_names(Rc::new(slint::VecModel::from(names)).into());
+    ui.set_export_profile_ids(Rc::new(slint::VecModel::from(ids)).into());
+    ui.set_export_profile_index(index.map(|i| i as i32).unwrap_or(-1));
+    show_export_profile(&ui, index.and_then(|i| profiles.get(i)));
+}
+
+/// Fills the profile editor with `profile`, or with the defaults for a new
+/// one.
+pub fn show_export_profile(
+    ui: &crate::ui::MainWindow,
+    profile: Option<&crate::payroll_export::ExportProfile>,
+) {
+    let format = profile.map(|p| p.format).unwrap_or_default();
+    ui.set_export_profile_name(profile.map(|p| p.name.as_str()).unwrap_or("").into());
+    ui.set_export_profile_columns(
+        profile
+            .map(|p| crate::payroll_export::format_columns(&p.columns))
+            .unwrap_or_default()
+            .into(),
+    );
+    ui.set_export_separator_index(format.separator.index() as i32);
+    ui.set_export_hours_index(format.hours.index() as i32);
+    ui.set_export_encoding_index(format.encoding.index() as i32);
+    ui.set_export_header(format.header);
+}
+
+pub fn selected_export_profile(ui: &crate::ui::MainWindow, index: i32) -> Option<i64> {
+    let index = usize::try_from(index).ok()?;
+    ui.get_export_profile_ids()
+        .row_data(index)
+        .map(|id| id as i64)
+}
+
+/// Loads the schedule assignments shown under the worker being edited.
+pub fn refresh_worker_assignments(
+    conn: &Rc<RefCell<rusqlite::Connection>>,
+    ui_handle: &slint::Weak<crate::ui::MainWindow>,
+    worker_id: i64,
+) {
+    let Some(ui) = ui_handle.upgrade() else {
+        return;
+    };
+    let conn = conn.borrow();
+    let result = crate::schedules::worker_assignments(&conn, worker_id).and_then(|assignments| {
+        assignments
+            .into_iter()
+            .map(|assignment| {
+                let schedule = crate::schedules::get_schedule(&conn, assignment.schedule_id)?;
+                Ok(AssignmentItem {
+                    id: assignment.id as i32,
+                    schedule: schedule.map(|s| s.name).unwrap_or_default().into(),
+                    from: assignment.effective_from.to_string().into(),
+                    to: assignment
+                        .effective_to
+                        .map(|to| to.to_string())
+                        .unwrap_or_else(|| "indefinido".to_string())
+                        .into(),
+                })
+            })
+            .collect::<crate::db::Result<Vec<_>>>()
+    });
+    match result {
+        Ok(items) => ui.set_worker_assignments(Rc::new(slint::VecModel::from(items)).into()),
+        Err(e) => {
+            ui.set_error_dialog_message(
+                format!("Error al cargar horarios asignados: {}", e).into(),
+            );
+            ui.set_show_error_dialog(true);
+            ui.set_trigger_error_dialog_show(true);
+        }
+    }
+}
+
+/// Loads the absences shown under the worker being edited.
+pub fn refresh_worker_absences(
+    conn: &Rc<RefCell<rusqlite::Connection>>,
+    ui_handle: &slint::Weak<crate::ui::MainWindow>,
+    worker_id: i64,
+) {
+    let Some(ui) = ui_handle.upgrade() else {
+        return;
+    };
+    match crate::absences::worker_absences(&conn.borrow(), worker_id) {
+        Ok(absences) => {
+            let items: Vec<AbsenceItem> = absences
+                .into_iter()
+                .map(|absence| AbsenceItem {
+                    id: absence.id as i32,
+                    kind_index: absence.kind.index() as i32,
+                    kind: absence.kind.label().into(),
+                    from: absence.start_date.to_string().into(),
+                    to: absence.end_date.to_string().into(),
+                    paid: absence.paid,
+                    note: absence.note.into(),
+                })
+                .collect();
+            ui.set_worker_absences(Rc::new(slint::VecModel::from(items)).into());
+        }
+        Err(e) => {
+            ui.set_error_dialog_message(format!("Error al cargar ausencias: {}", e).into());
+            ui.set_show_error_dialog(true);
+            ui.set_trigger_error_dialog_show(true);
+        }
+    }
+}
+
+/// Loads the holidays of the year shown in Settings, defaulting to the
+/// current year.
+pub fn refresh_holidays(
+    conn: &Rc<RefCell<rusqlite::Connection>>,
+    ui_handle: &slint::Weak<crate::ui::MainWindow>,
+) {
+    let Some(ui) = ui_handle.upgrade() else {
+        return;
+    };
+    if ui.get_holiday_year() <= 0 {
+        ui.set_holiday_year(local_today_naive().year());
+    }
+    let year = ui.get_holiday_year();
+    let (Some(from), Some(to)) = (
+        chrono::NaiveDate::from_ymd_opt(year, 1, 1),
+        chrono::NaiveDate::from_ymd_opt(year, 12, 31),
+    ) else {
+        return;
+    };
+    match crate::holidays::holidays_between(&conn.borrow(), from, to) {
+        Ok(holidays) => {
+            let items: Vec<HolidayItem> = holidays
+                .into_values()
+                .map(|holiday| HolidayItem {
+                    date: holiday.date.to_string().into(),
+                    name: holiday.name.into(),
+                    source: holiday.source.into(),
+                })
+                .collect();
+            ui.set_holiday_items(Rc::new(slint::VecModel::from(items)).into());
+        }
+        Err(e) => {
+            ui.set_error_dialog_message(format!("Error al cargar feriados: {}", e).into());
+            ui.set_show_error_dialog(true);
+            ui.set_trigger_error_dialog_show(true);
+        }
+    }
+}
+
+/// Formats a stored RFC 3339 timestamp in local time, or returns it
+/// unchanged if it cannot be parsed.
+fn local_timestamp(value: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| {
+            dt.with_timezone(&timezone::current())
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|_| value.to_string())
+}
+
+/// Worker id behind the correction panel's worker picker, if one is chosen.
+pub fn selected_correction_worker(ui: &crate::ui::MainWindow) -> Option<i64> {
+    let index = usize::try_from(ui.get_correction_worker_index()).ok()?;
+    ui.get_correction_worker_ids()
+        .row_data(index)
+        .map(|id| id as i64)
+}
+
+fn audit_item(entry: crate::audit::AuditEntry) -> AuditItem {
+    let at = chrono::DateTime::parse_from_rfc3339(&entry.at)
+        .map(|dt| {
+            dt.with_timezone(&timezone::current())
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or(entry.at);
+    let action = match entry.entity_id {
+        Some(id) => format!("{} #{} {}", entry.entity, id, entry.action),
+        None => format!("{} {}", entry.entity, entry.action),
+    };
+    let mut detail = format!(
+        "{} → {}",
+        entry.before.as_deref().unwrap_or("∅"),
+        entry.after.as_deref().unwrap_or("∅")
+    );
+    if let Some(reason) = entry.reason {
+        detail = format!("[{}] {}", reason, detail);
+    }
+    AuditItem {
+        at: SharedString::from(at),
+        actor: SharedString::from(entry.actor),
+        source: SharedString::from(entry.source),
+        action: SharedString::from(action),
+        detail: SharedString::from(detail),
+    }
+}
+
+/// "3 (25 min)" for the Reports tab; "0" when nothing happened.
+fn format_day_count(days: i64, minutes: i64) -> String {
+    if days == 0 {
+        "0".to_string()
+    } else {
+        format!("{} ({} min)", days, minutes)
+    }
+}